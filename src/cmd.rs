@@ -2,44 +2,306 @@
 //! cmd-related programs
 
 use {
+    std:: {
+        env,
+        fs,
+        mem,
+        time,
+    },
     crate:: {
+        completion,
+        diagnostic,
         display,
+        explorer,
         file,
+        files,
+        finder,
+        grep,
+        key,
+        keymap,
+        line,
+        lsp,
+        motion,
+        quickfix,
+        register,
+        search,
+        shell,
+        substitute,
+        syntax,
+        visual,
+        window,
     }
 };
 
-const CMD_APPEND:                    &str = "a";
-const CMD_BACKSPACE_LEFT:            &str = "b";
-const CMD_BACKSPACE_RIGHT:           &str = "B";
-const CMD_CENTER_DOWN:               &str = "j";
-const CMD_CENTER_END_FILE:           &str = "J";
-const CMD_CENTER_END_LINE:           &str = "K";
-const CMD_CENTER_LEFT:               &str = "h";
-const CMD_CENTER_RIGHT:              &str = "k";
-const CMD_CENTER_START_FILE:         &str = "I";
-const CMD_CENTER_START_LINE:         &str = "H";
-const CMD_CENTER_UP:                 &str = "i";
-const CMD_NEWLINE:                   &str = "n";
-const CMD_QUIT:                      &str = "q";
-const CMD_REPLACE:                   &str = "r";
-const CMD_THEME_CHANGE_TO_ONE_DARK:  &str = "t:one_dark";
-const CMD_THEME_CHANGE_TO_ONE_LIGHT: &str = "t:one_light";
+const CMD_ALL:                       &str = "all";
+const CMD_APPEND:                    &str = "append";
+const CMD_BACKSPACE:                 &str = "backspace";
+const CMD_BACKSPACE_LEFT:            &str = "backspace_left";
+const CMD_BACKSPACE_RIGHT:           &str = "backspace_right";
+const CMD_BUFFER_NEXT:               &str = "buffer_next";
+const CMD_BUFFER_PREV:               &str = "buffer_prev";
+const CMD_CANCEL:                    &str = "cancel";
+const CMD_CENTER_DOWN:               &str = "center_down";
+const CMD_CENTER_END_FILE:           &str = "center_end_file";
+const CMD_CENTER_END_LINE:           &str = "center_end_line";
+const CMD_CENTER_FIRST_NON_BLANK:    &str = "center_first_non_blank";
+const CMD_CENTER_LEFT:               &str = "center_left";
+const CMD_CENTER_LINE:               &str = "center_line";
+const CMD_CENTER_MATCHING_BRACKET:   &str = "center_matching_bracket";
+const CMD_CENTER_PAGE_DOWN:          &str = "center_page_down";
+const CMD_CENTER_PAGE_UP:            &str = "center_page_up";
+const CMD_CENTER_PARAGRAPH_NEXT:     &str = "center_paragraph_next";
+const CMD_CENTER_PARAGRAPH_PREV:     &str = "center_paragraph_prev";
+const CMD_CENTER_RIGHT:              &str = "center_right";
+const CMD_CENTER_START_FILE:         &str = "center_start_file";
+const CMD_CENTER_START_LINE:         &str = "center_start_line";
+const CMD_CENTER_UP:                 &str = "center_up";
+const CMD_CENTER_WORD_BACKWARD:      &str = "center_word_backward";
+const CMD_CENTER_WORD_BACKWARD_BIG:  &str = "center_word_backward_big";
+const CMD_CENTER_WORD_END:           &str = "center_word_end";
+const CMD_CENTER_WORD_END_BIG:       &str = "center_word_end_big";
+const CMD_CENTER_WORD_FORWARD:       &str = "center_word_forward";
+const CMD_CENTER_WORD_FORWARD_BIG:   &str = "center_word_forward_big";
+const CMD_CHANGE:                    &str = "change";
+const CMD_COMPLETE:                  &str = "complete";
+const CMD_COMPLETION_ACCEPT:         &str = "completion_accept";
+const CMD_COMPLETION_NEXT:           &str = "completion_next";
+const CMD_COMPLETION_PREV:           &str = "completion_prev";
+const CMD_DELETE:                    &str = "delete";
+const CMD_DELETE_ROW:                &str = "delete_row";
+const CMD_DIAGNOSTIC_NEXT:           &str = "diagnostic_next";
+const CMD_DIAGNOSTIC_PREV:           &str = "diagnostic_prev";
+const CMD_DOWN:                      &str = "down";
+const CMD_END:                       &str = "end";
+const CMD_EXECUTE:                   &str = "execute";
+const CMD_EXPLORER_OPEN:             &str = "explorer_open";
+const CMD_EXPLORER_PARENT:           &str = "explorer_parent";
+const CMD_EXPLORER_TREE:             &str = "explorer_tree";
+const CMD_FINDER:                    &str = "finder";
+const CMD_FINISH:                    &str = "finish";
+const CMD_HOME:                      &str = "home";
+const CMD_INDENT:                    &str = "indent";
+const CMD_LEFT:                      &str = "left";
+const CMD_LINE:                      &str = "line";
+const CMD_LOCATION_OPEN:             &str = "location_open";
+const CMD_LSP_DEFINITION:            &str = "lsp_definition";
+const CMD_LSP_HOVER:                 &str = "lsp_hover";
+const CMD_LSP_REFERENCES:            &str = "lsp_references";
+const CMD_LOWERCASE:                 &str = "lowercase";
+const CMD_NEWLINE:                   &str = "newline";
+const CMD_NO:                        &str = "no";
+const CMD_OPTION_EXPAND_TAB:         &str = "option_expand_tab";
+const CMD_OPTION_HARD_TAB:           &str = "option_hard_tab";
+const CMD_OPTION_ROW_NUM:            &str = "option_row_num";
+const CMD_OPTION_TAB_WIDTH:          &str = "option_tab_width";
+const CMD_OPTION_TAB_WIDTH_2:        &str = "option_tab_width_2";
+const CMD_OPTION_TAB_WIDTH_4:        &str = "option_tab_width_4";
+const CMD_OPTION_TAB_WIDTH_8:        &str = "option_tab_width_8";
+const CMD_OPTION_VIRTUAL_TEXT:       &str = "option_virtual_text";
+const CMD_OPTION_WHITESPACE:         &str = "option_whitespace";
+const CMD_OPTION_WRAP:               &str = "option_wrap";
+const CMD_OPTION_WRAP_MOVE:          &str = "option_wrap_move";
+const CMD_OUTDENT:                   &str = "outdent";
+const CMD_PASTE:                     &str = "paste";
+const CMD_PASTE_BEFORE:              &str = "paste_before";
+const CMD_QUIT:                      &str = "quit";
+const CMD_QUICKFIX_NEXT:             &str = "quickfix_next";
+const CMD_QUICKFIX_PREV:             &str = "quickfix_prev";
+const CMD_REDO:                      &str = "redo";
+const CMD_REGISTER:                  &str = "register";
+const CMD_REPEAT:                    &str = "repeat";
+const CMD_REPLACE:                   &str = "replace";
+const CMD_RESET:                     &str = "reset";
+const CMD_RETAB_SPACE_TO_TAB:        &str = "retab_space_to_tab";
+const CMD_RETAB_TAB_TO_SPACE:        &str = "retab_tab_to_space";
+const CMD_RIGHT:                     &str = "right";
+const CMD_SEARCH_BACKWARD:           &str = "search_backward";
+const CMD_SEARCH_FORWARD:            &str = "search_forward";
+const CMD_SEARCH_NEXT:               &str = "search_next";
+const CMD_SEARCH_PREV:               &str = "search_prev";
+const CMD_SYNTAX_CHILD:              &str = "syntax_child";
+const CMD_SYNTAX_NEXT_SIBLING:       &str = "syntax_next_sibling";
+const CMD_SYNTAX_PARENT:             &str = "syntax_parent";
+const CMD_TAB_NEW:                   &str = "tab_new";
+const CMD_TAB_NEXT:                  &str = "tab_next";
+const CMD_TAB_PREV:                  &str = "tab_prev";
+const CMD_THEME_CHANGE_TO_ONE_DARK:  &str = "theme_change_to_one_dark";
+const CMD_THEME_CHANGE_TO_ONE_LIGHT: &str = "theme_change_to_one_light";
+const CMD_UNDO:                      &str = "undo";
+const CMD_UP:                        &str = "up";
+const CMD_UPPERCASE:                 &str = "uppercase";
+const CMD_VISUAL_BLOCK:              &str = "visual_block";
+const CMD_VISUAL_CHAR:               &str = "visual_char";
+const CMD_VISUAL_LINE:               &str = "visual_line";
+const CMD_WINDOW_CLOSE:              &str = "window_close";
+const CMD_WINDOW_DOWN:               &str = "window_down";
+const CMD_WINDOW_LEFT:               &str = "window_left";
+const CMD_WINDOW_NARROWER:           &str = "window_narrower";
+const CMD_WINDOW_NEXT:               &str = "window_next";
+const CMD_WINDOW_RIGHT:              &str = "window_right";
+const CMD_WINDOW_SHORTER:            &str = "window_shorter";
+const CMD_WINDOW_SPLIT:              &str = "window_split";
+const CMD_WINDOW_TALLER:             &str = "window_taller";
+const CMD_WINDOW_UP:                 &str = "window_up";
+const CMD_WINDOW_VSPLIT:             &str = "window_vsplit";
+const CMD_WINDOW_WIDER:              &str = "window_wider";
+const CMD_YANK:                      &str = "yank";
+const CMD_YANK_ROW:                  &str = "yank_row";
+const CMD_YES:                       &str = "yes";
+
+/// # CONTENT
+/// count prefix limit (larger counts are clamped)
+const MAX_COUNT: usize = 1_000_000;
+
+/// # CONTENT
+/// repeat limit of a cmd replayed by its count prefix
+const MAX_REPEAT: usize = 10_000;
+
+const PROMPT_FINDER:                 &str = ">";
+const PROMPT_LINE:                   &str = ":";
+const PROMPT_SEARCH_BACKWARD:        &str = "?";
+const PROMPT_SEARCH_FORWARD:         &str = "/";
 
 #[derive(Clone)]
 enum Mode {
     Append,
+    Confirm,
     Default,
+    Finder,
+    Line,
     Replace,
+    Search,
+    Visual,
+}
+
+impl Mode {
+    /// # CONTENT
+    /// get mode name used by the keymap
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// mode name
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Append => "append",
+            Self::Confirm => "confirm",
+            Self::Default => "default",
+            Self::Finder => "finder",
+            Self::Line => "line",
+            Self::Replace => "replace",
+            Self::Search => "search",
+            Self::Visual => "visual",
+        }
+    }
+}
+
+/// # CONTENT
+/// editing cmd replayed by dot-repeat
+/// - Keys: entered keys
+/// - Line: executed cmd line (without ':')
+#[derive(Clone)]
+enum Edit {
+    Keys(Vec<key::Key>),
+    Line(String),
+}
+
+/// # CONTENT
+/// get selection kind of a visual cmd
+/// # ARGUMENT
+/// - cmd: CMD_VISUAL_BLOCK, CMD_VISUAL_CHAR or CMD_VISUAL_LINE
+/// # RETURN VALUE
+/// selection kind
+fn visual_kind(cmd: &str) -> visual::Kind {
+    match cmd {
+        CMD_VISUAL_BLOCK => visual::Kind::Block,
+        CMD_VISUAL_LINE => visual::Kind::Line,
+        _ => visual::Kind::Char,
+    }
 }
 
 /// # CONTENT
 /// cmd info
 /// # FIELD
 /// - cmd: cmd history
+/// - expand_tab: insert spaces instead of "\t" on tab key
+/// - count: count prefix of the running cmd
+/// - keymap: key bindings of each mode
+/// - pending: entered keys of an incomplete key sequence
+/// - done: keys of the last completed cmd (with count prefix)
+/// - recording: the running editing cmd
+/// - last_edit: the last editing cmd (replayed by dot-repeat)
+/// - repeating: true while replaying keys of a count or dot-repeat
+/// - search: searched pattern (empty if no search)
+/// - search_forward: search direction of the last search cmd
+/// - search_shown: matches of Cmd::search are highlighted (cleared by ":nohlsearch" until the next search cmd)
+/// - search_origin: center pos and pattern before the running search cmd
+/// - prompt_tail: char count after the cursor of the cmd line or search prompt (0 if the cursor is at the end)
+/// - replaced: char under center before a replace cmd (restored on cancel, None at the row end)
+/// - substitute: running confirm-each substitute cmd, its current match and replaced count
+/// - completion: cmd line completion candidates and the shown one
+/// - finder: running fuzzy finder (the query is read in the cmd line)
+/// - popup: completion popup of the word typed in append mode
+/// - popup_refresh: the typed word changed (the popup is rebuilt by Cmd::popup_update)
+/// - buffers: paths of the open buffers (completed by ":buffer", set by Cmd::buffers_update)
+/// - make_program: build program run by ":make"
+/// - quickfix: diagnostics parsed from the last ":make" output
+/// - lint_program: linter run by ":lint"
+/// - job: running ":make" or ":lint" program (its output is parsed by Cmd::job_poll)
+/// - grep: running ":grep" search (its matches are listed by Cmd::job_poll)
+/// - lsp_servers: configured (language id, shell cmd) of language servers
+/// - visual: selection kind and the pos where the selection started
+/// - visual_keys: keys entered since visual mode started (recorded for dot-repeat by a visual operator)
+/// - registers: yanked and deleted text
+/// - register: register selected for the next cmd
+/// - selecting_register: the next key is a register name
+/// - request: buffer cmd run by Files after the key
+/// - window_request: window cmd run by Windows after the key
+/// - lsp_request: language server cmd run by Lsp after the key
+/// - lsp_restart: languages whose server is restarted by Lsp after the key (their server cmd changed)
+/// - diagnostics: (source, (path, diagnostic)) of build or linter output attached by Files after the key
+/// - message: message shown in the cmd line while Cmd::buffer is empty
 pub struct Cmd {
     history: String,
     pub buffer: String,
     mode: Mode,
+    expand_tab: bool,
+    count: usize,
+    keymap: keymap::Keymap,
+    pending: Vec<key::Key>,
+    timeout: time::Duration,
+    done: Option<Vec<key::Key>>,
+    recording: Option<Edit>,
+    last_edit: Edit,
+    repeating: bool,
+    pub search: String,
+    search_forward: bool,
+    search_shown: bool,
+    search_origin: (usize, usize, String),
+    prompt_tail: usize,
+    replaced: Option<char>,
+    substitute: Option<(substitute::Substitute, (usize, usize, usize), usize)>,
+    completion: Option<(Vec<String>, usize)>,
+    pub finder: Option<finder::Finder>,
+    pub popup: Option<completion::Popup>,
+    popup_refresh: bool,
+    buffers: Vec<String>,
+    make_program: String,
+    quickfix: Option<quickfix::Quickfix>,
+    lint_program: String,
+    job: Option<quickfix::Job>,
+    grep: Option<grep::Job>,
+    pub lsp_servers: Vec<(String, String)>,
+    visual: (visual::Kind, (usize, usize)),
+    visual_keys: Vec<key::Key>,
+    registers: register::Registers,
+    register: Option<char>,
+    selecting_register: bool,
+    pub request: Option<files::Request>,
+    pub window_request: Option<window::Request>,
+    pub lsp_request: Option<lsp::Request>,
+    pub lsp_restart: Vec<String>,
+    pub diagnostics: Option<(&'static str, Vec<(String, diagnostic::Diagnostic)>)>,
+    pub message: String,
 }
 
 impl Cmd {
@@ -54,124 +316,1817 @@ impl Cmd {
             history: String::new(),
             buffer: String::new(),
             mode: Mode::Default,
+            expand_tab: false,
+            count: 1,
+            keymap: keymap::Keymap::new(),
+            pending: Vec::new(),
+            timeout: time::Duration::from_millis(1000),
+            done: None,
+            recording: None,
+            last_edit: Edit::Keys(Vec::new()),
+            repeating: false,
+            search: String::new(),
+            search_forward: true,
+            search_shown: true,
+            search_origin: (0, 0, String::new()),
+            prompt_tail: 0,
+            replaced: None,
+            substitute: None,
+            completion: None,
+            finder: None,
+            popup: None,
+            popup_refresh: false,
+            buffers: Vec::new(),
+            make_program: quickfix::DEFAULT_PROGRAM.to_string(),
+            quickfix: None,
+            lint_program: diagnostic::DEFAULT_LINT_PROGRAM.to_string(),
+            job: None,
+            grep: None,
+            lsp_servers: Vec::new(),
+            visual: (visual::Kind::Char, (0, 0)),
+            visual_keys: Vec::new(),
+            registers: register::Registers::new(),
+            register: None,
+            selecting_register: false,
+            request: None,
+            window_request: None,
+            lsp_request: None,
+            lsp_restart: Vec::new(),
+            diagnostics: None,
+            message: String::new(),
         }
     }
 
-    fn check(&mut self, cmd_msg: &str, key: char) -> bool {
-        let mut start = self.buffer.clone();
-        start.push(key);
-        if cmd_msg.to_string().starts_with(&start) {
-            self.buffer.push(key);
-            if cmd_msg.to_string() == start {
+    /// # CONTENT
+    /// get the count prefix entered in Cmd::buffer
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// count digits (after the '"' register prefix)
+    fn count_digits(&self) -> &str {
+        let digits = match self.register {
+            Some(_) => self.buffer.get(2..).unwrap_or_default(),
+            None => &self.buffer,
+        };
+        &digits[..digits.len() - digits.trim_start_matches(|c: char| c.is_ascii_digit()).len()]
+    }
+
+    /// # CONTENT
+    /// resolve a key in the keymap of the current mode
+    /// # ARGUMENT
+    /// - k: entered key (None to resolve the pending keys on timeout)
+    /// # RETURN VALUE
+    /// Resolved (Cmd::done, Cmd::count, Cmd::buffer and Cmd::message are updated in default mode)
+    fn resolve(&mut self, k: Option<key::Key>) -> keymap::Resolved {
+        let count = self.count_digits().to_string();
+        let mut done = key::Key::keys(&count);
+        done.extend(self.pending.iter().copied());
+        done.extend(k);
+        let resolved = match k {
+            Some(k) => self.keymap.resolve(self.mode.name(), &mut self.pending, k),
+            None => self.keymap.flush(self.mode.name(), &mut self.pending),
+        };
+        if !matches!(self.mode, Mode::Default | Mode::Visual) {
+            return resolved;
+        }
+        let notation = k.map(|k| k.notation()).unwrap_or_default();
+        match &resolved {
+            keymap::Resolved::Cmd(_) => {
+                self.count = if count.is_empty() { 1 } else { count.parse().unwrap_or(MAX_COUNT).min(MAX_COUNT) };
                 self.history += &self.buffer;
+                self.history += &notation;
+                self.done = Some(done);
                 self.buffer = String::new();
-                return true;
+            }
+            keymap::Resolved::Pending => {
+                self.buffer.push_str(&notation);
+                self.message = self.keymap.list(Some(self.mode.name()), &self.pending).join("\n");
+            }
+            keymap::Resolved::Unbound(keys) => {
+                self.message = format!("unknown cmd: {}", key::Key::notations(keys));
+                self.buffer = String::new();
+                self.register = None;
             }
         }
-        false
+        resolved
     }
 
     /// # CONTENT
-    /// execute cmd
+    /// get the time to wait for the next key of a pending key sequence
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// Some(duration): keys are pending (Cmd::flush on timeout)
+    /// None: no pending keys or no timeout
+    pub fn timeout(&self) -> Option<time::Duration> {
+        Some(self.timeout).filter(|t| !self.pending.is_empty() && !t.is_zero())
+    }
+
+    /// # CONTENT
+    /// start an editing cmd (save an undo step and record keys for dot-repeat)
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// none
+    fn edit(&mut self, display_handle: &display::Display, file_handle: &mut file::File) {
+        if self.repeating {
+            return;
+        }
+        file_handle.save_undo(display_handle.center_x, display_handle.center_y);
+        self.recording = self.done.clone().map(Edit::Keys);
+    }
+
+    /// # CONTENT
+    /// start an undo step and the recording of a visual operator (dot-repeat replays the selection keys)
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// none
+    fn visual_edit(&mut self, display_handle: &display::Display, file_handle: &mut file::File) {
+        if self.repeating {
+            return;
+        }
+        file_handle.save_undo(display_handle.center_x, display_handle.center_y);
+        self.recording = Some(Edit::Keys(mem::take(&mut self.visual_keys)));
+    }
+
+    /// # CONTENT
+    /// replay keys as if they were entered
     /// # ARGUMENT
-    /// - key: enterd key event
+    /// - keys: replayed keys
+    /// - repeating: true: part of the running cmd (no undo step, not recorded)
     /// # RETURN VALUE
     /// - true: continue program
     /// - false: quit program
-    pub fn key(&mut self, key: char, display_handle: &mut display::Display, file_handle: &mut file::File) -> bool {
-        match self.mode.clone() {
-            Mode::Append => {
-                self.buffer.push(key);
-                if key == '\n' {
-                    file_handle.content[display_handle.center_y].remove(display_handle.center_x);
-                    self.history += &self.buffer;
-                } else {
-                    file_handle.content[display_handle.center_y].insert(display_handle.center_x, key);
-                    display_handle.center_x += 1;
-                }
+    fn replay(&mut self, keys: &[key::Key], repeating: bool, display_handle: &mut display::Display, file_handle: &mut file::File) -> bool {
+        let prev_repeating = self.repeating;
+        self.repeating = repeating;
+        let mut ret = true;
+        for k in keys {
+            if !self.key(*k, display_handle, file_handle) {
+                ret = false;
+                break;
             }
-            Mode::Default => {
-                if key == '\n' { // reset buffer
-                    self.buffer = String::new();
-                } else if self.check(CMD_APPEND, key) {
-                    file_handle.content[display_handle.center_y].insert(display_handle.center_x, ' ');
-                    self.mode = Mode::Append;
-                } else if self.check(CMD_BACKSPACE_LEFT, key) {
-                    file_handle.content[display_handle.center_y].remove(display_handle.center_x);
-                    if display_handle.center_x > 0 {
-                        display_handle.center_x -= 1;
-                    }
-                } else if self.check(CMD_BACKSPACE_RIGHT, key) {
-                    file_handle.content[display_handle.center_y].remove(display_handle.center_x);
-                    if display_handle.center_x > file_handle.content[display_handle.center_y].len() {
-                        display_handle.center_x = file_handle.content[display_handle.center_y].len();
+        }
+        if ret && !self.pending.is_empty() {
+            ret = self.flush(display_handle, file_handle);
+        }
+        self.repeating = prev_repeating;
+        ret
+    }
+
+    /// # CONTENT
+    /// move center to the syntax node of motion (a message without the syntax feature)
+    /// # ARGUMENT
+    /// - motion: structural motion
+    /// # RETURN VALUE
+    /// none
+    fn syntax_motion(&mut self, motion: syntax::Motion, display_handle: &mut display::Display, file_handle: &mut file::File) {
+        if !syntax::ENABLED {
+            self.message = "syntax: feature disabled (build with --features syntax)".to_string();
+        } else if let Some((x, y)) = file_handle.syntax.motion(&file_handle.content, display_handle.center_x, display_handle.center_y, motion) {
+            display_handle.center_x = x;
+            display_handle.center_y = y;
+        }
+    }
+
+    /// # CONTENT
+    /// move center to a motion destination
+    /// # ARGUMENT
+    /// - pos: new (x, y)
+    /// # RETURN VALUE
+    /// none
+    fn move_center(&self, pos: (usize, usize), display_handle: &mut display::Display, file_handle: &file::File) {
+        display_handle.center_y = pos.1.min(file_handle.content.len() - 1);
+        display_handle.center_x = pos.0.min(file_handle.row_len(display_handle.center_y));
+    }
+
+    /// # CONTENT
+    /// start a search cmd (the pattern is read in the cmd line)
+    /// # ARGUMENT
+    /// - forward: true: search after center, false: before center
+    /// # RETURN VALUE
+    /// none
+    fn search_start(&mut self, forward: bool, display_handle: &display::Display) {
+        self.search_origin = (display_handle.center_x, display_handle.center_y, self.search.clone());
+        self.search_forward = forward;
+        self.search_shown = true;
+        self.search = String::new();
+        self.buffer = if forward { PROMPT_SEARCH_FORWARD } else { PROMPT_SEARCH_BACKWARD }.to_string();
+        self.prompt_tail = 0;
+        self.mode = Mode::Search;
+    }
+
+    /// # CONTENT
+    /// start a ":make" or ":lint" program (unless one is running)
+    /// # ARGUMENT
+    /// - source: diagnostic::SOURCE_MAKE or diagnostic::SOURCE_LINT (also the cmd name)
+    /// - program: shell cmd
+    /// # RETURN VALUE
+    /// none
+    fn job_start(&mut self, source: &'static str, program: &str) {
+        self.message = match &self.job {
+            Some(job) => format!("{}: \"{}\" is running", source, job.program),
+            None => {
+                self.job = Some(quickfix::Job::spawn(source, program));
+                format!("{}: running \"{}\"", source, program)
+            }
+        };
+    }
+
+    /// # CONTENT
+    /// check if a ":make" or ":lint" program or a ":grep" search is running
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// true: running
+    pub fn job_running(&self) -> bool {
+        self.job.is_some() || self.grep.is_some()
+    }
+
+    /// # CONTENT
+    /// list the matches of the ":grep" search, or parse the output of the ":make" or ":lint" program, if it ended
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// true: a job ended (Cmd::message, Cmd::request or Cmd::diagnostics are updated)
+    pub fn job_poll(&mut self) -> bool {
+        if let Some(result) = self.grep.as_ref().and_then(|grep| grep.poll()) {
+            let pattern = self.grep.take().map(|grep| grep.pattern).unwrap_or_default();
+            match result {
+                Ok(summary) => {
+                    let mut message = format!("grep: {} matches in {} files", summary.matches.len(), summary.searched);
+                    if summary.matches.len() == grep::MAX_MATCHES {
+                        message += " (match limit reached)";
                     }
-                } else if self.check(CMD_CENTER_DOWN, key) {
-                    if display_handle.center_y != file_handle.content.len() {
-                        display_handle.center_y += 1;
-                        if display_handle.center_x > file_handle.content[display_handle.center_y].len() {
-                            display_handle.center_x = file_handle.content[display_handle.center_y].len();
-                        }
+                    if summary.skipped > 0 {
+                        message += &format!(" ({} large files skipped)", summary.skipped);
                     }
-                } else if self.check(CMD_CENTER_END_FILE, key) {
-                    display_handle.center_y = file_handle.content.len() - 1;
-                    display_handle.center_x = file_handle.content[display_handle.center_y].len();
-                } else if self.check(CMD_CENTER_END_LINE, key) {
-                    display_handle.center_x = file_handle.content[display_handle.center_y].len();
-                } else if self.check(CMD_CENTER_LEFT, key) {
-                    if display_handle.center_x != 0 {
-                        display_handle.center_x -= 1;
+                    if summary.failed > 0 {
+                        message += &format!(" ({} files not searched: search thread panicked)", summary.failed);
                     }
-                } else if self.check(CMD_CENTER_RIGHT, key) {
-                    if display_handle.center_x < file_handle.content[display_handle.center_y].len() {
-                        display_handle.center_x += 1;
-                    }
-                } else if self.check(CMD_CENTER_START_FILE, key) {
-                    display_handle.center_x = 0;
-                    display_handle.center_y = 0;
-                } else if self.check(CMD_CENTER_START_LINE, key) {
-                    display_handle.center_x = 0;
-                } else if self.check(CMD_CENTER_UP, key) {
-                    if display_handle.center_y != 0 {
-                        display_handle.center_y -= 1;
-                        if display_handle.center_x >= file_handle.content[display_handle.center_y].len() {
-                            display_handle.center_x = file_handle.content[display_handle.center_y].len() - 1;
+                    self.request = Some(files::Request::Add(Box::new(file::File::locations(summary.matches, format!("[grep] {}", pattern))), message));
+                }
+                Err(e) => self.message = format!("grep: {}", e),
+            }
+            return true;
+        }
+        let Some(result) = self.job.as_ref().and_then(|job| job.poll()) else {
+            return false;
+        };
+        let Some(job) = self.job.take() else {
+            return false;
+        };
+        let output = match result {
+            Ok(output) => output,
+            Err(e) => {
+                self.message = format!("{}: {}", job.source, e);
+                return true;
+            }
+        };
+        let locations = quickfix::parse(&output);
+        self.diagnostics = Some((job.source, diagnostic::from_locations(&locations, job.source)));
+        if job.source == diagnostic::SOURCE_LINT {
+            self.message = format!("lint: {} diagnostics in the output of \"{}\"", locations.len(), job.program);
+        } else if locations.is_empty() {
+            self.message = format!("make: no diagnostics in the output of \"{}\"", job.program);
+        } else {
+            self.quickfix = Some(quickfix::Quickfix::new(locations));
+            self.quickfix_step(0);
+        }
+        true
+    }
+
+    /// # CONTENT
+    /// update the buffer paths completed in the cmd line
+    /// # ARGUMENT
+    /// - files_handle: open buffers
+    /// # RETURN VALUE
+    /// none
+    pub fn buffers_update(&mut self, files_handle: &files::Files) {
+        if matches!(self.mode, Mode::Line) {
+            self.buffers = files_handle.paths();
+        }
+    }
+
+    /// # CONTENT
+    /// rebuild the completion popup after the typed word changed
+    /// # ARGUMENT
+    /// - files_handle: open buffers (words are offered from all of them)
+    /// # RETURN VALUE
+    /// none
+    pub fn popup_update(&mut self, files_handle: &mut files::Files, display_handle: &display::Display) {
+        if !mem::take(&mut self.popup_refresh) || !matches!(self.mode, Mode::Append) {
+            return;
+        }
+        let (x, y) = (display_handle.center_x, display_handle.center_y);
+        let row = files_handle.current().content[y].clone();
+        self.popup = completion::Popup::new(files_handle, &row, (x, y));
+        if self.popup.is_some() {
+            self.lsp_request = Some(lsp::Request::Completion);
+        }
+    }
+
+    /// # CONTENT
+    /// add server completion items to the popup still open on their row
+    /// # ARGUMENT
+    /// - y: row index of the completion request
+    /// - items: server items
+    /// # RETURN VALUE
+    /// none
+    pub fn popup_add(&mut self, y: usize, items: Vec<completion::Item>) {
+        if let Some(popup) = self.popup.as_mut().filter(|popup| popup.start.1 == y) {
+            popup.add(items, true);
+        }
+    }
+
+    /// # CONTENT
+    /// replace the typed word with the highlighted completion item (recorded as typed text for repeats)
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// none
+    fn popup_accept(&mut self, display_handle: &mut display::Display, file_handle: &mut file::File) {
+        let Some(popup) = self.popup.take() else {
+            return;
+        };
+        let label = popup.items[popup.selected].label.clone();
+        let (start, y) = popup.start;
+        let mut typed = Vec::new();
+        for _ in start..display_handle.center_x {
+            file_handle.remove(start, y);
+            self.buffer.pop();
+            typed.push(key::Key::new(key::Code::Backspace));
+        }
+        file_handle.insert(start, y, &label);
+        display_handle.center_x = start + label.chars().count();
+        self.buffer += &label;
+        typed.extend(key::Key::keys(&label));
+        if let Some(Edit::Keys(keys)) = self.recording.as_mut().filter(|_| !self.repeating) {
+            keys.pop(); // the accepting key
+            keys.extend(typed);
+        }
+    }
+
+    /// # CONTENT
+    /// move center to a diagnostic of the file (the message is shown in the cmd line)
+    /// # ARGUMENT
+    /// - step: diagnostics moved forward from center (negative for backward)
+    /// # RETURN VALUE
+    /// none
+    fn diagnostic_step(&mut self, step: isize, display_handle: &mut display::Display, file_handle: &file::File) {
+        let center = (display_handle.center_y, display_handle.center_x);
+        let diagnostics = &file_handle.diagnostics;
+        let index = if step > 0 {
+            diagnostics.iter().position(|d| (d.start.1, d.start.0) > center).map(|i| i + step.unsigned_abs() - 1)
+        } else {
+            diagnostics.iter().rposition(|d| (d.start.1, d.start.0) < center).and_then(|i| i.checked_sub(step.unsigned_abs() - 1))
+        };
+        match index.and_then(|i| diagnostics.get(i).map(|d| (i, d))) {
+            Some((i, diagnostic)) => {
+                self.move_center(diagnostic.start, display_handle, file_handle);
+                self.message = format!("({} of {}) {}", i + 1, diagnostics.len(), diagnostic.summary());
+            }
+            None if diagnostics.is_empty() => self.message = "diagnostic: no diagnostics".to_string(),
+            None => self.message = format!("diagnostic: no {} diagnostic", if step < 0 { "previous" } else { "next" }),
+        }
+    }
+
+    /// # CONTENT
+    /// jump to a diagnostic of the quickfix list (the message is shown in the cmd line)
+    /// # ARGUMENT
+    /// - step: diagnostics moved forward (negative for backward; 0 for the current one)
+    /// # RETURN VALUE
+    /// none
+    fn quickfix_step(&mut self, step: isize) {
+        match self.quickfix.as_mut().map(|quickfix| quickfix.step(step)) {
+            Some(Some((location, message))) => {
+                self.request = Some(files::Request::Jump(location.path.clone(), (location.x, location.y), message));
+            }
+            Some(None) => self.message = format!("quickfix: no {} diagnostic", if step < 0 { "previous" } else { "next" }),
+            None => self.message = "quickfix: no list (run :make)".to_string(),
+        }
+    }
+
+    /// # CONTENT
+    /// start a fuzzy finder (the query is read in the cmd line)
+    /// # ARGUMENT
+    /// - root: walked directory
+    /// # RETURN VALUE
+    /// none
+    fn finder_start(&mut self, root: &str) {
+        let finder = finder::Finder::new(root);
+        if finder.len() == 0 {
+            self.message = format!("finder: no files in {}", root);
+            return;
+        }
+        self.finder = Some(finder);
+        self.buffer = PROMPT_FINDER.to_string();
+        self.mode = Mode::Finder;
+    }
+
+    /// # CONTENT
+    /// get the directory of the buffer
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// the explorer dir of the center row, or the dir of the file ("." without a dir)
+    fn buffer_dir(&self, display_handle: &display::Display, file_handle: &file::File) -> String {
+        match (&file_handle.explorer, &file_handle.path) {
+            (Some(explorer), _) => explorer.dir(display_handle.center_y),
+            (None, Some(path)) => std::path::Path::new(path).parent()
+                .map(|parent| parent.to_string_lossy().to_string())
+                .filter(|parent| !parent.is_empty())
+                .unwrap_or(".".to_string()),
+            (None, None) => ".".to_string(),
+        }
+    }
+
+    /// # CONTENT
+    /// create, rename or delete an entry of the explorer buffer and list it again
+    /// # ARGUMENT
+    /// - name: cmd name ("create", "rename" or "delete")
+    /// - arg: created path or new name (relative to the buffer dir)
+    /// # RETURN VALUE
+    /// none
+    fn explorer_line(&mut self, name: &str, arg: Option<&str>, display_handle: &mut display::Display, file_handle: &mut file::File) {
+        let dir = self.buffer_dir(display_handle, file_handle);
+        let target = file_handle.explorer.as_ref().and_then(|explorer| explorer.target(display_handle.center_y));
+        let result = match (name, target) {
+            ("create", _) => {
+                let path = explorer::join(&dir, arg.unwrap_or_default());
+                explorer::create(&path).map(|_| format!("created: {}", path))
+            }
+            ("rename", Some((path, _))) => {
+                let parent = std::path::Path::new(&path).parent().map(|parent| parent.to_string_lossy().to_string()).unwrap_or_default();
+                let renamed = explorer::join(&parent, arg.unwrap_or_default());
+                fs::rename(&path, &renamed).map(|_| format!("renamed: {} -> {}", path, renamed))
+            }
+            ("delete", Some((path, is_dir))) => explorer::delete(&path, is_dir).map(|_| format!("deleted: {}", path)),
+            _ => {
+                self.message = format!("{}: no explorer entry at center", name);
+                return;
+            }
+        };
+        self.message = match result.and_then(|message| file_handle.refresh().map(|_| message)) {
+            Ok(message) => message,
+            Err(e) => format!("{}: {}", name, e),
+        };
+        self.move_center((display_handle.center_x, display_handle.center_y), display_handle, file_handle);
+    }
+
+    /// # CONTENT
+    /// move center to the next match of Cmd::search
+    /// # ARGUMENT
+    /// - forward: true: after center, false: before center
+    /// # RETURN VALUE
+    /// none
+    fn search_next(&mut self, forward: bool, display_handle: &mut display::Display, file_handle: &file::File) {
+        self.search_shown = true;
+        if let Some(pos) = search::next(&file_handle.content, &self.search, (display_handle.center_x, display_handle.center_y), forward, false) {
+            self.move_center(pos, display_handle, file_handle);
+        }
+    }
+
+    /// # CONTENT
+    /// get the highlighted search pattern
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// Cmd::search ("" after ":nohlsearch")
+    pub fn highlight(&self) -> &str {
+        if self.search_shown { &self.search } else { "" }
+    }
+
+    /// # CONTENT
+    /// execute a cmd line
+    /// # ARGUMENT
+    /// - s: cmd line without ':'
+    /// # RETURN VALUE
+    /// - true: continue program
+    /// - false: quit program
+    fn line(&mut self, s: &str, display_handle: &mut display::Display, file_handle: &mut file::File) -> bool {
+        if s.trim().is_empty() {
+            return true;
+        }
+        let parsed = match line::parse(s) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                self.message = e;
+                return true;
+            }
+        };
+        match parsed.command.name {
+            "buffer" => self.request = Some(files::Request::Name(parsed.args[0].clone())),
+            "buffer_close" => self.request = Some(files::Request::Close),
+            "buffer_next" => self.request = Some(files::Request::Next),
+            "buffer_prev" => self.request = Some(files::Request::Prev),
+            "buffers" => self.request = Some(files::Request::List),
+            "close" => self.window_request = Some(window::Request::Close),
+            "edit" => self.request = Some(files::Request::Open(parsed.args[0].clone())),
+            "create" | "delete" | "rename" => self.explorer_line(parsed.command.name, parsed.args.first().map(|arg| arg.as_str()), display_handle, file_handle),
+            "diagnostics" => self.request = Some(files::Request::Diagnostics),
+            "explore" => {
+                let dir = parsed.args.first().cloned().unwrap_or_else(|| self.buffer_dir(display_handle, file_handle));
+                self.request = Some(files::Request::Open(dir));
+            }
+            "find" => self.finder_start(parsed.args.first().map_or(".", |root| root.as_str())),
+            "goto" => {
+                let y = parsed.args[0].parse::<usize>().unwrap_or(1).saturating_sub(1);
+                self.move_center(motion::first_non_blank(&file_handle.content, y.min(file_handle.content.len() - 1)), display_handle, file_handle);
+            }
+            "grep" => {
+                let pattern = parsed.args[0].trim_start();
+                match grep::regex(pattern) {
+                    Ok(_) if pattern.is_empty() => self.message = "grep: missing text".to_string(),
+                    Ok(regex) => self.message = match &self.grep {
+                        Some(grep) => format!("grep: \"{}\" is running", grep.pattern),
+                        None => {
+                            self.grep = Some(grep::Job::spawn(".", regex));
+                            format!("grep: searching \"{}\"", pattern)
                         }
+                    },
+                    Err(e) => self.message = format!("grep: {}", e),
+                }
+            }
+            "lint" => {
+                let program = match &file_handle.path {
+                    Some(path) => self.lint_program.replace('%', &shell::quote(path)),
+                    None if self.lint_program.contains('%') => {
+                        self.message = "lint: no file path for %".to_string();
+                        return true;
                     }
-                } else if self.check(CMD_NEWLINE, key) {
-                    file_handle.content.insert(display_handle.center_y + 1, file_handle.content[display_handle.center_y][display_handle.center_x..].to_string());
-                    file_handle.content[display_handle.center_y] = file_handle.content[display_handle.center_y][..display_handle.center_x].to_string();
-                    display_handle.center_x = 0;
-                    display_handle.center_y += 1;
-                } else if self.check(CMD_QUIT, key) {
-                    return false;
-                } else if self.check(CMD_REPLACE, key) {
-                    let mut row: Vec<char> = file_handle.content[display_handle.center_y].chars().collect();
-                    row[display_handle.center_x] = ' ';
-                    file_handle.content[display_handle.center_y] = row.into_iter().collect();
-                    self.mode = Mode::Replace;
-                } else if self.check(CMD_THEME_CHANGE_TO_ONE_DARK, key) {
-                    display_handle.theme = display::Theme::one_dark();
-                } else if self.check(CMD_THEME_CHANGE_TO_ONE_LIGHT, key) {
-                    display_handle.theme = display::Theme::one_light();
+                    None => self.lint_program.clone(),
+                };
+                self.job_start(diagnostic::SOURCE_LINT, &program);
+            }
+            "lint_program" => self.lint_program = parsed.args[0].trim().to_string(),
+            "lsp_rename" => self.lsp_request = Some(lsp::Request::Rename(parsed.args[0].trim().to_string())),
+            "lsp_server" => match parsed.args[0].trim().split_once(char::is_whitespace) {
+                Some((language, command)) => {
+                    self.lsp_servers.retain(|(l, _)| l != language);
+                    self.lsp_servers.push((language.to_string(), command.trim().to_string()));
+                    self.lsp_restart.push(language.to_string());
+                }
+                None => self.message = "lsp_server: missing cmd (lsp_server <language> <cmd>)".to_string(),
+            },
+            "make" => self.job_start(diagnostic::SOURCE_MAKE, &self.make_program.clone()),
+            "make_program" => self.make_program = parsed.args[0].trim().to_string(),
+            "map" => match parsed.args.as_slice() {
+                [] => self.message = self.keymap.list(None, &[]).join("\n"),
+                [mode] => self.message = self.keymap.list(Some(mode), &[]).join("\n"),
+                [mode, seq] => self.message = self.keymap.list(Some(mode), &key::Key::parse(seq).unwrap_or_default()).join("\n"),
+                [mode, seq, cmd, ..] => match self.keymap.map(mode, seq, cmd) {
+                    Ok(()) => self.message = self.keymap.ambiguous().iter().map(|a| format!("ambiguous: {}", a)).collect::<Vec<_>>().join("\n"),
+                    Err(e) => self.message = format!("map: {}", e),
+                },
+            },
+            "nohlsearch" => self.search_shown = false,
+            "quickfix" => match &self.quickfix {
+                Some(quickfix) => {
+                    let file_handle = file::File::locations(quickfix.locations.clone(), "[quickfix]".to_string());
+                    self.request = Some(files::Request::Add(Box::new(file_handle), String::new()));
+                }
+                None => self.message = "quickfix: no list (run :make)".to_string(),
+            },
+            "quit" => return false,
+            "resize" | "vresize" => {
+                let split = if parsed.command.name == "resize" { window::Split::Horizontal } else { window::Split::Vertical };
+                match parsed.args[0].parse::<usize>() {
+                    Ok(n) if n > 0 => self.window_request = Some(window::Request::Resize(split, n)),
+                    _ => self.message = format!("{}: must be positive", parsed.command.name),
                 }
             }
-            Mode::Replace => {
-                if key == '\n' {
-                    file_handle.content[display_handle.center_y].remove(display_handle.center_x);
-                    self.buffer.push(key);
-                    self.history += &self.buffer;
+            "registers" => self.message = self.registers.list().join("\n"),
+            "split" | "vsplit" => {
+                let split = if parsed.command.name == "split" { window::Split::Horizontal } else { window::Split::Vertical };
+                self.window_request = Some(window::Request::Split(split));
+                if let Some(path) = parsed.args.first() {
+                    self.request = Some(files::Request::Open(path.clone()));
+                }
+            }
+            "substitute" => self.substitute(&format!("{}s{}", parsed.range, parsed.args[0]), display_handle, file_handle),
+            "tab" => match parsed.args[0].parse::<usize>() {
+                Ok(n) => self.window_request = Some(window::Request::Tab(n)),
+                Err(_) => self.message = "tab: must be a number".to_string(),
+            },
+            "tab_close" => self.window_request = Some(window::Request::TabClose),
+            "tab_new" => {
+                self.window_request = Some(window::Request::TabNew);
+                if let Some(path) = parsed.args.first() {
+                    self.request = Some(files::Request::Open(path.clone()));
+                }
+            }
+            "tab_next" => self.window_request = Some(window::Request::TabNext),
+            "tab_prev" => self.window_request = Some(window::Request::TabPrev),
+            "tab_width" => match parsed.args[0].parse::<usize>() {
+                Ok(n) if n > 0 => display_handle.tab_width = n,
+                _ => self.message = "tab_width: must be positive".to_string(),
+            },
+            "theme" => {
+                if let Some(theme) = display::Theme::from_name(&parsed.args[0]) {
+                    display_handle.theme = theme;
+                }
+            }
+            "timeout" => {
+                self.timeout = time::Duration::from_millis(parsed.args[0].parse().unwrap_or(0));
+            }
+            "tree" => self.window_request = Some(window::Request::Tree(parsed.args.first().cloned().unwrap_or(".".to_string()))),
+            "unmap" => {
+                if let Err(e) = self.keymap.unmap(&parsed.args[0], &parsed.args[1]) {
+                    self.message = format!("unmap: {}", e);
+                }
+            }
+            "write" => match file_handle.write(parsed.args.first().cloned()) {
+                Ok(path) => self.message = format!("written: {} ({} rows)", path, file_handle.content.len()),
+                Err(e) => self.message = format!("write: {}", e),
+            },
+            _ => {}
+        }
+        true
+    }
+
+    /// # CONTENT
+    /// execute the cmd lines of the config file
+    /// ($CENTER_CONFIG, or center/config in $XDG_CONFIG_HOME or ~/.config; '#' starts a comment row)
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// none
+    pub fn config(&mut self, display_handle: &mut display::Display, file_handle: &mut file::File) {
+        let path = match env::var("CENTER_CONFIG") {
+            Ok(path) => path,
+            Err(_) => match env::var("XDG_CONFIG_HOME").or_else(|_| env::var("HOME").map(|home| format!("{}/.config", home))) {
+                Ok(dir) => format!("{}/center/config", dir),
+                Err(_) => return,
+            },
+        };
+        let Ok(text) = fs::read_to_string(&path) else {
+            return;
+        };
+        let mut messages = Vec::new();
+        for (i, row) in text.lines().enumerate() {
+            let row = row.trim();
+            if row.is_empty() || row.starts_with('#') {
+                continue;
+            }
+            self.message = String::new();
+            self.line(row.strip_prefix(PROMPT_LINE).unwrap_or(row), display_handle, file_handle);
+            if !self.message.is_empty() {
+                messages.push(format!("{}:{}: {}", path, i + 1, self.message));
+            }
+        }
+        self.message = messages.join("\n");
+    }
+
+    /// # CONTENT
+    /// execute a substitute cmd
+    /// # ARGUMENT
+    /// - s: "[range]s/pattern/replacement/[flags]"
+    /// # RETURN VALUE
+    /// none
+    fn substitute(&mut self, s: &str, display_handle: &mut display::Display, file_handle: &mut file::File) {
+        match substitute::Substitute::parse(s, display_handle.center_y, file_handle.content.len()) {
+            Ok(sub) => {
+                let Some(m) = sub.find(&file_handle.content, (0, sub.top)) else {
+                    self.message = "pattern not found".to_string();
+                    return;
+                };
+                if !self.repeating {
+                    self.recording = Some(Edit::Line(s.to_string()));
+                }
+                if sub.confirm {
+                    // undo is saved by the first replacement
+                    self.substitute = Some((sub, m, 0));
+                    self.substitute_next(display_handle, file_handle);
                 } else {
-                    let mut row: Vec<char> = file_handle.content[display_handle.center_y].chars().collect();
-                    row[display_handle.center_x] = key;
-                    file_handle.content[display_handle.center_y] = row.into_iter().collect();
-                    self.buffer.push(key);
-                    self.history += &self.buffer;
+                    if !self.repeating {
+                        file_handle.save_undo(display_handle.center_x, display_handle.center_y);
+                    }
+                    let count = sub.run(file_handle.content_mut());
+                    self.move_center((display_handle.center_x, display_handle.center_y), display_handle, file_handle);
+                    self.message = format!("{} substitutions", count);
                 }
-                self.mode = Mode::Default;
             }
+            Err(e) => self.message = e,
         }
+    }
 
-        true
+    /// # CONTENT
+    /// complete the cmd line (repeated tab keys cycle the candidates)
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// none
+    fn complete(&mut self) {
+        let (candidates, index) = match self.completion.take() {
+            Some((candidates, index)) => {
+                let index = (index + 1) % candidates.len();
+                (candidates, index)
+            }
+            None => (line::complete(&self.buffer[PROMPT_LINE.len()..], &self.buffers), 0),
+        };
+        match candidates.get(index) {
+            Some(candidate) => {
+                self.buffer = format!("{}{}", PROMPT_LINE, candidate);
+                self.prompt_tail = 0;
+                if candidates.len() > 1 {
+                    self.message = format!("{} of {} completions", index + 1, candidates.len());
+                }
+                self.completion = Some((candidates, index));
+            }
+            None => self.message = "no completion".to_string(),
+        }
+    }
+
+    /// # CONTENT
+    /// edit the text of the cmd line or search prompt at its cursor
+    /// # ARGUMENT
+    /// - cmd: left, right, home, end, delete or backspace (other cmds type the keys)
+    /// - unbound: keys typed as text
+    /// # RETURN VALUE
+    /// none
+    fn prompt_edit(&mut self, cmd: &str, unbound: &[key::Key]) {
+        let prompt = if matches!(self.mode, Mode::Line) { PROMPT_LINE.len() } else { PROMPT_SEARCH_FORWARD.len() };
+        let mut text: Vec<char> = self.buffer[prompt..].chars().collect();
+        self.prompt_tail = self.prompt_tail.min(text.len());
+        let x = text.len() - self.prompt_tail;
+        match cmd {
+            CMD_LEFT => self.prompt_tail = (self.prompt_tail + 1).min(text.len()),
+            CMD_RIGHT => self.prompt_tail = self.prompt_tail.saturating_sub(1),
+            CMD_HOME => self.prompt_tail = text.len(),
+            CMD_END => self.prompt_tail = 0,
+            CMD_DELETE if self.prompt_tail > 0 => {
+                text.remove(x);
+                self.prompt_tail -= 1;
+            }
+            CMD_BACKSPACE if x > 0 => {
+                text.remove(x - 1);
+            }
+            CMD_DELETE | CMD_BACKSPACE => {}
+            _ => {
+                text.splice(x..x, unbound.iter().filter_map(|k| k.char()));
+            }
+        }
+        self.buffer.truncate(prompt);
+        self.buffer.extend(text);
+    }
+
+    /// # CONTENT
+    /// get the cursor of the cmd line or search prompt
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// Some(x): char index of the cursor in Cmd::buffer
+    /// None: no prompt, or the cursor is at the end
+    pub fn prompt_cursor(&self) -> Option<usize> {
+        if !matches!(self.mode, Mode::Line | Mode::Search) || self.prompt_tail == 0 {
+            return None;
+        }
+        self.buffer.chars().count().checked_sub(self.prompt_tail)
+    }
+
+    /// # CONTENT
+    /// show the current match of the confirm-each substitute cmd (or finish it)
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// none
+    fn substitute_next(&mut self, display_handle: &mut display::Display, file_handle: &file::File) {
+        match &self.substitute {
+            Some((_, (start, _, y), _)) => {
+                let x = file_handle.content[*y][..*start].chars().count();
+                self.move_center((x, *y), display_handle, file_handle);
+                self.buffer = "replace? (y/n/a/q)".to_string();
+                self.mode = Mode::Confirm;
+            }
+            None => {
+                self.buffer = String::new();
+                self.mode = Mode::Default;
+            }
+        }
+    }
+
+    /// # CONTENT
+    /// get indentation inserted by a tab key
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// "\t" or spaces to the next tab stop
+    fn indent(&self, display_handle: &display::Display, file_handle: &file::File) -> String {
+        if self.expand_tab {
+            let visual_x = display_handle.visual_x(&file_handle.content[display_handle.center_y], display_handle.center_x);
+            " ".repeat(display_handle.tab_width - visual_x % display_handle.tab_width)
+        } else {
+            "\t".to_string()
+        }
+    }
+
+    /// # CONTENT
+    /// convert buffer indentation keeping the center display col
+    /// # ARGUMENT
+    /// - expand_tab: true: tabs to spaces, false: leading spaces to tabs
+    /// # RETURN VALUE
+    /// none
+    fn retab(&self, expand_tab: bool, display_handle: &mut display::Display, file_handle: &mut file::File) {
+        let visual_x = display_handle.visual_x(&file_handle.content[display_handle.center_y], display_handle.center_x);
+        file_handle.retab(display_handle.tab_width, expand_tab);
+        display_handle.center_x = display_handle.char_x(&file_handle.content[display_handle.center_y], visual_x);
+    }
+
+    /// # CONTENT
+    /// execute a cmd in default mode
+    /// # ARGUMENT
+    /// - cmd: cmd name
+    /// # RETURN VALUE
+    /// - true: continue program
+    /// - false: quit program
+    fn default_cmd(&mut self, cmd: &'static str, display_handle: &mut display::Display, file_handle: &mut file::File) -> bool {
+        match cmd {
+            CMD_INDENT => {
+                self.edit(display_handle, file_handle);
+                let indent = self.indent(display_handle, file_handle);
+                file_handle.insert(display_handle.center_x, display_handle.center_y, &indent);
+                display_handle.center_x += indent.chars().count();
+            }
+            CMD_APPEND => {
+                self.edit(display_handle, file_handle);
+                file_handle.insert(display_handle.center_x, display_handle.center_y, " ");
+                self.mode = Mode::Append;
+            }
+            CMD_BACKSPACE_LEFT => {
+                self.edit(display_handle, file_handle);
+                file_handle.remove(display_handle.center_x, display_handle.center_y);
+                if display_handle.center_x > 0 {
+                    display_handle.center_x -= 1;
+                }
+            }
+            CMD_BACKSPACE_RIGHT => {
+                self.edit(display_handle, file_handle);
+                file_handle.remove(display_handle.center_x, display_handle.center_y);
+                if display_handle.center_x > file_handle.row_len(display_handle.center_y) {
+                    display_handle.center_x = file_handle.row_len(display_handle.center_y);
+                }
+            }
+            CMD_BUFFER_NEXT => {
+                self.request = Some(files::Request::Next);
+            }
+            CMD_BUFFER_PREV => {
+                self.request = Some(files::Request::Prev);
+            }
+            CMD_CENTER_DOWN => {
+                if display_handle.wrap && display_handle.wrap_move {
+                    display_handle.wrap_move(&file_handle.content, true);
+                } else {
+                    let visual_x = display_handle.visual_x(&file_handle.content[display_handle.center_y], display_handle.center_x);
+                    display_handle.center_y = display_handle.center_y.saturating_add(self.count).min(file_handle.content.len() - 1);
+                    display_handle.center_x = display_handle.char_x(&file_handle.content[display_handle.center_y], visual_x);
+                    self.count = 1; // the count is the row count
+                }
+            }
+            CMD_CENTER_END_FILE => {
+                display_handle.center_y = file_handle.content.len() - 1;
+                display_handle.center_x = file_handle.row_len(display_handle.center_y);
+            }
+            CMD_CENTER_END_LINE => {
+                display_handle.center_x = file_handle.row_len(display_handle.center_y);
+            }
+            CMD_CENTER_FIRST_NON_BLANK => {
+                self.move_center(motion::first_non_blank(&file_handle.content, display_handle.center_y), display_handle, file_handle);
+            }
+            CMD_CENTER_LINE => {
+                let y = self.count.saturating_sub(1);
+                self.count = 1; // count is the row number
+                self.move_center(motion::first_non_blank(&file_handle.content, y.min(file_handle.content.len() - 1)), display_handle, file_handle);
+            }
+            CMD_DELETE_ROW | CMD_YANK_ROW => {
+                let (y, count) = (display_handle.center_y, self.count);
+                let bottom = (y + count).min(file_handle.content.len()) - 1;
+                let rows = file_handle.content[y..=bottom].to_vec();
+                if cmd == CMD_DELETE_ROW {
+                    self.edit(display_handle, file_handle);
+                    file_handle.content_mut().drain(y..=bottom);
+                    if file_handle.content.is_empty() {
+                        file_handle.content_mut().push(String::new());
+                    }
+                    self.move_center(motion::first_non_blank(&file_handle.content, y.min(file_handle.content.len() - 1)), display_handle, file_handle);
+                } else {
+                    self.message = format!("{} rows yanked", rows.len());
+                }
+                self.registers.set(self.register, register::Register { kind: visual::Kind::Line, rows });
+                self.count = 1; // the count is the row count
+            }
+            CMD_DIAGNOSTIC_NEXT => {
+                self.diagnostic_step(self.count as isize, display_handle, file_handle);
+                self.count = 1; // the count is the diagnostic count
+            }
+            CMD_DIAGNOSTIC_PREV => {
+                self.diagnostic_step(-(self.count as isize), display_handle, file_handle);
+                self.count = 1; // the count is the diagnostic count
+            }
+            CMD_EXPLORER_OPEN => match file_handle.explorer.as_mut().map(|explorer| (explorer.entry(display_handle.center_y), &mut explorer.kind)) {
+                Some((Some((path, true)), explorer::Kind::Tree(_, _))) => {
+                    if let Some(explorer) = file_handle.explorer.as_mut() {
+                        explorer.toggle(&path);
+                    }
+                    if let Err(e) = file_handle.refresh() {
+                        self.message = format!("explorer_open: {}", e);
+                    }
+                    self.move_center((display_handle.center_x, display_handle.center_y), display_handle, file_handle);
+                }
+                Some((Some((path, _)), explorer::Kind::Tree(_, _))) => {
+                    // a file of the tree panel opens in the pane at its right
+                    self.window_request = Some(window::Request::Focus(window::Direction::Right));
+                    self.request = Some(files::Request::Open(path));
+                }
+                Some((Some((path, _)), explorer::Kind::Directory(_))) => self.request = Some(files::Request::Open(path)),
+                Some((None, _)) => self.message = "explorer_open: empty directory".to_string(),
+                None => self.message = "explorer_open: not an explorer buffer".to_string(),
+            },
+            CMD_EXPLORER_PARENT => match &file_handle.explorer {
+                Some(explorer) if matches!(explorer.kind, explorer::Kind::Tree(_, _)) => self.message = "explorer_parent: tree panel".to_string(),
+                Some(_) => self.request = file_handle.path.as_deref().map(|dir| files::Request::Open(explorer::parent(dir))),
+                None => self.request = Some(files::Request::Open(self.buffer_dir(display_handle, file_handle))),
+            },
+            CMD_EXPLORER_TREE => self.window_request = Some(window::Request::Tree(".".to_string())),
+            CMD_FINDER => self.finder_start("."),
+            CMD_LSP_DEFINITION => self.lsp_request = Some(lsp::Request::Definition),
+            CMD_LSP_HOVER => self.lsp_request = Some(lsp::Request::Hover),
+            CMD_LSP_REFERENCES => self.lsp_request = Some(lsp::Request::References),
+            CMD_LOCATION_OPEN => match file_handle.locations.as_ref().map(|locations| locations.get(display_handle.center_y)) {
+                Some(Some(location)) => self.request = Some(files::Request::Jump(location.path.clone(), (location.x, location.y), String::new())),
+                Some(None) => self.message = "location_open: empty list".to_string(),
+                None => self.message = "location_open: not a location list buffer".to_string(),
+            },
+            CMD_LINE => {
+                self.buffer = PROMPT_LINE.to_string();
+                self.prompt_tail = 0;
+                self.mode = Mode::Line;
+            }
+            CMD_CENTER_LEFT => {
+                display_handle.center_x = display_handle.center_x.saturating_sub(self.count);
+                self.count = 1; // the count is the char count
+            }
+            CMD_CENTER_MATCHING_BRACKET => {
+                if let Some(pos) = motion::matching_bracket(&file_handle.content, (display_handle.center_x, display_handle.center_y)) {
+                    self.move_center(pos, display_handle, file_handle);
+                }
+            }
+            CMD_CENTER_PAGE_DOWN => {
+                let visual_x = display_handle.visual_x(&file_handle.content[display_handle.center_y], display_handle.center_x);
+                display_handle.center_y = (display_handle.center_y + display_handle.code_rows()).min(file_handle.content.len() - 1);
+                display_handle.center_x = display_handle.char_x(&file_handle.content[display_handle.center_y], visual_x);
+            }
+            CMD_CENTER_PAGE_UP => {
+                let visual_x = display_handle.visual_x(&file_handle.content[display_handle.center_y], display_handle.center_x);
+                display_handle.center_y = display_handle.center_y.saturating_sub(display_handle.code_rows());
+                display_handle.center_x = display_handle.char_x(&file_handle.content[display_handle.center_y], visual_x);
+            }
+            CMD_CENTER_PARAGRAPH_NEXT => {
+                self.move_center(motion::paragraph(&file_handle.content, display_handle.center_y, true), display_handle, file_handle);
+            }
+            CMD_CENTER_PARAGRAPH_PREV => {
+                self.move_center(motion::paragraph(&file_handle.content, display_handle.center_y, false), display_handle, file_handle);
+            }
+            CMD_CENTER_RIGHT => {
+                display_handle.center_x = display_handle.center_x.saturating_add(self.count).min(file_handle.row_len(display_handle.center_y));
+                self.count = 1; // the count is the char count
+            }
+            CMD_CENTER_START_FILE => {
+                display_handle.center_x = 0;
+                display_handle.center_y = 0;
+            }
+            CMD_CENTER_START_LINE => {
+                display_handle.center_x = 0;
+            }
+            CMD_CENTER_UP => {
+                if display_handle.wrap && display_handle.wrap_move {
+                    display_handle.wrap_move(&file_handle.content, false);
+                } else {
+                    let visual_x = display_handle.visual_x(&file_handle.content[display_handle.center_y], display_handle.center_x);
+                    display_handle.center_y = display_handle.center_y.saturating_sub(self.count);
+                    display_handle.center_x = display_handle.char_x(&file_handle.content[display_handle.center_y], visual_x);
+                    self.count = 1; // the count is the row count
+                }
+            }
+            CMD_CENTER_WORD_BACKWARD => {
+                self.move_center(motion::word_backward(&file_handle.content, (display_handle.center_x, display_handle.center_y), false), display_handle, file_handle);
+            }
+            CMD_CENTER_WORD_BACKWARD_BIG => {
+                self.move_center(motion::word_backward(&file_handle.content, (display_handle.center_x, display_handle.center_y), true), display_handle, file_handle);
+            }
+            CMD_CENTER_WORD_END => {
+                self.move_center(motion::word_end(&file_handle.content, (display_handle.center_x, display_handle.center_y), false), display_handle, file_handle);
+            }
+            CMD_CENTER_WORD_END_BIG => {
+                self.move_center(motion::word_end(&file_handle.content, (display_handle.center_x, display_handle.center_y), true), display_handle, file_handle);
+            }
+            CMD_CENTER_WORD_FORWARD => {
+                self.move_center(motion::word_forward(&file_handle.content, (display_handle.center_x, display_handle.center_y), false), display_handle, file_handle);
+            }
+            CMD_CENTER_WORD_FORWARD_BIG => {
+                self.move_center(motion::word_forward(&file_handle.content, (display_handle.center_x, display_handle.center_y), true), display_handle, file_handle);
+            }
+            CMD_NEWLINE => {
+                self.edit(display_handle, file_handle);
+                let byte_x = file_handle.byte_x(display_handle.center_x, display_handle.center_y);
+                let tail = file_handle.content_mut()[display_handle.center_y].split_off(byte_x);
+                file_handle.content_mut().insert(display_handle.center_y + 1, tail);
+                display_handle.center_x = 0;
+                display_handle.center_y += 1;
+            }
+            CMD_OPTION_EXPAND_TAB => {
+                self.expand_tab = true;
+            }
+            CMD_OPTION_HARD_TAB => {
+                self.expand_tab = false;
+            }
+            CMD_OPTION_ROW_NUM => {
+                display_handle.row_num = display_handle.row_num.next();
+            }
+            CMD_OPTION_TAB_WIDTH => {
+                // any width is entered in the cmd line
+                self.buffer = format!("{}tab_width {}", PROMPT_LINE, display_handle.tab_width);
+                self.prompt_tail = 0;
+                self.mode = Mode::Line;
+            }
+            CMD_OPTION_TAB_WIDTH_2 => {
+                display_handle.tab_width = 2;
+            }
+            CMD_OPTION_TAB_WIDTH_4 => {
+                display_handle.tab_width = 4;
+            }
+            CMD_OPTION_TAB_WIDTH_8 => {
+                display_handle.tab_width = 8;
+            }
+            CMD_OPTION_VIRTUAL_TEXT => {
+                display_handle.virtual_text = !display_handle.virtual_text;
+            }
+            CMD_OPTION_WHITESPACE => {
+                display_handle.show_whitespace = !display_handle.show_whitespace;
+            }
+            CMD_OPTION_WRAP => {
+                display_handle.wrap = !display_handle.wrap;
+            }
+            CMD_OPTION_WRAP_MOVE => {
+                display_handle.wrap_move = !display_handle.wrap_move;
+            }
+            CMD_PASTE | CMD_PASTE_BEFORE => {
+                let name = self.register.unwrap_or('"');
+                match self.registers.get(name) {
+                    Some(register) => {
+                        self.edit(display_handle, file_handle);
+                        let pos = (display_handle.center_x, display_handle.center_y);
+                        let pos = visual::paste(file_handle.content_mut(), pos, register.kind, &register.rows, cmd == CMD_PASTE, display_handle);
+                        self.move_center(pos, display_handle, file_handle);
+                    }
+                    None => self.message = format!("register \"{} is empty", name),
+                }
+            }
+            CMD_QUIT => {
+                return false;
+            }
+            CMD_QUICKFIX_NEXT => {
+                self.quickfix_step(self.count as isize);
+                self.count = 1; // the count is the diagnostic count
+            }
+            CMD_QUICKFIX_PREV => {
+                self.quickfix_step(-(self.count as isize));
+                self.count = 1; // the count is the diagnostic count
+            }
+            CMD_REPLACE => {
+                self.edit(display_handle, file_handle);
+                // a space marks the replaced char (it is inserted at the row end)
+                self.replaced = file_handle.remove(display_handle.center_x, display_handle.center_y);
+                file_handle.insert(display_handle.center_x, display_handle.center_y, " ");
+                self.mode = Mode::Replace;
+            }
+            CMD_REGISTER => {
+                self.buffer = "\"".to_string();
+                self.selecting_register = true;
+            }
+            CMD_REDO => {
+                if let Some((x, y)) = file_handle.redo(display_handle.center_x, display_handle.center_y) {
+                    display_handle.center_x = x;
+                    display_handle.center_y = y;
+                }
+            }
+            CMD_REPEAT => {
+                let (count, done) = (self.count, self.done.clone());
+                let ret = match self.last_edit.clone() {
+                    Edit::Keys(keys) => self.replay(&keys, self.repeating, display_handle, file_handle),
+                    Edit::Line(s) => self.line(&s, display_handle, file_handle),
+                };
+                if !ret {
+                    return false;
+                }
+                (self.count, self.done) = (count, done);
+            }
+            CMD_RESET => {} // Cmd::resolve already cleared Cmd::buffer
+            CMD_RETAB_SPACE_TO_TAB => {
+                self.edit(display_handle, file_handle);
+                self.retab(false, display_handle, file_handle);
+            }
+            CMD_RETAB_TAB_TO_SPACE => {
+                self.edit(display_handle, file_handle);
+                self.retab(true, display_handle, file_handle);
+            }
+            CMD_SEARCH_BACKWARD => {
+                self.search_start(false, display_handle);
+            }
+            CMD_SEARCH_FORWARD => {
+                self.search_start(true, display_handle);
+            }
+            CMD_SEARCH_NEXT => {
+                self.search_next(self.search_forward, display_handle, file_handle);
+            }
+            CMD_SEARCH_PREV => {
+                self.search_next(!self.search_forward, display_handle, file_handle);
+            }
+            CMD_SYNTAX_CHILD => {
+                self.syntax_motion(syntax::Motion::Child, display_handle, file_handle);
+            }
+            CMD_SYNTAX_NEXT_SIBLING => {
+                self.syntax_motion(syntax::Motion::NextSibling, display_handle, file_handle);
+            }
+            CMD_SYNTAX_PARENT => {
+                self.syntax_motion(syntax::Motion::Parent, display_handle, file_handle);
+            }
+            CMD_THEME_CHANGE_TO_ONE_DARK => {
+                display_handle.theme = display::Theme::one_dark();
+            }
+            CMD_THEME_CHANGE_TO_ONE_LIGHT => {
+                display_handle.theme = display::Theme::one_light();
+            }
+            CMD_UNDO => {
+                if let Some((x, y)) = file_handle.undo(display_handle.center_x, display_handle.center_y) {
+                    display_handle.center_x = x;
+                    display_handle.center_y = y;
+                }
+            }
+            CMD_VISUAL_BLOCK | CMD_VISUAL_CHAR | CMD_VISUAL_LINE => {
+                self.visual = (visual_kind(cmd), (display_handle.center_x, display_handle.center_y));
+                self.visual_keys = self.done.clone().unwrap_or_default();
+                self.mode = Mode::Visual;
+            }
+            CMD_TAB_NEW => self.window_request = Some(window::Request::TabNew),
+            CMD_TAB_NEXT => self.window_request = Some(window::Request::TabNext),
+            CMD_TAB_PREV => self.window_request = Some(window::Request::TabPrev),
+            CMD_WINDOW_CLOSE => self.window_request = Some(window::Request::Close),
+            CMD_WINDOW_DOWN => self.window_request = Some(window::Request::Focus(window::Direction::Down)),
+            CMD_WINDOW_LEFT => self.window_request = Some(window::Request::Focus(window::Direction::Left)),
+            CMD_WINDOW_NEXT => self.window_request = Some(window::Request::FocusNext),
+            CMD_WINDOW_RIGHT => self.window_request = Some(window::Request::Focus(window::Direction::Right)),
+            CMD_WINDOW_SPLIT => self.window_request = Some(window::Request::Split(window::Split::Horizontal)),
+            CMD_WINDOW_UP => self.window_request = Some(window::Request::Focus(window::Direction::Up)),
+            CMD_WINDOW_VSPLIT => self.window_request = Some(window::Request::Split(window::Split::Vertical)),
+            CMD_WINDOW_NARROWER | CMD_WINDOW_SHORTER | CMD_WINDOW_TALLER | CMD_WINDOW_WIDER => {
+                // the count is the resize step (not a repeat)
+                let split = if matches!(cmd, CMD_WINDOW_SHORTER | CMD_WINDOW_TALLER) { window::Split::Horizontal } else { window::Split::Vertical };
+                let step = if matches!(cmd, CMD_WINDOW_NARROWER | CMD_WINDOW_SHORTER) { -(self.count as isize) } else { self.count as isize };
+                self.window_request = Some(window::Request::Grow(split, step));
+                self.count = 1;
+            }
+            _ => {}
+        }
+
+        // repeat the completed cmd by its count prefix
+        // (a motion is run again until center stops moving, other cmds are replayed up to MAX_REPEAT times)
+        if self.count > 1
+            && cmd.starts_with("center_")
+            && matches!(self.mode, Mode::Default | Mode::Visual) {
+            let count = mem::replace(&mut self.count, 1);
+            self.done = None;
+            for _ in 1..count {
+                let pos = (display_handle.center_x, display_handle.center_y);
+                self.default_cmd(cmd, display_handle, file_handle);
+                if pos == (display_handle.center_x, display_handle.center_y) {
+                    break;
+                }
+            }
+        } else if let Some(done) = self.done.take()
+            && self.count > 1
+            && matches!(self.mode, Mode::Default | Mode::Visual) {
+            let keys: Vec<key::Key> = done.into_iter().skip_while(|k| k.char().is_some_and(|c| c.is_ascii_digit())).collect();
+            for _ in 1..self.count.min(MAX_REPEAT) {
+                if !self.replay(&keys, true, display_handle, file_handle) {
+                    return false;
+                }
+            }
+        }
+
+        true
+    }
+
+    /// # CONTENT
+    /// get the visual selection
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// Some(selection): in visual mode
+    /// None: other mode
+    pub fn selection(&self, display_handle: &display::Display, file_handle: &file::File) -> Option<visual::Selection> {
+        if !matches!(self.mode, Mode::Visual) {
+            return None;
+        }
+        Some(visual::Selection::new(self.visual.0, self.visual.1, (display_handle.center_x, display_handle.center_y), &file_handle.content, display_handle))
+    }
+
+    /// # CONTENT
+    /// execute a cmd in visual mode (operators apply to the selection and return to default mode)
+    /// # ARGUMENT
+    /// - cmd: cmd name
+    /// # RETURN VALUE
+    /// - true: continue program
+    /// - false: quit program
+    fn visual_cmd(&mut self, cmd: &'static str, display_handle: &mut display::Display, file_handle: &mut file::File) -> bool {
+        let Some(selection) = self.selection(display_handle, file_handle) else {
+            return true;
+        };
+        let start = selection.start(&file_handle.content, display_handle);
+        match cmd {
+            CMD_CANCEL => {}
+            CMD_CHANGE | CMD_DELETE => {
+                self.visual_edit(display_handle, file_handle);
+                let rows = if cmd == CMD_CHANGE && selection.kind == visual::Kind::Line {
+                    file_handle.content_mut().splice(selection.top..=selection.bottom, [String::new()]).collect()
+                } else {
+                    selection.delete(file_handle.content_mut(), display_handle)
+                };
+                self.registers.set(self.register, register::Register { kind: selection.kind, rows });
+                self.move_center(start, display_handle, file_handle);
+                if cmd == CMD_CHANGE {
+                    file_handle.insert(display_handle.center_x, display_handle.center_y, " ");
+                    self.count = 1;
+                    self.buffer = String::new();
+                    self.mode = Mode::Append;
+                    return true;
+                }
+            }
+            CMD_INDENT | CMD_OUTDENT => {
+                self.visual_edit(display_handle, file_handle);
+                let indent = if self.expand_tab { " ".repeat(display_handle.tab_width) } else { "\t".to_string() };
+                selection.shift(file_handle.content_mut(), Some(indent.as_str()).filter(|_| cmd == CMD_INDENT), display_handle.tab_width);
+                self.move_center(motion::first_non_blank(&file_handle.content, selection.top), display_handle, file_handle);
+            }
+            CMD_LOWERCASE | CMD_UPPERCASE => {
+                self.visual_edit(display_handle, file_handle);
+                if cmd == CMD_LOWERCASE {
+                    selection.map(file_handle.content_mut(), display_handle, |c| c.to_lowercase().to_string());
+                } else {
+                    selection.map(file_handle.content_mut(), display_handle, |c| c.to_uppercase().to_string());
+                }
+                self.move_center(start, display_handle, file_handle);
+            }
+            CMD_VISUAL_BLOCK | CMD_VISUAL_CHAR | CMD_VISUAL_LINE if visual_kind(cmd) != selection.kind => {
+                self.visual.0 = visual_kind(cmd);
+                return true;
+            }
+            CMD_VISUAL_BLOCK | CMD_VISUAL_CHAR | CMD_VISUAL_LINE => {}
+            CMD_YANK => {
+                let rows = selection.text(&file_handle.content, display_handle);
+                self.message = format!("{} rows yanked", rows.len());
+                self.registers.set(self.register, register::Register { kind: selection.kind, rows });
+                self.move_center(start, display_handle, file_handle);
+            }
+            _ if cmd.starts_with("center_") || cmd.starts_with("syntax_") || [CMD_REGISTER, CMD_SEARCH_NEXT, CMD_SEARCH_PREV].contains(&cmd) => {
+                return self.default_cmd(cmd, display_handle, file_handle);
+            }
+            _ => {
+                self.message = format!("not in visual mode: {}", cmd);
+                return true;
+            }
+        }
+        self.buffer = String::new();
+        self.mode = Mode::Default;
+        true
+    }
+
+    /// # CONTENT
+    /// move the append placeholder (a space at center) to a new center pos
+    /// # ARGUMENT
+    /// - pos: new (x, y) without the placeholder
+    /// # RETURN VALUE
+    /// none
+    fn append_move(&mut self, pos: (usize, usize), display_handle: &mut display::Display, file_handle: &mut file::File) {
+        file_handle.remove(display_handle.center_x, display_handle.center_y);
+        self.move_center(pos, display_handle, file_handle);
+        file_handle.insert(display_handle.center_x, display_handle.center_y, " ");
+        self.count = 1; // typed text is no longer contiguous
+    }
+
+    /// # CONTENT
+    /// execute cmd
+    /// # ARGUMENT
+    /// - key: entered key event
+    /// # RETURN VALUE
+    /// - true: continue program
+    /// - false: quit program
+    pub fn key(&mut self, key: key::Key, display_handle: &mut display::Display, file_handle: &mut file::File) -> bool {
+        if !self.repeating {
+            self.message = String::new();
+        }
+        if matches!(self.mode, Mode::Append | Mode::Replace)
+            && let Some(Edit::Keys(keys)) = self.recording.as_mut().filter(|_| !self.repeating) {
+            keys.push(key);
+        }
+        if matches!(self.mode, Mode::Visual) && !self.repeating {
+            self.visual_keys.push(key);
+        }
+        if self.selecting_register {
+            self.selecting_register = false;
+            match key.char().filter(|c| register::valid(*c)) {
+                Some(c) => {
+                    self.register = Some(c);
+                    self.buffer.push(c);
+                }
+                None => {
+                    self.message = format!("unknown register: {}", key.notation());
+                    self.buffer = String::new();
+                }
+            }
+            return true;
+        }
+        if matches!(self.mode, Mode::Default | Mode::Visual)
+            && self.pending.is_empty()
+            && let Some(c) = key.char()
+            && c.is_ascii_digit()
+            && (c != '0' || !self.count_digits().is_empty()) {
+            self.buffer.push(c); // count prefix
+            return true;
+        }
+        let resolved = self.resolve(Some(key));
+        self.run(resolved, display_handle, file_handle)
+    }
+
+    /// # CONTENT
+    /// execute the pending key sequence without waiting for more keys
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// - true: continue program
+    /// - false: quit program
+    pub fn flush(&mut self, display_handle: &mut display::Display, file_handle: &mut file::File) -> bool {
+        if !self.repeating {
+            self.message = String::new();
+        }
+        let resolved = self.resolve(None);
+        self.run(resolved, display_handle, file_handle)
+    }
+
+    /// # CONTENT
+    /// execute a resolved key sequence in the current mode
+    /// # ARGUMENT
+    /// - resolved: resolved key sequence
+    /// # RETURN VALUE
+    /// - true: continue program
+    /// - false: quit program
+    fn run(&mut self, resolved: keymap::Resolved, display_handle: &mut display::Display, file_handle: &mut file::File) -> bool {
+        let (cmd, unbound) = match resolved {
+            keymap::Resolved::Cmd(cmd) => (cmd, Vec::new()),
+            keymap::Resolved::Pending => return true,
+            keymap::Resolved::Unbound(keys) => ("", keys),
+        };
+        let (x, y) = (display_handle.center_x, display_handle.center_y);
+        if !matches!(cmd, CMD_BACKSPACE | CMD_COMPLETION_ACCEPT | CMD_COMPLETION_NEXT | CMD_COMPLETION_PREV | CMD_DOWN | CMD_UP) || !matches!(self.mode, Mode::Append) {
+            self.popup = None;
+        }
+        match self.mode.clone() {
+            Mode::Append => match cmd {
+                CMD_BACKSPACE => {
+                    if x > 0 {
+                        file_handle.remove(x - 1, y);
+                        display_handle.center_x -= 1;
+                        self.buffer.pop();
+                    } else if y > 0 {
+                        let row = file_handle.content_mut().remove(y);
+                        display_handle.center_x = file_handle.row_len(y - 1);
+                        display_handle.center_y -= 1;
+                        file_handle.content_mut()[y - 1].push_str(&row);
+                        self.count = 1;
+                    }
+                    self.popup_refresh = self.popup.is_some();
+                }
+                CMD_COMPLETION_ACCEPT if self.popup.as_ref().is_some_and(|popup| !popup.items.is_empty()) => {
+                    self.popup_accept(display_handle, file_handle);
+                }
+                CMD_COMPLETION_NEXT | CMD_DOWN if let Some(popup) = self.popup.as_mut() => popup.step(1),
+                CMD_COMPLETION_PREV | CMD_UP if let Some(popup) = self.popup.as_mut() => popup.step(-1),
+                CMD_COMPLETION_NEXT if !completion::prefix(&file_handle.content[y], x).1.is_empty() => self.popup_refresh = true,
+                CMD_COMPLETION_NEXT => {
+                    self.buffer.push('\t');
+                    let typed = self.indent(display_handle, file_handle);
+                    file_handle.insert(x, y, &typed);
+                    display_handle.center_x += typed.chars().count();
+                }
+                CMD_COMPLETION_PREV => {}
+                CMD_DELETE => {
+                    if x + 1 < file_handle.row_len(y) {
+                        file_handle.remove(x + 1, y);
+                    } else if y + 1 < file_handle.content.len() {
+                        let row = file_handle.content_mut().remove(y + 1);
+                        file_handle.content_mut()[y].push_str(&row);
+                    }
+                    self.count = 1;
+                }
+                CMD_DOWN => self.append_move((x, y + 1), display_handle, file_handle),
+                CMD_END => self.append_move((usize::MAX, y), display_handle, file_handle),
+                CMD_COMPLETION_ACCEPT | CMD_FINISH => {
+                    self.popup = None;
+                    if self.count > 1 && !self.repeating {
+                        let (count, typed) = (self.count, key::Key::keys(&self.buffer));
+                        self.count = 1;
+                        for _ in 1..count.min(MAX_REPEAT) {
+                            self.replay(&typed, true, display_handle, file_handle);
+                        }
+                    }
+                    self.buffer.push('\n');
+                    file_handle.remove(display_handle.center_x, display_handle.center_y);
+                    self.history += &self.buffer;
+                    self.buffer = String::new();
+                    self.mode = Mode::Default;
+                }
+                CMD_HOME => self.append_move((0, y), display_handle, file_handle),
+                CMD_LEFT => self.append_move((x.saturating_sub(1), y), display_handle, file_handle),
+                CMD_RIGHT => self.append_move((x + 1, y), display_handle, file_handle),
+                CMD_UP => self.append_move((x, y.saturating_sub(1)), display_handle, file_handle),
+                _ => {
+                    for c in unbound.iter().filter_map(|k| k.char()) {
+                        self.buffer.push(c);
+                        let typed = if c == '\t' { self.indent(display_handle, file_handle) } else { c.to_string() };
+                        file_handle.insert(display_handle.center_x, display_handle.center_y, &typed);
+                        display_handle.center_x += typed.chars().count();
+                        self.popup_refresh = completion::word_char(c);
+                    }
+                }
+            },
+            Mode::Default | Mode::Visual => {
+                let ret = match (cmd, &self.mode) {
+                    ("", _) => true,
+                    (_, Mode::Visual) => self.visual_cmd(cmd, display_handle, file_handle),
+                    _ => self.default_cmd(cmd, display_handle, file_handle),
+                };
+                if cmd != CMD_REGISTER {
+                    self.register = None;
+                }
+                if !ret {
+                    return false;
+                }
+            }
+            Mode::Replace => {
+                match (cmd, unbound.first().and_then(|k| k.char())) {
+                    (CMD_DELETE, _) => {
+                        file_handle.remove(x, y);
+                        self.buffer.push('\n');
+                        self.history += &self.buffer;
+                    }
+                    (CMD_CANCEL, _) | (_, None) => {
+                        file_handle.remove(x, y);
+                        if let Some(replaced) = self.replaced {
+                            file_handle.insert(x, y, &replaced.to_string());
+                        }
+                    }
+                    (_, Some(c)) => {
+                        let mut row: Vec<char> = file_handle.content[y].chars().collect();
+                        let count = self.count.min(row.len() - x);
+                        for r in row.iter_mut().skip(x).take(count) {
+                            *r = c;
+                        }
+                        file_handle.content_mut()[y] = row.into_iter().collect();
+                        display_handle.center_x += count.saturating_sub(1);
+                        self.buffer.push(c);
+                        self.history += &self.buffer;
+                    }
+                }
+                self.buffer = String::new();
+                self.mode = Mode::Default;
+            }
+            Mode::Confirm => {
+                if let Some((sub, m, count)) = self.substitute.take() {
+                    if count == 0 && [CMD_ALL, CMD_YES].contains(&cmd) {
+                        file_handle.save_undo(display_handle.center_x, display_handle.center_y);
+                    }
+                    let (m, count, ask) = match cmd {
+                        CMD_ALL => {
+                            let (mut m, mut count) = (Some(m), count);
+                            while let Some(found) = m {
+                                m = sub.replace_next(file_handle.content_mut(), found);
+                                count += 1;
+                            }
+                            (None, count, false)
+                        }
+                        CMD_NO => (sub.skip_next(&file_handle.content, m), count, true),
+                        CMD_QUIT => (None, count, false),
+                        CMD_YES => (sub.replace_next(file_handle.content_mut(), m), count + 1, true),
+                        _ => (Some(m), count, true),
+                    };
+                    match m.filter(|_| ask) {
+                        Some(m) => self.substitute = Some((sub, m, count)),
+                        None => self.message = format!("{} substitutions", count),
+                    }
+                    self.substitute_next(display_handle, file_handle);
+                    if self.substitute.is_none() {
+                        self.move_center((display_handle.center_x, display_handle.center_y), display_handle, file_handle);
+                    }
+                }
+            }
+            Mode::Finder => match cmd {
+                CMD_BACKSPACE | CMD_CANCEL if cmd == CMD_CANCEL || self.buffer == PROMPT_FINDER => {
+                    self.finder = None;
+                    self.buffer = String::new();
+                    self.mode = Mode::Default;
+                }
+                CMD_DOWN | CMD_UP => {
+                    if let Some(finder) = self.finder.as_mut() {
+                        finder.select(cmd == CMD_DOWN);
+                    }
+                }
+                CMD_EXECUTE => {
+                    if let Some(path) = self.finder.take().and_then(|finder| finder.path()) {
+                        self.request = Some(files::Request::Open(path));
+                    }
+                    self.buffer = String::new();
+                    self.mode = Mode::Default;
+                }
+                _ => {
+                    if cmd == CMD_BACKSPACE {
+                        self.buffer.pop();
+                    }
+                    self.buffer.extend(unbound.iter().filter_map(|k| k.char()));
+                    if let Some(finder) = self.finder.as_mut() {
+                        finder.update(&self.buffer[PROMPT_FINDER.len()..]);
+                    }
+                }
+            },
+            Mode::Line => {
+                if cmd == CMD_COMPLETE {
+                    self.complete();
+                    return true;
+                }
+                self.completion = None;
+                match cmd {
+                    CMD_BACKSPACE | CMD_CANCEL if cmd == CMD_CANCEL || self.buffer == PROMPT_LINE => {
+                        self.buffer = String::new();
+                        self.mode = Mode::Default;
+                    }
+                    CMD_EXECUTE => {
+                        let line = self.buffer[PROMPT_LINE.len()..].to_string();
+                        self.history += &self.buffer;
+                        self.buffer = String::new();
+                        self.mode = Mode::Default;
+                        if !self.line(&line, display_handle, file_handle) {
+                            return false;
+                        }
+                    }
+                    _ => self.prompt_edit(cmd, &unbound),
+                }
+            }
+            Mode::Search => {
+                let (origin_x, origin_y, prev_search) = self.search_origin.clone();
+                match cmd {
+                    CMD_BACKSPACE | CMD_CANCEL if cmd == CMD_CANCEL || self.search.is_empty() => {
+                        self.search = prev_search;
+                        self.move_center((origin_x, origin_y), display_handle, file_handle);
+                        self.buffer = String::new();
+                        self.mode = Mode::Default;
+                    }
+                    CMD_EXECUTE => {
+                        self.history += &self.buffer;
+                        self.buffer = String::new();
+                        self.mode = Mode::Default;
+                    }
+                    _ => {
+                        self.prompt_edit(cmd, &unbound);
+                        self.search = self.buffer[PROMPT_SEARCH_FORWARD.len()..].to_string();
+                        // incremental: move to the nearest match from the origin
+                        let pos = search::next(&file_handle.content, &self.search, (origin_x, origin_y), self.search_forward, false).unwrap_or((origin_x, origin_y));
+                        self.move_center(pos, display_handle, file_handle);
+                    }
+                }
+            }
+        }
+
+        // finish recording the editing cmd
+        if matches!(self.mode, Mode::Default)
+            && !self.repeating
+            && let Some(recording) = self.recording.take() {
+            self.last_edit = recording;
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(rows: &[&str], keys: &str) -> (Cmd, display::Display, file::File) {
+        let mut cmd = Cmd::new();
+        let mut display_handle = display::Display::new();
+        let mut file_handle = file::File::new(None);
+        file_handle.content = rows.iter().map(|row| row.to_string()).collect();
+        for c in keys.chars() {
+            let key = if c == '\u{1b}' { key::Key::new(key::Code::Escape) } else { key::Key::keys(&c.to_string())[0] };
+            cmd.key(key, &mut display_handle, &mut file_handle);
+        }
+        (cmd, display_handle, file_handle)
+    }
+
+    #[test]
+    fn prompt_cursor_edits() {
+        let mut cmd = Cmd::new();
+        let mut display_handle = display::Display::new();
+        let mut file_handle = file::File::new(None);
+        file_handle.content = vec!["x abc".to_string()];
+        let mut keys = |cmd: &mut Cmd, notation: &str| {
+            for key in key::Key::parse(notation).unwrap() {
+                cmd.key(key, &mut display_handle, &mut file_handle);
+            }
+        };
+        keys(&mut cmd, ":abc<Left><Left>X");
+        assert_eq!((cmd.buffer.as_str(), cmd.prompt_cursor()), (":aXbc", Some(3)));
+        keys(&mut cmd, "<End>Y<Home><Del>");
+        assert_eq!((cmd.buffer.as_str(), cmd.prompt_cursor()), (":XbcY", Some(1)));
+        keys(&mut cmd, "<Right><BS><BS>");
+        assert_eq!((cmd.buffer.as_str(), cmd.prompt_cursor()), (":bcY", Some(1)));
+        keys(&mut cmd, "<Esc>/ac<Left>b");
+        assert_eq!((cmd.buffer.as_str(), cmd.search.as_str()), ("/abc", "abc"));
+        keys(&mut cmd, "<Enter>");
+        assert_eq!(cmd.prompt_cursor(), None);
+        assert_eq!((display_handle.center_x, display_handle.center_y), (2, 0));
+    }
+
+    #[test]
+    fn expand_tab_to_tab_stop() {
+        let tab = |row: &str, keys: &str, expand_tab: bool| {
+            let mut cmd = Cmd::new();
+            let mut display_handle = display::Display::new();
+            let mut file_handle = file::File::new(None);
+            file_handle.content = vec![row.to_string()];
+            cmd.expand_tab = expand_tab;
+            for key in key::Key::parse(keys).unwrap() {
+                cmd.key(key, &mut display_handle, &mut file_handle);
+            }
+            file_handle.content[0].clone()
+        };
+        assert_eq!(tab("\t b", "kka<Tab><Esc>", true), "\t    b");
+        assert_eq!(tab("x  y", "kka<Tab><Esc>", true), "x    y");
+        assert_eq!(tab("  y", "kka<Tab><Tab><Esc>", true), "        y");
+        assert_eq!(tab("x  y", "kka<Tab><Esc>", false), "x \t y");
+    }
+
+    #[test]
+    fn edits_refresh_caches() {
+        let refresh = |rows: &[&str], keys: &str, pattern: &str| {
+            let mut cmd = Cmd::new();
+            let mut display_handle = display::Display::new();
+            let mut file_handle = file::File::new(None);
+            file_handle.content = rows.iter().map(|row| row.to_string()).collect();
+            // the caches are refreshed after every key like in the main loop
+            file_handle.words();
+            file_handle.update_matches(pattern);
+            for key in key::Key::parse(keys).unwrap() {
+                cmd.key(key, &mut display_handle, &mut file_handle);
+                file_handle.words();
+                file_handle.update_matches(pattern);
+            }
+            (file_handle.words().iter().cloned().collect::<Vec<_>>(), file_handle.matches(pattern).unwrap_or_default().len())
+        };
+        assert_eq!(refresh(&["ab cd"], "kkkrx", "x"), (vec!["ab".to_string(), "xd".to_string()], 1));
+        // the placeholder of append mode is a space
+        assert_eq!(refresh(&["ab", "cd"], "ja<BS>", "b c").1, 1);
+        assert_eq!(refresh(&["ab", "cd"], "kka<Del>", "b c").1, 1);
+        assert_eq!(refresh(&["a a"], ":s/a/b/gc<Enter>yy", "b").1, 2);
+        assert_eq!(refresh(&["a b"], "dd", "a"), (Vec::new(), 0));
+        assert_eq!(refresh(&["a b"], "ddu", "a"), (vec!["a".to_string(), "b".to_string()], 1));
+    }
+
+    #[test]
+    fn explorer_entry_cmds() {
+        let dir = std::env::temp_dir().join(format!("center_explorer_cmds_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let dir = dir.to_string_lossy().to_string();
+        let mut cmd = Cmd::new();
+        let mut display_handle = display::Display::new();
+        let mut file_handle = file::File::explorer(explorer::Kind::Directory(dir.clone()), dir.clone()).unwrap();
+        let mut line = |cmd: &mut Cmd, display_handle: &mut display::Display, notation: &str| {
+            for key in key::Key::parse(notation).unwrap() {
+                cmd.key(key, display_handle, &mut file_handle);
+            }
+            (cmd.message.clone(), file_handle.content.len())
+        };
+        let path = |name: &str| explorer::join(&dir, name);
+        assert_eq!(line(&mut cmd, &mut display_handle, ":delete<Enter>"), ("delete: no explorer entry at center".to_string(), 1));
+        assert_eq!(line(&mut cmd, &mut display_handle, ":create n.txt<Enter>"), (format!("created: {}", path("n.txt")), 2));
+        display_handle.center_y = 1;
+        assert_eq!(line(&mut cmd, &mut display_handle, ":rename m.txt<Enter>"), (format!("renamed: {} -> {}", path("n.txt"), path("m.txt")), 2));
+        assert!(fs::metadata(path("m.txt")).is_ok() && fs::metadata(path("n.txt")).is_err());
+        assert_eq!(line(&mut cmd, &mut display_handle, ":create m.txt<Enter>").1, 2);
+        assert!(cmd.message.starts_with("create: "));
+        assert_eq!(line(&mut cmd, &mut display_handle, ":delete<Enter>"), (format!("deleted: {}", path("m.txt")), 1));
+        assert_eq!(display_handle.center_y, 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn go_to_line_clamped() {
+        let (_, display_handle, _) = run(&["a", "  b", "c"], "2gg");
+        assert_eq!((display_handle.center_x, display_handle.center_y), (2, 1));
+        let (_, display_handle, _) = run(&["a", "  b", "c"], "99gg");
+        assert_eq!((display_handle.center_x, display_handle.center_y), (0, 2));
+        let (_, display_handle, _) = run(&["a", "  b", "c"], "jjgg");
+        assert_eq!((display_handle.center_x, display_handle.center_y), (0, 0));
+    }
+
+    #[test]
+    fn count_moves_center() {
+        let (_, display_handle, _) = run(&["a", "b", "c", "d"], "2j");
+        assert_eq!(display_handle.center_y, 2);
+        let (_, display_handle, _) = run(&["abcdef"], "4k");
+        assert_eq!(display_handle.center_x, 4);
+    }
+
+    #[test]
+    fn count_clamped() {
+        let (cmd, display_handle, _) = run(&["a", "b", "c"], "99999999999999999999j");
+        assert_eq!(display_handle.center_y, 2);
+        assert_eq!(cmd.count, 1);
+        let (_, display_handle, _) = run(&["abc"], "123456789k");
+        assert_eq!(display_handle.center_x, 3);
+    }
+
+    #[test]
+    fn count_repeats_edit() {
+        let (_, _, file_handle) = run(&["abcdef"], "3B");
+        assert_eq!(file_handle.content, ["def"]);
+        let (_, _, file_handle) = run(&["a", "b", "c", "d"], "2dd");
+        assert_eq!(file_handle.content, ["c", "d"]);
+    }
+
+    #[test]
+    fn dot_repeats_last_edit() {
+        let (_, _, file_handle) = run(&["abcdef"], "B.");
+        assert_eq!(file_handle.content, ["cdef"]);
+        let (_, _, file_handle) = run(&["abcdef"], "2B.");
+        assert_eq!(file_handle.content, ["ef"]);
+        let (_, _, file_handle) = run(&["", ""], "ax\u{1b}j.");
+        assert_eq!(file_handle.content, ["x", "x"]);
+    }
+
+    #[test]
+    fn nohlsearch_hides_until_next_search() {
+        let (cmd, _, _) = run(&["one", "two"], "/o\n:nohlsearch\n");
+        assert_eq!(cmd.highlight(), "");
+        let (cmd, display_handle, _) = run(&["one", "two"], "/o\n:nohlsearch\n;");
+        assert_eq!(cmd.highlight(), "o");
+        assert_eq!((display_handle.center_x, display_handle.center_y), (0, 0));
+    }
+
+    #[test]
+    fn substitute_saves_undo_on_replacement() {
+        let (_, _, file_handle) = run(&["abc"], "B:s/z/y/\nu");
+        assert_eq!(file_handle.content, ["abc"]);
+        let (_, _, file_handle) = run(&["abc"], "B:s/c/y/c\nqu");
+        assert_eq!(file_handle.content, ["abc"]);
+        let (_, _, file_handle) = run(&["abc"], "B:s/c/y/c\nyu");
+        assert_eq!(file_handle.content, ["bc"]);
+        let (_, _, file_handle) = run(&["abc"], "B:s/c/y/\nu");
+        assert_eq!(file_handle.content, ["bc"]);
+    }
+
+    #[test]
+    fn replace_at_row_end() {
+        let (_, _, file_handle) = run(&["ab"], "rx");
+        assert_eq!(file_handle.content, ["xb"]);
+        let (_, _, file_handle) = run(&[""], "rx");
+        assert_eq!(file_handle.content, ["x"]);
+        let (_, _, file_handle) = run(&["ab"], "kkrx");
+        assert_eq!(file_handle.content, ["abx"]);
+        let (_, _, file_handle) = run(&["ab"], "kkr\u{1b}");
+        assert_eq!(file_handle.content, ["ab"]);
+        let (_, _, file_handle) = run(&[""], "r\u{1b}");
+        assert_eq!(file_handle.content, [""]);
+    }
+
+    #[test]
+    fn dot_repeats_visual_operator() {
+        let (_, _, file_handle) = run(&["a", "b", "c", "d", "e"], "Vjd.");
+        assert_eq!(file_handle.content, ["e"]);
+        let (_, _, file_handle) = run(&["abcd", "efgh"], "vkUjk.");
+        assert_eq!(file_handle.content, ["ABcd", "eFGh"]);
+        let (_, _, file_handle) = run(&["ab", "cd"], "vcx\u{1b}jh.");
+        assert_eq!(file_handle.content, ["xb", "xd"]);
+        let (_, _, file_handle) = run(&["a", "b", "c", "d", "e"], "Vjd.u");
+        assert_eq!(file_handle.content, ["c", "d", "e"]);
+    }
+
+    #[test]
+    fn undo_restores_repeated_edit() {
+        let (_, _, file_handle) = run(&["abcdef"], "B.u");
+        assert_eq!(file_handle.content, ["bcdef"]);
+        let (_, _, file_handle) = run(&["abcdef"], "Bu");
+        assert_eq!(file_handle.content, ["abcdef"]);
     }
 }
@@ -0,0 +1,132 @@
+//! # CONTENT
+//! completion popup of append mode (words of open buffers and language server items)
+
+use {
+    std:: {
+        collections::HashSet,
+    },
+    crate:: {
+        files,
+    },
+};
+
+/// # CONTENT
+/// item count limit of a popup
+const MAX_ITEMS: usize = 50;
+
+/// # CONTENT
+/// completion item
+/// # FIELD
+/// - label: inserted text
+/// - detail: kind shown after the label ("buffer" or the server item detail)
+#[derive(Clone)]
+pub struct Item {
+    pub label: String,
+    pub detail: String,
+}
+
+/// # CONTENT
+/// completion popup at a word being typed
+/// # FIELD
+/// - start: pos (x, y) of the first char of the typed word
+/// - prefix: typed word
+/// - items: candidates (server items first, then buffer words)
+/// - selected: index of the highlighted item
+pub struct Popup {
+    pub start: (usize, usize),
+    pub prefix: String,
+    pub items: Vec<Item>,
+    pub selected: usize,
+}
+
+/// # CONTENT
+/// check if a char is part of a completed word
+/// # ARGUMENT
+/// - c: char
+/// # RETURN VALUE
+/// true for alphanumeric and '_'
+pub fn word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// # CONTENT
+/// get the word typed before a pos
+/// # ARGUMENT
+/// - row: row content
+/// - x: char index of the pos
+/// # RETURN VALUE
+/// (char index of the word start, word)
+pub fn prefix(row: &str, x: usize) -> (usize, String) {
+    let before: Vec<char> = row.chars().take(x).collect();
+    let len = before.iter().rev().take_while(|&&c| word_char(c)).count();
+    (before.len() - len, before[before.len() - len..].iter().collect())
+}
+
+impl Popup {
+    /// # CONTENT
+    /// init Popup with the words of open buffers starting with the word typed before a pos
+    /// # ARGUMENT
+    /// - files_handle: open buffers (their word indexes are updated)
+    /// - row: row content of the pos
+    /// - pos: (x, y) of the center
+    /// # RETURN VALUE
+    /// Some(popup): popup of the typed word (without items until a server adds some if no word matches)
+    /// None: no typed word
+    pub fn new(files_handle: &mut files::Files, row: &str, pos: (usize, usize)) -> Option<Self> {
+        let (x, y) = pos;
+        let (start, prefix) = prefix(row, x);
+        if prefix.is_empty() {
+            return None;
+        }
+        let mut seen = HashSet::new();
+        for i in 0..files_handle.len() {
+            let matching = files_handle.get_mut(i).words().range(prefix.clone()..).take_while(|word| word.starts_with(&prefix));
+            seen.extend(matching.filter(|word| word.len() > prefix.len()).cloned());
+        }
+        let mut words: Vec<String> = seen.into_iter().collect();
+        words.sort();
+        let mut popup = Self {
+            start: (start, y),
+            prefix,
+            items: Vec::new(),
+            selected: 0,
+        };
+        popup.add(words.into_iter().map(|label| Item { label, detail: "buffer".to_string() }).collect(), false);
+        Some(popup)
+    }
+
+    /// # CONTENT
+    /// add candidates matching the typed word (server items go before buffer words, duplicates are dropped)
+    /// # ARGUMENT
+    /// - items: candidates
+    /// - first: put them before the current candidates (server items)
+    /// # RETURN VALUE
+    /// none
+    pub fn add(&mut self, items: Vec<Item>, first: bool) {
+        let mut added: Vec<Item> = items.into_iter()
+            .filter(|item| item.label.to_lowercase().starts_with(&self.prefix.to_lowercase()) && item.label != self.prefix)
+            .collect();
+        if first {
+            self.items.retain(|item| !added.iter().any(|a| a.label == item.label));
+            added.append(&mut self.items);
+            self.items = added;
+        } else {
+            added.retain(|item| !self.items.iter().any(|i| i.label == item.label));
+            self.items.append(&mut added);
+        }
+        self.items.truncate(MAX_ITEMS);
+        self.selected = self.selected.min(self.items.len().saturating_sub(1));
+    }
+
+    /// # CONTENT
+    /// highlight another item (wrapping around)
+    /// # ARGUMENT
+    /// - step: items moved forward (negative for backward)
+    /// # RETURN VALUE
+    /// none
+    pub fn step(&mut self, step: isize) {
+        if !self.items.is_empty() {
+            self.selected = (self.selected as isize + step).rem_euclid(self.items.len() as isize) as usize;
+        }
+    }
+}
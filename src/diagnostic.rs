@@ -0,0 +1,199 @@
+//! # CONTENT
+//! diagnostics of a file (published by a language server, or parsed from build or linter output)
+
+use {
+    crate:: {
+        location,
+    },
+};
+
+/// # CONTENT
+/// source of diagnostics published by a language server
+pub const SOURCE_LSP: &str = "lsp";
+
+/// # CONTENT
+/// source of diagnostics parsed from ":make" output
+pub const SOURCE_MAKE: &str = "make";
+
+/// # CONTENT
+/// source of diagnostics parsed from ":lint" output
+pub const SOURCE_LINT: &str = "lint";
+
+/// # CONTENT
+/// linter run by ":lint" until ":lint_program" sets another one ('%' is replaced by the quoted file path)
+pub const DEFAULT_LINT_PROGRAM: &str = "cargo clippy";
+
+/// # CONTENT
+/// diagnostic severity (ordered from the most severe)
+#[derive(Clone, Copy, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Severity {
+    Error,
+    Warning,
+    Information,
+    Hint,
+}
+
+impl Severity {
+    /// # CONTENT
+    /// get Severity from the language server protocol number
+    /// # ARGUMENT
+    /// - n: 1 (error) to 4 (hint)
+    /// # RETURN VALUE
+    /// Severity (Error if missing or unknown)
+    pub fn from_number(n: Option<u64>) -> Self {
+        match n {
+            Some(2) => Self::Warning,
+            Some(3) => Self::Information,
+            Some(4) => Self::Hint,
+            _ => Self::Error,
+        }
+    }
+
+    /// # CONTENT
+    /// get Severity from the leading word of a build output message
+    /// # ARGUMENT
+    /// - text: message ("warning: ...", "note: ...", ...)
+    /// # RETURN VALUE
+    /// Severity (Error if no known word leads)
+    pub fn from_text(text: &str) -> Self {
+        let text = text.trim_start().to_lowercase();
+        if text.starts_with("warning") {
+            Self::Warning
+        } else if text.starts_with("note") || text.starts_with("info") {
+            Self::Information
+        } else if text.starts_with("help") || text.starts_with("hint") {
+            Self::Hint
+        } else {
+            Self::Error
+        }
+    }
+
+    /// # CONTENT
+    /// get the gutter mark
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// mark char
+    pub fn mark(&self) -> char {
+        match self {
+            Self::Error => 'E',
+            Self::Warning => 'W',
+            Self::Information => 'I',
+            Self::Hint => 'H',
+        }
+    }
+}
+
+/// # CONTENT
+/// diagnostic of a file
+/// # FIELD
+/// - severity: severity
+/// - start: first pos (x, y) of the range
+/// - end: pos (x, y) after the range
+/// - message: diagnostic message
+/// - source: producer replacing its own diagnostics (SOURCE_LSP, SOURCE_MAKE or SOURCE_LINT)
+#[derive(Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+    pub message: String,
+    pub source: &'static str,
+}
+
+/// # CONTENT
+/// convert parsed build output locations to diagnostics (the range is the char at the location)
+/// # ARGUMENT
+/// - locations: parsed locations
+/// - source: producer of the output
+/// # RETURN VALUE
+/// (path, diagnostic) in output order
+pub fn from_locations(locations: &[location::Location], source: &'static str) -> Vec<(String, Diagnostic)> {
+    locations.iter().map(|location| (location.path.clone(), Diagnostic {
+        severity: Severity::from_text(&location.text),
+        start: (location.x, location.y),
+        end: (location.x, location.y),
+        message: location.text.clone(),
+        source,
+    })).collect()
+}
+
+impl Diagnostic {
+    /// # CONTENT
+    /// check if a char is in the range (an empty range covers the char at its start)
+    /// # ARGUMENT
+    /// - x: char index
+    /// - y: row index
+    /// # RETURN VALUE
+    /// true if covered
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        if self.start == self.end {
+            return (x, y) == self.start;
+        }
+        (y, x) >= (self.start.1, self.start.0) && (y, x) < (self.end.1, self.end.0)
+    }
+
+    /// # CONTENT
+    /// check if the range is on a row (a range ending at the start of a row is not on it)
+    /// # ARGUMENT
+    /// - y: row index
+    /// # RETURN VALUE
+    /// true if on the row
+    pub fn on_row(&self, y: usize) -> bool {
+        self.start.1 <= y && (y < self.end.1 || y == self.end.1 && (self.end.0 > 0 || self.start.1 == y))
+    }
+
+    /// # CONTENT
+    /// get the first message row with the severity mark and source
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// "E source: message"
+    pub fn summary(&self) -> String {
+        format!("{} {}: {}", self.severity.mark(), self.source, self.message.lines().next().unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnostic(start: (usize, usize), end: (usize, usize)) -> Diagnostic {
+        Diagnostic { severity: Severity::Error, start, end, message: String::new(), source: SOURCE_MAKE }
+    }
+
+    #[test]
+    fn severity_from_text() {
+        assert!(Severity::from_text("  Warning: unused") == Severity::Warning);
+        assert!(Severity::from_text("note: here") == Severity::Information);
+        assert!(Severity::from_text("info") == Severity::Information);
+        assert!(Severity::from_text("help: try") == Severity::Hint);
+        assert!(Severity::from_text("error[E0308]: mismatched") == Severity::Error);
+        assert!(Severity::from_text("unknown") == Severity::Error);
+    }
+
+    #[test]
+    fn contains_range() {
+        let d = diagnostic((2, 1), (1, 3));
+        assert!(!d.contains(1, 1));
+        assert!(d.contains(2, 1));
+        assert!(d.contains(0, 2));
+        assert!(d.contains(0, 3));
+        assert!(!d.contains(1, 3));
+        let empty = diagnostic((4, 0), (4, 0));
+        assert!(empty.contains(4, 0));
+        assert!(!empty.contains(5, 0));
+    }
+
+    #[test]
+    fn on_rows() {
+        let d = diagnostic((2, 1), (0, 3));
+        assert!(!d.on_row(0));
+        assert!(d.on_row(1));
+        assert!(d.on_row(2));
+        assert!(!d.on_row(3));
+        let empty = diagnostic((0, 4), (0, 4));
+        assert!(empty.on_row(4));
+        assert!(!empty.on_row(5));
+    }
+}
@@ -4,10 +4,17 @@
 use {
     std:: {
         io,
+        ops,
     },
     crate:: {
         cmd,
+        completion,
+        diagnostic,
         file,
+        finder,
+        search,
+        syntax,
+        window,
     },
     crossterm:: {
         cursor,
@@ -17,11 +24,24 @@ use {
     },
 };
 
+/// # CONTENT
+/// row count limit of the completion popup
+const POPUP_ROWS: usize = 8;
+
+/// # CONTENT
+/// printed cell of (glyph, char index, whitespace mark or escaped char)
+type Cell = (char, usize, bool);
+
+/// # CONTENT
+/// printed code display row of (row index, cells, display row range, first display row of the row)
+type CodeRow = (usize, Vec<Cell>, ops::Range<usize>, bool);
+
 /// # CONTENT
 /// printed color handle
 /// # FIELD
 /// - background: background color
 /// - foreground: foreground color
+#[derive(Clone)]
 pub struct Color {
     background: style::Color,
     foreground: style::Color,
@@ -48,6 +68,7 @@ impl Color {
 /// # FIELD
 /// - text: text color
 /// - cursor: cursor color
+#[derive(Clone)]
 pub struct Theme {
     bar: Color,
     center: Color,
@@ -56,12 +77,49 @@ pub struct Theme {
     cmd: Color,
     code: Color,
     col_num: Color,
+    diagnostic_error: Color,
+    diagnostic_hint: Color,
+    diagnostic_info: Color,
+    diagnostic_warning: Color,
     file_path: Color,
     row_num: Color,
+    search: Color,
+    selection: Color,
     space: Color,
+    syntax_attribute: Color,
+    syntax_comment: Color,
+    syntax_constant: Color,
+    syntax_function: Color,
+    syntax_keyword: Color,
+    syntax_operator: Color,
+    syntax_property: Color,
+    syntax_string: Color,
+    syntax_type: Color,
+    tab_active: Color,
+    tab_inactive: Color,
+    whitespace: Color,
 }
 
 impl Theme {
+    /// # CONTENT
+    /// theme names accepted by Theme::from_name
+    pub const NAMES: &[&str] = &["one_dark", "one_light"];
+
+    /// # CONTENT
+    /// get Theme from name
+    /// # ARGUMENT
+    /// - name: theme name (one of Theme::NAMES)
+    /// # RETURN VALUE
+    /// Some(Theme): known theme
+    /// None: unknown theme
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "one_dark" => Some(Self::one_dark()),
+            "one_light" => Some(Self::one_light()),
+            _ => None,
+        }
+    }
+
     /// # CONTENT
     /// get one dark theme
     /// # ARGUMENT
@@ -100,6 +158,22 @@ impl Theme {
                 background: bg,
                 foreground: style::Color::Rgb {r: 0x63, g: 0x6D, b: 0x83},
             },
+            diagnostic_error: Color {
+                background: bg,
+                foreground: style::Color::Rgb {r: 0xE0, g: 0x6C, b: 0x75},
+            },
+            diagnostic_hint: Color {
+                background: bg,
+                foreground: style::Color::Rgb {r: 0x56, g: 0xB6, b: 0xC2},
+            },
+            diagnostic_info: Color {
+                background: bg,
+                foreground: style::Color::Rgb {r: 0x61, g: 0xAF, b: 0xEF},
+            },
+            diagnostic_warning: Color {
+                background: bg,
+                foreground: style::Color::Rgb {r: 0xE5, g: 0xC0, b: 0x7B},
+            },
             file_path: Color {
                 background: style::Color::Rgb {r: 0x21, g: 0x25, b: 0x2B},
                 foreground: style::Color::Rgb {r: 0x9D, g: 0xA5, b: 0xB4},
@@ -108,10 +182,66 @@ impl Theme {
                 background: bg,
                 foreground: style::Color::Rgb {r: 0x63, g: 0x6D, b: 0x83},
             },
+            search: Color {
+                background: style::Color::Rgb {r: 0xE5, g: 0xC0, b: 0x7B},
+                foreground: bg,
+            },
+            selection: Color {
+                background: style::Color::Rgb {r: 0x3E, g: 0x44, b: 0x51},
+                foreground: fg,
+            },
             space: Color {
                 background: style::Color::Rgb {r: 0x21, g: 0x25, b: 0x2B},
                 foreground: style::Color::Rgb {r: 0x21, g: 0x25, b: 0x2B},
             },
+            syntax_attribute: Color {
+                background: bg,
+                foreground: style::Color::Rgb {r: 0xE5, g: 0xC0, b: 0x7B},
+            },
+            syntax_comment: Color {
+                background: bg,
+                foreground: style::Color::Rgb {r: 0x5C, g: 0x63, b: 0x70},
+            },
+            syntax_constant: Color {
+                background: bg,
+                foreground: style::Color::Rgb {r: 0xD1, g: 0x9A, b: 0x66},
+            },
+            syntax_function: Color {
+                background: bg,
+                foreground: style::Color::Rgb {r: 0x61, g: 0xAF, b: 0xEF},
+            },
+            syntax_keyword: Color {
+                background: bg,
+                foreground: style::Color::Rgb {r: 0xC6, g: 0x78, b: 0xDD},
+            },
+            syntax_operator: Color {
+                background: bg,
+                foreground: style::Color::Rgb {r: 0x56, g: 0xB6, b: 0xC2},
+            },
+            syntax_property: Color {
+                background: bg,
+                foreground: style::Color::Rgb {r: 0xE0, g: 0x6C, b: 0x75},
+            },
+            syntax_string: Color {
+                background: bg,
+                foreground: style::Color::Rgb {r: 0x98, g: 0xC3, b: 0x79},
+            },
+            syntax_type: Color {
+                background: bg,
+                foreground: style::Color::Rgb {r: 0xE5, g: 0xC0, b: 0x7B},
+            },
+            tab_active: Color {
+                background: bg,
+                foreground: fg,
+            },
+            tab_inactive: Color {
+                background: style::Color::Rgb {r: 0x21, g: 0x25, b: 0x2B},
+                foreground: style::Color::Rgb {r: 0x63, g: 0x6D, b: 0x83},
+            },
+            whitespace: Color {
+                background: bg,
+                foreground: style::Color::Rgb {r: 0x4B, g: 0x52, b: 0x63},
+            },
         }
     }
 
@@ -153,6 +283,22 @@ impl Theme {
                 background: bg,
                 foreground: style::Color::Rgb {r: 0x9D, g: 0x9D, b: 0x9F},
             },
+            diagnostic_error: Color {
+                background: bg,
+                foreground: style::Color::Rgb {r: 0xE4, g: 0x56, b: 0x49},
+            },
+            diagnostic_hint: Color {
+                background: bg,
+                foreground: style::Color::Rgb {r: 0x01, g: 0x84, b: 0xBC},
+            },
+            diagnostic_info: Color {
+                background: bg,
+                foreground: style::Color::Rgb {r: 0x40, g: 0x78, b: 0xF2},
+            },
+            diagnostic_warning: Color {
+                background: bg,
+                foreground: style::Color::Rgb {r: 0xC1, g: 0x84, b: 0x01},
+            },
             file_path: Color {
                 background: style::Color::Rgb {r: 0xEA, g: 0xEA, b: 0xEB},
                 foreground: style::Color::Rgb {r: 0x42, g: 0x42, b: 0x43},
@@ -161,10 +307,126 @@ impl Theme {
                 background: bg,
                 foreground: style::Color::Rgb {r: 0x9D, g: 0x9D, b: 0x9F},
             },
+            search: Color {
+                background: style::Color::Rgb {r: 0xC1, g: 0x84, b: 0x01},
+                foreground: bg,
+            },
+            selection: Color {
+                background: style::Color::Rgb {r: 0xE5, g: 0xE5, b: 0xE6},
+                foreground: fg,
+            },
             space: Color {
                 background: style::Color::Rgb {r: 0xEA, g: 0xEA, b: 0xEB},
                 foreground: style::Color::Rgb {r: 0xEA, g: 0xEA, b: 0xEB},
             },
+            syntax_attribute: Color {
+                background: bg,
+                foreground: style::Color::Rgb {r: 0xC1, g: 0x84, b: 0x01},
+            },
+            syntax_comment: Color {
+                background: bg,
+                foreground: style::Color::Rgb {r: 0xA0, g: 0xA1, b: 0xA7},
+            },
+            syntax_constant: Color {
+                background: bg,
+                foreground: style::Color::Rgb {r: 0x98, g: 0x68, b: 0x01},
+            },
+            syntax_function: Color {
+                background: bg,
+                foreground: style::Color::Rgb {r: 0x40, g: 0x78, b: 0xF2},
+            },
+            syntax_keyword: Color {
+                background: bg,
+                foreground: style::Color::Rgb {r: 0xA6, g: 0x26, b: 0xA4},
+            },
+            syntax_operator: Color {
+                background: bg,
+                foreground: style::Color::Rgb {r: 0x01, g: 0x84, b: 0xBC},
+            },
+            syntax_property: Color {
+                background: bg,
+                foreground: style::Color::Rgb {r: 0xE4, g: 0x56, b: 0x49},
+            },
+            syntax_string: Color {
+                background: bg,
+                foreground: style::Color::Rgb {r: 0x50, g: 0xA1, b: 0x4F},
+            },
+            syntax_type: Color {
+                background: bg,
+                foreground: style::Color::Rgb {r: 0xC1, g: 0x84, b: 0x01},
+            },
+            tab_active: Color {
+                background: bg,
+                foreground: fg,
+            },
+            tab_inactive: Color {
+                background: style::Color::Rgb {r: 0xEA, g: 0xEA, b: 0xEB},
+                foreground: style::Color::Rgb {r: 0x9D, g: 0x9D, b: 0x9F},
+            },
+            whitespace: Color {
+                background: bg,
+                foreground: style::Color::Rgb {r: 0xC2, g: 0xC2, b: 0xC3},
+            },
+        }
+    }
+
+    /// # CONTENT
+    /// get diagnostic color (the foreground is the mark and underline color)
+    /// # ARGUMENT
+    /// - severity: diagnostic severity
+    /// # RETURN VALUE
+    /// Color of the severity
+    pub fn diagnostic(&self, severity: diagnostic::Severity) -> &Color {
+        match severity {
+            diagnostic::Severity::Error => &self.diagnostic_error,
+            diagnostic::Severity::Hint => &self.diagnostic_hint,
+            diagnostic::Severity::Information => &self.diagnostic_info,
+            diagnostic::Severity::Warning => &self.diagnostic_warning,
+        }
+    }
+
+    /// # CONTENT
+    /// get syntax highlight color
+    /// # ARGUMENT
+    /// - kind: highlight kind
+    /// # RETURN VALUE
+    /// Color of the kind
+    pub fn syntax(&self, kind: syntax::Kind) -> &Color {
+        match kind {
+            syntax::Kind::Attribute => &self.syntax_attribute,
+            syntax::Kind::Comment => &self.syntax_comment,
+            syntax::Kind::Constant => &self.syntax_constant,
+            syntax::Kind::Function => &self.syntax_function,
+            syntax::Kind::Keyword => &self.syntax_keyword,
+            syntax::Kind::Operator => &self.syntax_operator,
+            syntax::Kind::Property => &self.syntax_property,
+            syntax::Kind::String => &self.syntax_string,
+            syntax::Kind::Type => &self.syntax_type,
+        }
+    }
+}
+
+/// # CONTENT
+/// row number mode
+#[derive(Clone, Copy)]
+pub enum RowNum {
+    Absolute,
+    Hybrid,
+    Relative,
+}
+
+impl RowNum {
+    /// # CONTENT
+    /// get next mode (absolute -> relative -> hybrid)
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// next RowNum
+    pub fn next(self) -> Self {
+        match self {
+            Self::Absolute => Self::Relative,
+            Self::Hybrid => Self::Absolute,
+            Self::Relative => Self::Hybrid,
         }
     }
 }
@@ -172,17 +434,40 @@ impl Theme {
 /// # CONTENT
 /// display info
 /// # FIELD
-/// - col: display col
-/// - row: display row
+/// - left: first display col of the focused pane
+/// - top: first display row of the focused pane
+/// - col: display col of the focused pane
+/// - row: display row of the focused pane (without the cmd row)
+/// - term_col: terminal col
+/// - term_row: terminal row
 /// - center_x: center pos x
 /// - center_y: center pos y
+/// - show_whitespace: print whitespace marks
+/// - tab_width: display col count of a tab stop
 /// - theme: color theme
+/// - wrap: soft wrap long rows
+/// - wrap_move: move up/down by display row in soft wrap
+/// - row_num: row number mode
+/// - virtual_text: print the most severe diagnostic of a row after its end
+/// - sign: print a diagnostic mark col after the row numbers (set for a pane of a file with diagnostics)
+#[derive(Clone)]
 pub struct Display {
+    left: usize,
+    top: usize,
     col: usize,
     row: usize,
+    pub term_col: usize,
+    pub term_row: usize,
     pub center_x: usize,
     pub center_y: usize,
+    pub row_num: RowNum,
+    pub show_whitespace: bool,
+    pub tab_width: usize,
     pub theme: Theme,
+    pub wrap: bool,
+    pub wrap_move: bool,
+    pub virtual_text: bool,
+    sign: bool,
 }
 
 impl Display {
@@ -194,16 +479,118 @@ impl Display {
     /// inited Display
     pub fn new() -> Self {
         Self {
+            left: 0,
+            top: 0,
             col: 0,
             row: 0,
+            term_col: 0,
+            term_row: 0,
             center_x: 0,
             center_y: 0,
+            row_num: RowNum::Absolute,
+            show_whitespace: false,
+            tab_width: 4,
             theme: Theme::one_light(),
+            wrap: false,
+            wrap_move: false,
+            virtual_text: false,
+            sign: false,
+        }
+    }
+
+    /// # CONTENT
+    /// get printed glyphs of a char
+    /// # ARGUMENT
+    /// - c: char
+    /// - visual_x: display col where c starts
+    /// # RETURN VALUE
+    /// - glyphs: printed chars (one per display col)
+    /// - special: true if glyphs are whitespace marks or escaped control chars
+    fn glyph(&self, c: char, visual_x: usize) -> (Vec<char>, bool) {
+        match c {
+            '\t' => {
+                let width = self.tab_width - visual_x % self.tab_width;
+                let mut glyphs = vec![' '; width];
+                if self.show_whitespace {
+                    glyphs[0] = '→';
+                }
+                (glyphs, self.show_whitespace)
+            }
+            '\r' if self.show_whitespace => (vec!['␍'], true),
+            '\u{A0}' if self.show_whitespace => (vec!['⍽'], true),
+            '\0'..='\u{1F}' | '\u{7F}' => (vec!['^', (c as u8 ^ 0x40) as char], true),
+            '\u{80}'..='\u{9F}' => (format!("<{:02X}>", c as u32).chars().collect(), true),
+            _ => (vec![c], false),
+        }
+    }
+
+    /// # CONTENT
+    /// get display col width of a char
+    /// # ARGUMENT
+    /// - c: char
+    /// - visual_x: display col where c starts
+    /// # RETURN VALUE
+    /// display col width
+    fn char_width(&self, c: char, visual_x: usize) -> usize {
+        match c {
+            '\t' => self.tab_width - visual_x % self.tab_width,
+            '\r' | '\u{A0}' if self.show_whitespace => 1,
+            '\0'..='\u{1F}' | '\u{7F}' => 2,
+            '\u{80}'..='\u{9F}' => 4,
+            _ => 1,
         }
     }
 
     /// # CONTENT
-    /// update File::col and File::row
+    /// escape control chars of a str for printing
+    /// # ARGUMENT
+    /// - s: str
+    /// # RETURN VALUE
+    /// str without raw control chars
+    pub fn escape(&self, s: &str) -> String {
+        let mut escaped = String::new();
+        for c in s.chars() {
+            if c.is_control() {
+                escaped.extend(self.glyph(c, escaped.chars().count()).0);
+            } else {
+                escaped.push(c);
+            }
+        }
+        escaped
+    }
+
+    /// # CONTENT
+    /// convert char index to display col
+    /// # ARGUMENT
+    /// - row: row content
+    /// - x: char index
+    /// # RETURN VALUE
+    /// display col where the char starts
+    pub fn visual_x(&self, row: &str, x: usize) -> usize {
+        row.chars().take(x).fold(0, |visual_x, c| visual_x + self.char_width(c, visual_x))
+    }
+
+    /// # CONTENT
+    /// convert display col to char index
+    /// # ARGUMENT
+    /// - row: row content
+    /// - visual_x: display col
+    /// # RETURN VALUE
+    /// char index covering the display col (clamped to row length)
+    pub fn char_x(&self, row: &str, visual_x: usize) -> usize {
+        let mut col = 0;
+        for (x, c) in row.chars().enumerate() {
+            col += self.char_width(c, col);
+            if col > visual_x {
+                return x;
+            }
+        }
+        row.chars().count()
+    }
+
+    /// # CONTENT
+    /// update Display::term_col and Display::term_row
+    /// (the focused pane takes the whole display until Windows::fit)
     /// # ARGUMENT
     /// none
     /// # RETURN VALUE
@@ -211,37 +598,558 @@ impl Display {
     /// Err(...): error
     pub fn resize(&mut self) -> io::Result<()> {
         let (col, row) = terminal::size()?;
-        self.col = col as usize;
-        self.row = row as usize;
+        self.term_col = col as usize;
+        self.term_row = row as usize;
+        self.set_area(window::Rect { left: 0, top: 0, col: self.term_col, row: self.term_row.saturating_sub(1) });
+        Ok(())
+    }
+
+    /// # CONTENT
+    /// set the focused pane area
+    /// # ARGUMENT
+    /// - rect: pane area
+    /// # RETURN VALUE
+    /// none
+    pub fn set_area(&mut self, rect: window::Rect) {
+        self.left = rect.left;
+        self.top = rect.top;
+        self.col = rect.col;
+        self.row = rect.row;
+    }
+
+    /// # CONTENT
+    /// move to the start of a pane row
+    /// (raw mode does not return the cursor on '\n')
+    /// # ARGUMENT
+    /// - r: display row in the pane
+    /// # RETURN VALUE
+    /// Ok(()): ok
+    /// Err(...): error
+    fn move_to(&self, r: usize) -> io::Result<()> {
+        execute!(
+            io::stdout(),
+            cursor::MoveTo(self.left as u16, (self.top + r) as u16),
+        )
+    }
+
+    /// # CONTENT
+    /// get printed cells of a row
+    /// (wide glyphs are expanded to cells of the same char index)
+    /// # ARGUMENT
+    /// - content: row content
+    /// # RETURN VALUE
+    /// cells of (glyph, char index, special)
+    fn cells(&self, content: &str) -> Vec<Cell> {
+        let trailing_x = content.trim_end_matches(' ').chars().count();
+        let mut cells: Vec<Cell> = Vec::new();
+        for (x, c) in content.chars().enumerate() {
+            if c == ' ' && x >= trailing_x && self.show_whitespace {
+                cells.push(('·', x, true));
+            } else {
+                let (glyphs, special) = self.glyph(c, cells.len());
+                for glyph in glyphs {
+                    cells.push((glyph, x, special));
+                }
+            }
+        }
+        cells
+    }
+
+    /// # CONTENT
+    /// get cell index of the center
+    /// # ARGUMENT
+    /// - cells: cells of the center row
+    /// # RETURN VALUE
+    /// cell index (cells.len() if the center is at the end of row)
+    fn center_cell(&self, cells: &[Cell]) -> usize {
+        cells.iter().position(|&(_, x, _)| x == self.center_x).unwrap_or(cells.len())
+    }
+
+    /// # CONTENT
+    /// get printed row number
+    /// # ARGUMENT
+    /// - y: row index
+    /// # RETURN VALUE
+    /// row number of Display::row_num mode
+    fn row_num(&self, y: usize) -> usize {
+        match self.row_num {
+            RowNum::Absolute => y + 1,
+            RowNum::Hybrid if y == self.center_y => y + 1,
+            RowNum::Hybrid | RowNum::Relative => y.abs_diff(self.center_y),
+        }
+    }
+
+    /// # CONTENT
+    /// get code display row count (moved by a page cmd)
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// code display row count
+    pub fn code_rows(&self) -> usize {
+        self.row.saturating_sub(3).max(1)
+    }
+
+    /// # CONTENT
+    /// get row number width
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// width of the widest row number on display (and the mark col)
+    fn code_left(&self) -> usize {
+        let bottom = self.center_y + self.code_rows() / 2;
+        let width = match self.row_num {
+            RowNum::Absolute | RowNum::Relative => self.row_num(bottom).to_string().len(),
+            RowNum::Hybrid => self.row_num(bottom).to_string().len().max((self.center_y + 1).to_string().len()),
+        };
+        width + usize::from(self.sign)
+    }
+
+    /// # CONTENT
+    /// get code col count (display col without row number)
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// code col count
+    fn code_col(&self) -> usize {
+        self.col.saturating_sub(self.code_left()).max(1)
+    }
+
+    /// # CONTENT
+    /// split cells into soft wrapped display rows at word boundaries
+    /// # ARGUMENT
+    /// - content: row content
+    /// - cells: cells of the row
+    /// - width: display row width
+    /// # RETURN VALUE
+    /// cell ranges of display rows (the last one includes the end of row)
+    /// (without a word boundary, a row breaks before the glyph that does not fit unless the glyph is wider than a display row)
+    fn wrap(&self, content: &str, cells: &[Cell], width: usize) -> Vec<ops::Range<usize>> {
+        let chars: Vec<char> = content.chars().collect();
+        let mut rows = Vec::new();
+        let mut start = 0;
+        while cells.len() + 1 - start > width {
+            let end = (start + 1..start + width).rev()
+                .find(|&i| chars[cells[i - 1].1].is_whitespace() && cells[i].1 != cells[i - 1].1)
+                .unwrap_or_else(|| match cells.get(start + width) {
+                    Some(&(_, x, _)) => (start..start + width).find(|&i| cells[i].1 == x).filter(|&i| i > start).unwrap_or(start + width),
+                    None => start + width,
+                });
+            rows.push(start..end);
+            start = end;
+        }
+        rows.push(start..cells.len() + 1);
+        rows
+    }
+
+    /// # CONTENT
+    /// move center by a soft wrapped display row
+    /// # ARGUMENT
+    /// - content: file content
+    /// - down: true: next display row, false: previous display row
+    /// # RETURN VALUE
+    /// none
+    pub fn wrap_move(&mut self, content: &[String], down: bool) {
+        let width = self.code_col();
+        let cells = self.cells(&content[self.center_y]);
+        let rows = self.wrap(&content[self.center_y], &cells, width);
+        let center = self.center_cell(&cells);
+        let k = rows.iter().position(|r| r.contains(&center)).unwrap_or(0);
+        let offset = center - rows[k].start;
+        let (y, row) = if down && k + 1 < rows.len() {
+            (self.center_y, rows[k + 1].clone())
+        } else if down && self.center_y + 1 < content.len() {
+            let y = self.center_y + 1;
+            (y, self.wrap(&content[y], &self.cells(&content[y]), width)[0].clone())
+        } else if !down && k > 0 {
+            (self.center_y, rows[k - 1].clone())
+        } else if !down && self.center_y > 0 {
+            let y = self.center_y - 1;
+            (y, self.wrap(&content[y], &self.cells(&content[y]), width).pop().unwrap_or_default())
+        } else {
+            return;
+        };
+        let cells = if y == self.center_y { cells } else { self.cells(&content[y]) };
+        let i = (row.start + offset).min(row.end - 1);
+        self.center_y = y;
+        self.center_x = cells.get(i).map(|&(_, x, _)| x).unwrap_or(content[y].chars().count());
+    }
+
+    /// # CONTENT
+    /// print a code display row
+    /// # ARGUMENT
+    /// - file_handle: printed file
+    /// - marks: highlighted search pattern and selected char range of the row
+    /// - row: (row index, cells, display row range, first display row of the row)
+    /// - left: cell index printed at the left end (negative: left space)
+    /// - code_left: row number width
+    /// - code_col: code col count
+    /// # RETURN VALUE
+    /// Ok(()): ok
+    /// Err(...): error
+    fn print_code_row(&self, file_handle: &file::File, marks: (&str, Option<ops::Range<usize>>), row: (usize, &[Cell], ops::Range<usize>, bool), left: isize, code_left: usize, code_col: usize) -> io::Result<()> {
+        let (y, cells, range, first) = row;
+
+        // row number
+        let number_left = code_left - usize::from(self.sign);
+        if first && self.center_y == y {
+            self.theme.center_row.set()?;
+            print!("{:>width$}", self.row_num(y), width = number_left);
+        } else if first {
+            self.theme.row_num.set()?;
+            print!("{:>width$}", self.row_num(y), width = number_left);
+        } else {
+            self.theme.row_num.set()?;
+            print!("{:>width$}", "↪", width = number_left);
+        }
+
+        // diagnostic mark of the most severe diagnostic on the row
+        if self.sign {
+            let severity = file_handle.diagnostics.iter().filter(|d| first && d.on_row(y)).map(|d| d.severity).min();
+            match severity {
+                Some(severity) => {
+                    self.theme.diagnostic(severity).set()?;
+                    print!("{}", severity.mark());
+                }
+                None => print!(" "),
+            }
+        }
+
+        // code
+        let mut color = &self.theme.row_num;
+        let mut underline = None;
+        for (c, next_color, next_underline) in self.code_cells(file_handle, marks, (y, cells, range), left, code_col) {
+            if next_underline != underline {
+                underline = next_underline;
+                match underline {
+                    Some(severity) => execute!(
+                        io::stdout(),
+                        style::SetUnderlineColor(self.theme.diagnostic(severity).foreground),
+                        style::SetAttribute(style::Attribute::Underlined),
+                    )?,
+                    None => execute!(io::stdout(), style::SetAttribute(style::Attribute::NoUnderline))?,
+                }
+            }
+            if !std::ptr::eq(color, next_color) {
+                color = next_color;
+                color.set()?;
+            }
+            print!("{}", c);
+        }
+        if underline.is_some() {
+            execute!(io::stdout(), style::SetAttribute(style::Attribute::NoUnderline))?;
+        }
+        Ok(())
+    }
+
+    /// # CONTENT
+    /// get printed cells of a code display row
+    /// (the virtual text of the most severe diagnostic follows the end of the last display row)
+    /// # ARGUMENT
+    /// - file_handle: printed file
+    /// - marks: highlighted search pattern and selected char range of the row
+    /// - row: (row index, cells, display row range)
+    /// - left: cell index printed at the left end (negative: left space)
+    /// - code_col: code col count
+    /// # RETURN VALUE
+    /// (printed char, color, severity of the underline) of each code col
+    fn code_cells(&self, file_handle: &file::File, marks: (&str, Option<ops::Range<usize>>), row: (usize, &[Cell], ops::Range<usize>), left: isize, code_col: usize) -> Vec<(char, &Color, Option<diagnostic::Severity>)> {
+        let (y, cells, range) = row;
+        let (search, selected) = marks;
+        let center = if self.center_y == y { Some(self.center_cell(cells)) } else { None };
+        let virtual_text = file_handle.diagnostics.iter()
+            .filter(|d| self.virtual_text && range.contains(&cells.len()) && d.on_row(y))
+            .min_by_key(|d| d.severity)
+            .map(|d| (self.escape(&d.summary()).chars().collect::<Vec<char>>(), self.theme.diagnostic(d.severity)));
+        let virtual_left = cells.len() as isize + 2;
+        let matches = search::find(&file_handle.content[y], search);
+        let mut printed = Vec::new();
+        for x in left..left + code_col as isize {
+            let cell = if x < 0 || !range.contains(&(x as usize)) { None } else { cells.get(x as usize) };
+            let underline = cell.and_then(|&(_, char_x, _)| file_handle.diagnostics.iter().filter(|d| d.contains(char_x, y)).map(|d| d.severity).min());
+            let virtual_cell = virtual_text.as_ref()
+                .and_then(|(text, color)| usize::try_from(x - virtual_left).ok().and_then(|i| text.get(i)).map(|&c| (c, *color)));
+            let color = if x < 0 {
+                &self.theme.space
+            } else if let Some((_, virtual_color)) = virtual_cell {
+                virtual_color
+            } else if center.is_some() && match cell {
+                Some(&(_, char_x, _)) => char_x == self.center_x,
+                None => Some(x as usize) == center && range.contains(&(x as usize)),
+            } {
+                &self.theme.center
+            } else if selected.as_ref().is_some_and(|selected| match cell {
+                Some(&(_, char_x, _)) => selected.contains(&char_x),
+                None => x as usize == cells.len() && selected.contains(&file_handle.row_len(y)),
+            }) {
+                &self.theme.selection
+            } else if cell.is_some_and(|&(_, char_x, _)| matches.iter().any(|m| m.contains(&char_x))) {
+                &self.theme.search
+            } else if let Some(&(_, _, true)) = cell {
+                &self.theme.whitespace
+            } else {
+                match cell.and_then(|&(_, char_x, _)| file_handle.syntax.kind(char_x, y)) {
+                    Some(kind) => self.theme.syntax(kind),
+                    None => &self.theme.code,
+                }
+            };
+            printed.push((cell.map(|&(c, _, _)| c).or(virtual_cell.map(|(c, _)| c)).unwrap_or(' '), color, underline));
+        }
+        printed
+    }
+
+    /// # CONTENT
+    /// print the completion popup over the code rows
+    /// # ARGUMENT
+    /// - popup: completion popup
+    /// - center: (display row of the center, code display row count)
+    /// - left: display col of the typed word (labels are aligned with it)
+    /// # RETURN VALUE
+    /// Ok(()): ok
+    /// Err(...): error
+    fn print_popup(&self, popup: &completion::Popup, center: (usize, usize), left: usize) -> io::Result<()> {
+        let (center_row, code_rows) = center;
+        let (above, below) = (center_row - 2, code_rows - (center_row - 2) - 1);
+        let rows = popup.items.len().min(POPUP_ROWS);
+        let (top, rows) = if below >= rows || below >= above { (center_row + 1, rows.min(below)) } else { (center_row - rows.min(above), rows.min(above)) };
+        let items: Vec<(String, String)> = popup.items.iter().map(|item| (self.escape(&item.label), self.escape(&item.detail))).collect();
+        let width = items.iter().map(|(label, detail)| label.chars().count() + detail.chars().count() + 3).max().unwrap_or(0).min(self.col);
+        let left = left.saturating_sub(1).min(self.col - width);
+        let first = (popup.selected + 1).saturating_sub(rows);
+        for (r, (i, (label, detail))) in items.iter().enumerate().skip(first).take(rows).enumerate() {
+            execute!(
+                io::stdout(),
+                cursor::MoveTo((self.left + left) as u16, (self.top + top + r) as u16),
+            )?;
+            if i == popup.selected {
+                self.theme.center.set()?;
+            } else {
+                self.theme.bar.set()?;
+            }
+            let text = format!(" {:<pad$} {} ", label, detail, pad = width.saturating_sub(detail.chars().count() + 3));
+            print!("{}", text.chars().take(width).collect::<String>());
+        }
+        Ok(())
+    }
+
+    /// # CONTENT
+    /// print the fuzzy finder over the panes (ranked paths on the left, preview of the highlighted one on the right)
+    /// # ARGUMENT
+    /// - finder_handle: running finder
+    /// # RETURN VALUE
+    /// Ok(()): ok
+    /// Err(...): error
+    fn print_finder(&self, finder_handle: &finder::Finder) -> io::Result<()> {
+        let rows = self.term_row - 1;
+        let list_col = self.term_col / 2;
+        let preview_col = self.term_col.saturating_sub(list_col + 1);
+        let fit = |s: &str, width: usize| {
+            let s: String = s.chars().take(width).collect();
+            format!("{}{}", s, " ".repeat(width - s.chars().count()))
+        };
+        let offset = (finder_handle.selected + 2).saturating_sub(rows);
+        let preview = finder_handle.preview(rows.saturating_sub(1));
+        for r in 0..rows {
+            execute!(
+                io::stdout(),
+                cursor::MoveTo(0, r as u16),
+            )?;
+
+            // ranked paths (matched chars highlighted)
+            if r == 0 {
+                self.theme.bar.set()?;
+                print!("{}", fit(&format!("{}/{} files", finder_handle.matches.len(), finder_handle.len()), list_col));
+            } else if let Some((path, matched)) = finder_handle.matches.get(offset + r - 1) {
+                let base = if offset + r - 1 == finder_handle.selected { &self.theme.selection } else { &self.theme.code };
+                let mut color = base;
+                color.set()?;
+                for (x, c) in fit(&self.escape(path), list_col).chars().enumerate() {
+                    let next_color = if matched.contains(&x) { &self.theme.search } else { base };
+                    if !std::ptr::eq(color, next_color) {
+                        color = next_color;
+                        color.set()?;
+                    }
+                    print!("{}", c);
+                }
+            } else {
+                self.theme.code.set()?;
+                print!("{}", fit("", list_col));
+            }
+            self.theme.bar.set()?;
+            print!("│");
+
+            // preview
+            if r == 0 {
+                self.theme.file_path.set()?;
+                print!("{}", fit(&self.escape(&finder_handle.path().unwrap_or_default()), preview_col));
+            } else {
+                self.theme.code.set()?;
+                print!("{}", fit(&self.escape(preview.get(r - 1).map_or("", |row| row.as_str())), preview_col));
+            }
+        }
         Ok(())
     }
 
     /// # CONTENT
     /// print display
     /// # ARGUMENT
-    /// file_handle: print file content
+    /// - cmd_handle: cmd (message, search and selection)
+    /// - windows: window panes
     /// # RETURN VALUE
     /// Ok(()): ok
     /// Err(...): error
-    pub fn print(&self, cmd_handle: &cmd::Cmd, file_handle: &file::File) -> io::Result<()> {
-        let file_content = file_handle.get_content();
-        let code_top_num = self.center_y as isize - (self.row as isize - 4) / 2 + if (self.row - 4) % 2 == 0 { 1 } else { 0 };
-        let code_bottom_num = self.center_y as isize + (self.row as isize - 4) / 2;
-        let code_left = code_bottom_num.to_string().len();
-        let code_right = self.col;
-        let code_top = 2;
-        let code_bottom = self.row - 2;
-        let code_col = code_right - code_left;
-        #[allow(unused_variables)]
-        let code_row = code_bottom - code_top;
-        let code_left_num = self.center_x as isize - code_col as isize / 2 + if code_col % 2 == 0 { 1 } else { 0 };
-        let code_right_num = self.center_x as isize + code_col as isize / 2;
+    pub fn print(&self, cmd_handle: &cmd::Cmd, windows: &window::Windows) -> io::Result<()> {
         execute!(
             io::stdout(),
             terminal::Clear(terminal::ClearType::All),
             cursor::MoveTo(0, 0),
         )?;
-        if self.row < 5 {
+        if self.term_row < 2 {
+            return Ok(());
+        }
+
+        // tab line (only with several tabs; the rest of the row is filled as an inactive tab)
+        let tabs = windows.tabs();
+        if !tabs.is_empty() {
+            let mut width = 0;
+            for (label, current) in tabs {
+                let label: String = format!(" {} ", self.escape(&label)).chars().take(self.term_col - width).collect();
+                if current {
+                    self.theme.tab_active.set()?;
+                } else {
+                    self.theme.tab_inactive.set()?;
+                }
+                width += label.chars().count();
+                print!("{}", label);
+            }
+            self.theme.tab_inactive.set()?;
+            print!("{}", " ".repeat(self.term_col - width));
+        }
+
+        // panes
+        let (views, separators) = windows.views(self);
+        for (rect, file_handle, (x, y), focused) in views {
+            let mut view = self.clone();
+            view.set_area(rect);
+            view.center_y = y.min(file_handle.content.len() - 1);
+            view.center_x = x.min(file_handle.content[view.center_y].chars().count());
+            view.sign = !file_handle.diagnostics.is_empty();
+            view.print_pane(cmd_handle, file_handle, focused)?;
+        }
+        self.theme.bar.set()?;
+        for separator in separators {
+            for r in 0..separator.row {
+                execute!(
+                    io::stdout(),
+                    cursor::MoveTo(separator.left as u16, (separator.top + r) as u16),
+                )?;
+                print!("│");
+            }
+        }
+
+        if let Some(finder) = &cmd_handle.finder {
+            self.print_finder(finder)?;
+        }
+
+        // a multi-row message (e.g. ":map") covers the bottom rows
+        let message_max = self.term_row.saturating_sub(3).max(1);
+        let mut message_rows: Vec<String> = if cmd_handle.message.contains('\n') {
+            cmd_handle.message.lines().map(|m| self.escape(m)).collect()
+        } else {
+            Vec::new()
+        };
+        if message_rows.len() > message_max {
+            let more = message_rows.len() - message_max + 1;
+            message_rows.truncate(message_max - 1);
+            message_rows.push(format!("({} more)", more));
+        }
+        self.theme.cmd.set()?;
+        let message_top = self.term_row - 1 - message_rows.len();
+        for (i, message_row) in message_rows.iter().enumerate() {
+            execute!(
+                io::stdout(),
+                cursor::MoveTo(0, (message_top + i) as u16),
+            )?;
+            let text: String = message_row.chars().take(self.term_col).collect();
+            print!("{}{}", text, " ".repeat(self.term_col - text.chars().count()));
+        }
+
+        // cmd
+        execute!(
+            io::stdout(),
+            cursor::MoveTo(0, (self.term_row - 1) as u16),
+        )?;
+        let message = if cmd_handle.message.contains('\n') { "" } else { cmd_handle.message.as_str() };
+        let cmd_text = match (cmd_handle.buffer.is_empty(), message.is_empty()) {
+            (true, _) => message.to_string(),
+            (false, true) => cmd_handle.buffer.clone(),
+            (false, false) => format!("{}  ({})", cmd_handle.buffer, message),
+        };
+        let cmd_buffer: Vec<char> = self.escape(&cmd_text).chars().collect();
+        print!("{}", if cmd_buffer.len() <= self.term_col {
+            let mut _c: String = cmd_buffer.iter().collect();
+            for _ in cmd_buffer.len()..self.term_col {
+                _c.push(' ');
+            }
+            _c
+        } else {
+            format!("..{}", cmd_buffer[cmd_buffer.len() - self.term_col + 2..].iter().collect::<String>())
+        });
+        if let Some(x) = cmd_handle.prompt_cursor() {
+            // cursor of the cmd line or search prompt (reversed char)
+            let start = if cmd_buffer.len() <= self.term_col { 0 } else { cmd_buffer.len() - self.term_col + 2 };
+            if x >= start && x < cmd_buffer.len() {
+                let col = if start == 0 { x } else { x - start + 2 };
+                execute!(
+                    io::stdout(),
+                    cursor::MoveTo(col as u16, (self.term_row - 1) as u16),
+                    style::SetAttribute(style::Attribute::Reverse),
+                )?;
+                print!("{}", cmd_buffer[x]);
+                execute!(io::stdout(), style::SetAttribute(style::Attribute::NoReverse))?;
+            }
+        }
+
+        execute!(
+            io::stdout(),
+            cursor::MoveTo(0, 0),
+        )?;
+        Ok(())
+    }
+
+    /// # CONTENT
+    /// print a pane (path row, col numbers, code rows and bar)
+    /// # ARGUMENT
+    /// - cmd_handle: cmd (search and selection)
+    /// - file_handle: printed file
+    /// - focused: the pane is focused (selection and bar color)
+    /// # RETURN VALUE
+    /// Ok(()): ok
+    /// Err(...): error
+    fn print_pane(&self, cmd_handle: &cmd::Cmd, file_handle: &file::File, focused: bool) -> io::Result<()> {
+        let file_content = file_handle.get_content();
+        let code_rows = self.code_rows();
+        let code_top_num = self.center_y as isize - code_rows as isize / 2 + if code_rows.is_multiple_of(2) { 1 } else { 0 };
+        let code_bottom_num = self.center_y as isize + code_rows as isize / 2;
+        let code_left = self.code_left();
+        let code_col = self.code_col();
+        let center_visual_x = match file_content.get(self.center_y) {
+            Some(row) if self.wrap => {
+                let cells = self.cells(row);
+                let center = self.center_cell(&cells);
+                center - self.wrap(row, &cells, code_col).into_iter().find(|r| r.contains(&center)).unwrap_or_default().start
+            }
+            Some(row) => self.visual_x(row, self.center_x),
+            None => self.center_x,
+        };
+        let (code_left_num, code_right_num) = if self.wrap {
+            (0, code_col as isize - 1)
+        } else {
+            (
+                center_visual_x as isize - code_col as isize / 2 + if code_col.is_multiple_of(2) { 1 } else { 0 },
+                center_visual_x as isize + code_col as isize / 2,
+            )
+        };
+        if self.row < 4 || self.col <= code_left {
             return Ok(());
         }
         if self.center_y >= file_content.len() {
@@ -249,34 +1157,35 @@ impl Display {
         }
 
         // file path
-        let file_path = file_handle.path.clone().unwrap_or_default();
+        self.move_to(0)?;
+        let file_path: Vec<char> = self.escape(&file_handle.path.clone().unwrap_or_default()).chars().collect();
         self.theme.file_path.set()?;
         if file_path.len() <= self.col {
-            print!("{}", file_path);
+            print!("{}", file_path.iter().collect::<String>());
             for _ in file_path.len()..self.col {
                 print!(" ");
             }
-            println!("");
         } else {
-            println!("..{}", &file_path[file_path.len() - self.col + 2..]);
+            print!("..{}", file_path[file_path.len() - self.col + 2..].iter().collect::<String>());
         }
 
         // col number
+        self.move_to(1)?;
         self.theme.col_num.set()?;
         for _ in 0..code_left {
             print!(" ");
         }
         if code_left_num <= 0 {
-            for _ in 0..code_left_num.abs() as usize {
+            for _ in 0..code_left_num.unsigned_abs() {
                 print!(" ");
             }
             print!("    "); // skip 0~3
             for i in 1..code_right_num / 5 {
-                if i * 5 - 1 == self.center_x as isize {
+                if i * 5 - 1 == center_visual_x as isize {
                     self.theme.center_col.set()?;
                 }
                 print!("{:<5}", i * 5);
-                if i * 5 - 1 == self.center_x as isize {
+                if i * 5 - 1 == center_visual_x as isize {
                     self.theme.col_num.set()?;
                 }
             }
@@ -285,11 +1194,11 @@ impl Display {
                 print!(" ");
             }
             for i in code_left_num / 5 + 1..code_right_num / 5 {
-                if i * 5 - 1 == self.center_x as isize {
+                if i * 5 - 1 == center_visual_x as isize {
                     self.theme.center_col.set()?;
                 }
                 print!("{:<5}", i * 5);
-                if i * 5 - 1 == self.center_x as isize {
+                if i * 5 - 1 == center_visual_x as isize {
                     self.theme.col_num.set()?;
                 }
             }
@@ -298,139 +1207,340 @@ impl Display {
         for _ in 0..code_right_num as usize % 5 {
             print!(" ");
         }
-        println!("");
 
-        // top space
-        if code_top_num < 0 {
-            for _ in 0..code_top_num.abs() {
-                self.theme.row_num.set()?;
-                for _ in 0..code_left {
-                    print!(" ");
+        // code rows
+        let code_above = (code_rows - 1) / 2;
+        let code_below = code_rows / 2;
+        let mut rows: Vec<Option<CodeRow>> = Vec::new();
+        if self.wrap {
+            // display rows above the center (reversed), the center, and below
+            let cells = self.cells(&file_content[self.center_y]);
+            let wrapped = self.wrap(&file_content[self.center_y], &cells, code_col);
+            let center = self.center_cell(&cells);
+            let k = wrapped.iter().position(|r| r.contains(&center)).unwrap_or(0);
+            let mut above: Vec<_> = wrapped[..k].iter().rev().map(|r| Some((self.center_y, cells.clone(), r.clone(), r.start == 0))).collect();
+            let mut below: Vec<_> = wrapped[k + 1..].iter().map(|r| Some((self.center_y, cells.clone(), r.clone(), false))).collect();
+            let mut y = self.center_y;
+            while above.len() < code_above && y > 0 {
+                y -= 1;
+                let cells = self.cells(&file_content[y]);
+                for r in self.wrap(&file_content[y], &cells, code_col).into_iter().rev() {
+                    above.push(Some((y, cells.clone(), r.clone(), r.start == 0)));
                 }
-                self.theme.space.set()?;
-                for _ in 0..self.col - code_left {
-                    print!(" ");
-                }
-                println!("");
-            }
-        }
-
-        let mut i: usize = 0;
-        for content in file_content {
-            if i > code_bottom_num as usize {
-                break;
-            }
-
-            // row number
-            if self.center_y == if code_top_num < 0 { i } else { code_top_num as usize + i } {
-                self.theme.center_row.set()?;
-            } else {
-                self.theme.row_num.set()?;
             }
-            print!("{:>width$}", if code_top_num < 0 { i } else { code_top_num as usize + i } + 1, width = code_left);
-
-            // left space
-            self.theme.space.set()?;
-            if code_left_num < 0 {
-                for _ in 0..code_left_num.abs() as usize {
-                    print!(" ");
+            let mut y = self.center_y;
+            while below.len() < code_below && y + 1 < file_content.len() {
+                y += 1;
+                let cells = self.cells(&file_content[y]);
+                for r in self.wrap(&file_content[y], &cells, code_col) {
+                    below.push(Some((y, cells.clone(), r.clone(), r.start == 0)));
                 }
             }
-
-            // code
-            let print_str = if code_left_num < 0 {
-                if content.len() < code_col - code_left_num.abs() as usize {
-                    let mut _c = content.clone();
-                    for _ in content.len()..code_col - code_left_num.abs() as usize {
-                        _c.push(' ');
-                    }
-                    _c
+            above.resize(code_above, None);
+            below.resize(code_below, None);
+            rows.extend(above.into_iter().rev());
+            rows.push(Some((self.center_y, cells, wrapped[k].clone(), k == 0)));
+            rows.extend(below);
+        } else {
+            for y in code_top_num..=code_bottom_num {
+                rows.push(if y < 0 || y as usize >= file_content.len() {
+                    None
                 } else {
-                    content[..code_col - code_left_num.abs() as usize].to_string()
+                    let cells = self.cells(&file_content[y as usize]);
+                    let range = 0..cells.len() + 1;
+                    Some((y as usize, cells, range, true))
+                });
+            }
+        }
+        if self.center_x > file_content[self.center_y].chars().count() {
+            panic!("center_x requested access outside the range");
+        }
+        let selection = if focused { cmd_handle.selection(self, file_handle) } else { None };
+        for (i, row) in rows.into_iter().enumerate() {
+            self.move_to(2 + i)?;
+            match row {
+                Some((y, cells, range, first)) => {
+                    let left = if self.wrap { range.start as isize } else { code_left_num };
+                    let selected = selection.as_ref().and_then(|selection| selection.range(file_content, y, self));
+                    self.print_code_row(file_handle, (cmd_handle.highlight(), selected), (y, &cells, range, first), left, code_left, code_col)?;
                 }
-            } else {
-                if content.len() < code_right_num as usize {
-                    let mut _c = content[code_left_num as usize..].to_string();
-                    for _ in content.len()..code_right_num as usize {
-                        _c.push(' ');
+                None => {
+                    self.theme.row_num.set()?;
+                    for _ in 0..code_left {
+                        print!(" ");
+                    }
+                    self.theme.space.set()?;
+                    for _ in code_left..self.col {
+                        print!(" ");
                     }
-                    _c
-                } else {
-                    content[code_left_num as usize..code_right_num as usize].to_string()
-                }
-            };
-            if self.center_y == if code_top_num < 0 { i } else { code_top_num as usize + i } {
-                // center
-                if self.center_x < content.len() {
-                    self.theme.code.set()?;
-                    print!("{}", &print_str[..self.center_x]);
-                    self.theme.center.set()?;
-                    print!("{}", &print_str[self.center_x..self.center_x + 1]);
-                    self.theme.code.set()?;
-                    println!("{}", &print_str[self.center_x + 1..]);
-                } else if self.center_x > content.len() {
-                    panic!("center_x requested access outside the range");
                 }
-            } else {
-                self.theme.code.set()?;
-                println!("{}", print_str);
             }
-            i += 1;
         }
 
-        // bottom space
-        if file_content.len() < code_bottom_num as usize {
-            for _ in file_content.len()..code_bottom_num as usize {
-                self.theme.row_num.set()?;
-                for _ in 0..code_left {
-                    print!(" ");
-                }
-                self.theme.space.set()?;
-                for _ in code_left..self.col {
-                    print!(" ");
-                }
-                println!("");
-            }
+        // completion popup below (or above) the typed word
+        if focused && let Some(popup) = cmd_handle.popup.as_ref().filter(|popup| !popup.items.is_empty()) {
+            let row = &file_content[self.center_y];
+            let typed = self.visual_x(row, self.center_x) - self.visual_x(row, popup.start.0.min(self.center_x));
+            let left = (code_left as isize + center_visual_x as isize - code_left_num - typed as isize).max(code_left as isize) as usize;
+            self.print_popup(popup, (2 + code_above, code_rows), left)?;
         }
 
-        // bar
-        self.theme.bar.set()?;
-        println!("{}", if self.col > 23 + (self.center_x + 1).to_string().len() + (self.center_y + 1).to_string().len() + self.col.to_string().len() + self.row.to_string().len() {
-            let mut _s = format!("center({}, {})  display({}, {})", self.center_x + 1, self.center_y + 1, self.col, self.row);
-            for _ in 23 + (self.center_x + 1).to_string().len() + (self.center_y + 1).to_string().len() + self.col.to_string().len() + self.row.to_string().len()..self.col {
-                _s.push(' ');
-            }
-            _s
-        } else if self.col > 10 + (self.center_x + 1).to_string().len() + (self.center_y + 1).to_string().len() {
-            let mut _s = format!("center({}, {})", self.center_x + 1, self.center_y + 1);
-            for _ in 10 + (self.center_x + 1).to_string().len() + (self.center_y + 1).to_string().len()..self.col {
-                _s.push(' ');
-            }
-            _s
+        // bar (trailing items are dropped if they do not fit)
+        self.move_to(self.row - 1)?;
+        if focused {
+            self.theme.bar.set()?;
         } else {
-            let mut _s = String::new();
-            for _ in 0..self.col {
-                _s.push(' ');
-            }
-            _s
+            self.theme.row_num.set()?;
+        }
+        let mut bar = vec![
+            format!("center({}, {})", self.center_x + 1, self.center_y + 1),
+            format!("display({}, {})", self.col, self.row),
+        ];
+        if let Some(selection) = &selection {
+            bar.push(format!("visual {} ({} rows)", selection.kind.name(), selection.bottom - selection.top + 1));
+        }
+        if !cmd_handle.highlight().is_empty() {
+            let found;
+            let matches = match file_handle.matches(cmd_handle.highlight()) {
+                Some(matches) => matches,
+                None => {
+                    found = search::find_all(file_content, cmd_handle.highlight());
+                    &found
+                }
+            };
+            bar.push(match matches.iter().position(|&m| m == (self.center_x, self.center_y)) {
+                Some(k) => format!("match {} of {}", k + 1, matches.len()),
+                None => format!("{} matches", matches.len()),
+            });
+        }
+        while !bar.is_empty() && bar.join("  ").chars().count() >= self.col {
+            bar.pop();
+        }
+        let mut bar = bar.join("  ");
+        if let Some(diagnostic) = file_handle.diagnostics.iter().filter(|d| d.on_row(self.center_y)).min_by_key(|d| d.severity) {
+            let message = format!("  {}", diagnostic.summary());
+            bar.extend(self.escape(&message).chars().take(self.col.saturating_sub(bar.chars().count() + 1)));
+        }
+        print!("{:<width$}", bar, width = self.col);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn printed(display_handle: &Display, file_handle: &file::File, marks: (&str, Option<ops::Range<usize>>), y: usize, left: isize, code_col: usize) -> (String, Vec<&'static str>, String) {
+        let cells = display_handle.cells(&file_handle.content[y]);
+        let range = 0..cells.len() + 1;
+        let theme = &display_handle.theme;
+        let names = [
+            (&theme.code, "code"), (&theme.center, "center"), (&theme.search, "search"), (&theme.selection, "selection"),
+            (&theme.space, "space"), (&theme.whitespace, "whitespace"), (&theme.diagnostic_error, "error"),
+        ];
+        let printed = display_handle.code_cells(file_handle, marks, (y, &cells, range), left, code_col);
+        (
+            printed.iter().map(|&(c, _, _)| c).collect(),
+            printed.iter().map(|&(_, color, _)| names.iter().find(|(named, _)| std::ptr::eq(*named, color)).map_or("other", |&(_, name)| name)).collect(),
+            printed.iter().map(|&(_, _, underline)| underline.map_or('-', |severity| severity.mark())).collect(),
+        )
+    }
+
+    #[test]
+    fn code_cells_center_and_tab() {
+        let mut display_handle = Display::new();
+        let mut file_handle = file::File::new(None);
+        file_handle.content = vec!["a\tb".to_string(), "xy".to_string()];
+        display_handle.center_x = 2;
+        let (chars, colors, _) = printed(&display_handle, &file_handle, ("", None), 0, -1, 8);
+        assert_eq!(chars, " a   b  ");
+        assert_eq!(colors, ["space", "code", "code", "code", "code", "center", "code", "code"]);
+        display_handle.show_whitespace = true;
+        display_handle.center_x = 3;
+        let (chars, colors, _) = printed(&display_handle, &file_handle, ("", None), 0, 0, 6);
+        assert_eq!(chars, "a→  b ");
+        assert_eq!(colors, ["code", "whitespace", "whitespace", "whitespace", "code", "center"]);
+        // a center at the end of a row is the cell after the last char
+        display_handle.center_y = 1;
+        display_handle.center_x = 2;
+        let (chars, colors, _) = printed(&display_handle, &file_handle, ("", None), 1, 1, 3);
+        assert_eq!(chars, "y  ");
+        assert_eq!(colors, ["code", "center", "code"]);
+    }
+
+    #[test]
+    fn code_cells_marks() {
+        let mut display_handle = Display::new();
+        let mut file_handle = file::File::new(None);
+        file_handle.content = vec!["ab é\u{1}ab  ".to_string(), String::new()];
+        display_handle.center_y = 1;
+        display_handle.show_whitespace = true;
+        let (chars, colors, _) = printed(&display_handle, &file_handle, ("ab", Some(3..5)), 0, 0, 11);
+        assert_eq!(chars, "ab é^Aab·· ");
+        assert_eq!(colors, ["search", "search", "code", "selection", "selection", "selection", "search", "search", "whitespace", "whitespace", "code"]);
+        // a selection reaching the end of a row marks the cell after the last char
+        let (_, colors, _) = printed(&display_handle, &file_handle, ("", Some(8..10)), 0, 9, 3);
+        assert_eq!(colors, ["selection", "selection", "code"]);
+    }
+
+    #[test]
+    fn code_cells_diagnostics() {
+        let mut display_handle = Display::new();
+        let mut file_handle = file::File::new(None);
+        file_handle.content = vec!["abc".to_string(), "d".to_string()];
+        display_handle.center_y = 1;
+        file_handle.diagnostics.push(diagnostic::Diagnostic {
+            severity: diagnostic::Severity::Error,
+            start: (1, 0),
+            end: (2, 0),
+            message: "bad\nmore".to_string(),
+            source: "make",
         });
+        let (chars, colors, underlines) = printed(&display_handle, &file_handle, ("", None), 0, 0, 12);
+        assert_eq!(chars, "abc         ");
+        assert_eq!(colors, ["code"; 12]);
+        assert_eq!(underlines, "-E----------");
+        display_handle.virtual_text = true;
+        let (chars, colors, _) = printed(&display_handle, &file_handle, ("", None), 0, 0, 12);
+        assert_eq!(chars, "abc  E make:");
+        assert_eq!(colors, ["code", "code", "code", "code", "code", "error", "error", "error", "error", "error", "error", "error"]);
+        let (chars, _, _) = printed(&display_handle, &file_handle, ("", None), 1, 0, 4);
+        assert_eq!(chars, "d   ");
+    }
 
-        // cmd
-        self.theme.cmd.set()?;
-        println!("{}", if cmd_handle.buffer.len() <= self.col {
-            let mut _c = cmd_handle.buffer.clone();
-            for _ in cmd_handle.buffer.len()..self.col {
-                _c.push(' ');
+    #[test]
+    fn escape_control_chars() {
+        let mut display_handle = Display::new();
+        assert_eq!(display_handle.escape("a\tb\u{1}\n\u{7F}\u{85}é\u{A0}\r"), "a   b^A^J^?<85>é\u{A0}^M");
+        display_handle.show_whitespace = true;
+        display_handle.tab_width = 2;
+        assert_eq!(display_handle.escape("\t\u{1}\ta\r"), "→ ^A→ a␍");
+    }
+
+    #[test]
+    fn glyph_width() {
+        let mut display_handle = Display::new();
+        assert_eq!(display_handle.glyph('x', 0), (vec!['x'], false));
+        assert_eq!(display_handle.glyph('\u{1B}', 0), (vec!['^', '['], true));
+        assert_eq!(display_handle.glyph('\u{9F}', 0), ("<9F>".chars().collect(), true));
+        assert_eq!(display_handle.glyph('\t', 5), (vec![' '; 3], false));
+        assert_eq!(display_handle.glyph('\u{A0}', 0), (vec!['\u{A0}'], false));
+        for show_whitespace in [false, true] {
+            display_handle.show_whitespace = show_whitespace;
+            for c in ['x', 'é', '\t', '\r', '\n', '\u{7F}', '\u{85}', '\u{A0}'] {
+                for visual_x in 0..6 {
+                    assert_eq!(display_handle.char_width(c, visual_x), display_handle.glyph(c, visual_x).0.len());
+                }
             }
-            _c
-        } else {
-            format!("..{}", &cmd_handle.buffer[cmd_handle.buffer.len() - self.col + 2..])
+        }
+        assert_eq!(display_handle.glyph('\t', 4), (vec!['→', ' ', ' ', ' '], true));
+        assert_eq!(display_handle.glyph('\r', 0), (vec!['␍'], true));
+    }
+
+    #[test]
+    fn whitespace_cells() {
+        let mut display_handle = Display::new();
+        let chars = |display_handle: &Display, row: &str| display_handle.cells(row).iter().map(|&(c, _, _)| c).collect::<String>();
+        assert_eq!(chars(&display_handle, "a b  "), "a b  ");
+        display_handle.show_whitespace = true;
+        assert_eq!(chars(&display_handle, "a b  "), "a b··");
+        assert_eq!(chars(&display_handle, "   "), "···");
+        assert_eq!(display_handle.cells("\u{1}b").iter().map(|&(_, x, special)| (x, special)).collect::<Vec<_>>(), [(0, true), (0, true), (1, false)]);
+    }
+
+    #[test]
+    fn wrap_rows() {
+        let mut display_handle = Display::new();
+        let wrap = |display_handle: &Display, row: &str, width: usize| {
+            display_handle.wrap(row, &display_handle.cells(row), width).into_iter().map(|r| (r.start, r.end)).collect::<Vec<_>>()
+        };
+        assert_eq!(wrap(&display_handle, "", 4), [(0, 1)]);
+        assert_eq!(wrap(&display_handle, "", 1), [(0, 1)]);
+        assert_eq!(wrap(&display_handle, "abc", 1), [(0, 1), (1, 2), (2, 3), (3, 4)]);
+        assert_eq!(wrap(&display_handle, "abc", 4), [(0, 4)]);
+        assert_eq!(wrap(&display_handle, "abcd", 4), [(0, 4), (4, 5)]);
+        assert_eq!(wrap(&display_handle, "ab cd", 4), [(0, 3), (3, 6)]);
+        assert_eq!(wrap(&display_handle, "ab cdefg", 4), [(0, 3), (3, 7), (7, 9)]);
+        // a glyph of several cols moves to the next display row
+        assert_eq!(wrap(&display_handle, "ab\u{1}", 3), [(0, 2), (2, 5)]);
+        assert_eq!(wrap(&display_handle, "a\tb", 3), [(0, 1), (1, 4), (4, 6)]);
+        assert_eq!(wrap(&display_handle, "é\u{85}x", 4), [(0, 1), (1, 5), (5, 7)]);
+        // unless it is wider than a display row
+        assert_eq!(wrap(&display_handle, "\u{85}", 3), [(0, 3), (3, 5)]);
+        assert_eq!(wrap(&display_handle, "a\u{1}", 1), [(0, 1), (1, 2), (2, 3), (3, 4)]);
+        display_handle.tab_width = 8;
+        assert_eq!(wrap(&display_handle, "\t", 4), [(0, 4), (4, 8), (8, 9)]);
+    }
+
+    #[test]
+    fn wrap_move_by_display_row() {
+        let mut display_handle = Display::new();
+        display_handle.col = 5; // 1 row number col and 4 code cols
+        let content = ["abcdefghij".to_string(), "xy".to_string(), String::new()];
+        let moves = |display_handle: &mut Display, down: bool| {
+            display_handle.wrap_move(&content, down);
+            (display_handle.center_x, display_handle.center_y)
+        };
+        display_handle.center_x = 1;
+        assert_eq!(moves(&mut display_handle, true), (5, 0));
+        assert_eq!(moves(&mut display_handle, true), (9, 0));
+        assert_eq!(moves(&mut display_handle, true), (1, 1));
+        assert_eq!(moves(&mut display_handle, false), (9, 0));
+        assert_eq!(moves(&mut display_handle, false), (5, 0));
+        display_handle.center_x = 3;
+        assert_eq!(moves(&mut display_handle, true), (7, 0));
+        assert_eq!(moves(&mut display_handle, true), (10, 0));
+        assert_eq!(moves(&mut display_handle, true), (2, 1));
+        assert_eq!(moves(&mut display_handle, true), (0, 2));
+        assert_eq!(moves(&mut display_handle, true), (0, 2));
+        assert_eq!(moves(&mut display_handle, false), (0, 1));
+        display_handle.center_y = 0;
+        display_handle.center_x = 0;
+        assert_eq!(moves(&mut display_handle, false), (0, 0));
+    }
+
+    #[test]
+    fn row_numbers() {
+        let mut display_handle = Display::new();
+        display_handle.center_y = 99;
+        let numbers = |display_handle: &Display| [0, 98, 99, 100, 120].map(|y| display_handle.row_num(y));
+        assert_eq!(numbers(&display_handle), [1, 99, 100, 101, 121]);
+        display_handle.row_num = RowNum::Relative;
+        assert_eq!(numbers(&display_handle), [99, 1, 0, 1, 21]);
+        display_handle.row_num = RowNum::Hybrid;
+        assert_eq!(numbers(&display_handle), [99, 1, 100, 1, 21]);
+        assert!(matches!(display_handle.row_num.next().next().next(), RowNum::Hybrid));
+    }
+
+    #[test]
+    fn row_number_width() {
+        let mut display_handle = Display::new();
+        display_handle.center_y = 99;
+        display_handle.row = 23; // 20 code rows, the bottom row is 109
+        let widths = |display_handle: &mut Display| [RowNum::Absolute, RowNum::Relative, RowNum::Hybrid].map(|row_num| {
+            display_handle.row_num = row_num;
+            display_handle.code_left()
         });
+        assert_eq!(widths(&mut display_handle), [3, 2, 3]);
+        display_handle.center_y = 0;
+        assert_eq!(widths(&mut display_handle), [2, 2, 2]);
+        display_handle.row = 0; // 1 code row
+        assert_eq!(widths(&mut display_handle), [1, 1, 1]);
+        display_handle.sign = true;
+        assert_eq!(widths(&mut display_handle), [2, 2, 2]);
+    }
 
-        execute!(
-            io::stdout(),
-            cursor::MoveTo(0, 0),
-        )?;
-        Ok(())
+    #[test]
+    fn tab_cols() {
+        let mut display_handle = Display::new();
+        display_handle.tab_width = 4;
+        let row = "a\tb\tc";
+        assert_eq!((0..=5).map(|x| display_handle.visual_x(row, x)).collect::<Vec<_>>(), [0, 1, 4, 5, 8, 9]);
+        assert_eq!((0..=9).map(|visual_x| display_handle.char_x(row, visual_x)).collect::<Vec<_>>(), [0, 1, 1, 1, 2, 3, 3, 3, 4, 5]);
+        assert_eq!(display_handle.char_x(row, 99), 5);
+        display_handle.tab_width = 3;
+        assert_eq!((0..=5).map(|x| display_handle.visual_x(row, x)).collect::<Vec<_>>(), [0, 1, 3, 4, 6, 7]);
+        assert_eq!(display_handle.visual_x("\t\t", 2), 6);
+        assert_eq!(display_handle.char_x("\t\t", 5), 1);
     }
 }
@@ -0,0 +1,381 @@
+//! # CONTENT
+//! directory browser and tree panel buffers
+
+use {
+    std:: {
+        fs,
+        io,
+        path,
+    },
+};
+
+/// # CONTENT
+/// indentation of a tree depth
+const TREE_INDENT: &str = "  ";
+
+/// # CONTENT
+/// explorer buffer kind
+/// - Directory: listing of a directory (parent entry first, then dirs, then files)
+/// - Tree: collapsible tree of a root directory and its expanded dirs
+pub enum Kind {
+    Directory(String),
+    Tree(String, Vec<String>),
+}
+
+/// # CONTENT
+/// explorer buffer state
+/// # FIELD
+/// - kind: buffer kind
+/// - entries: (path, directory) of each row of the listing
+pub struct Explorer {
+    pub kind: Kind,
+    entries: Vec<(String, bool)>,
+}
+
+/// # CONTENT
+/// join a dir and an entry name (without a leading "./")
+/// # ARGUMENT
+/// - dir: directory path
+/// - name: entry name
+/// # RETURN VALUE
+/// joined path
+pub fn join(dir: &str, name: &str) -> String {
+    if dir == "." || dir.is_empty() {
+        name.to_string()
+    } else {
+        path::Path::new(dir).join(name).to_string_lossy().to_string()
+    }
+}
+
+/// # CONTENT
+/// get the parent directory
+/// # ARGUMENT
+/// - dir: directory or file path
+/// # RETURN VALUE
+/// absolute parent path ("/" for the root)
+pub fn parent(dir: &str) -> String {
+    fs::canonicalize(dir)
+        .ok()
+        .and_then(|dir| dir.parent().map(|parent| parent.to_string_lossy().to_string()))
+        .unwrap_or_else(|| "/".to_string())
+}
+
+/// # CONTENT
+/// get permission bits
+/// # ARGUMENT
+/// - metadata: entry metadata
+/// # RETURN VALUE
+/// permission bits (read-only or read-write without unix modes)
+#[cfg(unix)]
+fn mode(metadata: &fs::Metadata) -> u32 {
+    std::os::unix::fs::PermissionsExt::mode(&metadata.permissions())
+}
+
+#[cfg(not(unix))]
+fn mode(metadata: &fs::Metadata) -> u32 {
+    if metadata.permissions().readonly() { 0o444 } else { 0o644 }
+}
+
+/// # CONTENT
+/// format an entry as "mode  size  name" (dirs end with '/' and have no size)
+/// # ARGUMENT
+/// - name: entry name
+/// - metadata: entry metadata
+/// # RETURN VALUE
+/// listing row
+fn row(name: &str, metadata: &fs::Metadata) -> String {
+    let bits = mode(metadata);
+    let mut modes = String::from(if metadata.is_dir() { "d" } else if metadata.is_symlink() { "l" } else { "-" });
+    for (i, c) in "rwxrwxrwx".chars().enumerate() {
+        modes.push(if bits & (0o400 >> i) != 0 { c } else { '-' });
+    }
+    if metadata.is_dir() {
+        return format!("{} {:>7}  {}/", modes, "-", name);
+    }
+    let size = metadata.len();
+    let size = match size {
+        0..1024 => format!("{}", size),
+        1024..1048576 => format!("{:.1}K", size as f64 / 1024.0),
+        1048576..1073741824 => format!("{:.1}M", size as f64 / 1048576.0),
+        _ => format!("{:.1}G", size as f64 / 1073741824.0),
+    };
+    format!("{} {:>7}  {}", modes, size, name)
+}
+
+/// # CONTENT
+/// read directory entries (dirs first, then by name)
+/// # ARGUMENT
+/// - dir: directory path
+/// # RETURN VALUE
+/// Ok(entries): (name, directory, metadata)
+/// Err(...): read error
+fn entries(dir: &str) -> io::Result<Vec<(String, bool, fs::Metadata)>> {
+    let mut entries = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let Ok(name) = entry.file_name().into_string() else {
+            continue;
+        };
+        let Ok(metadata) = fs::metadata(entry.path()).or_else(|_| entry.metadata()) else {
+            continue;
+        };
+        entries.push((name, metadata.is_dir(), metadata));
+    }
+    entries.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    Ok(entries)
+}
+
+/// # CONTENT
+/// list a tree of a directory (children of expanded dirs follow their row)
+/// # ARGUMENT
+/// - dir: listed directory
+/// - depth: indent depth of the entries
+/// - expanded: paths of expanded dirs
+/// - rows: listing rows (pushed)
+/// - listed: (path, directory) of rows (pushed)
+/// # RETURN VALUE
+/// none
+fn tree(dir: &str, depth: usize, expanded: &[String], rows: &mut Vec<String>, listed: &mut Vec<(String, bool)>) {
+    let Ok(children) = entries(dir) else {
+        return;
+    };
+    for (name, is_dir, _) in children {
+        let path = join(dir, &name);
+        let open = is_dir && expanded.contains(&path);
+        let indent = TREE_INDENT.repeat(depth);
+        rows.push(match (is_dir, open) {
+            (true, true) => format!("{}▾ {}/", indent, name),
+            (true, false) => format!("{}▸ {}/", indent, name),
+            (false, _) => format!("{}  {}", indent, name),
+        });
+        listed.push((path.clone(), is_dir));
+        if open {
+            tree(&path, depth + 1, expanded, rows, listed);
+        }
+    }
+}
+
+/// # CONTENT
+/// create a file or a directory (path ending with '/') with its missing parent dirs
+/// # ARGUMENT
+/// - path: created path
+/// # RETURN VALUE
+/// Ok(()): created
+/// Err(...): already exists or create error
+pub fn create(path: &str) -> io::Result<()> {
+    if path.ends_with('/') {
+        return fs::create_dir_all(path);
+    }
+    if let Some(parent) = path::Path::new(path).parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    fs::File::create_new(path).map(|_| ())
+}
+
+/// # CONTENT
+/// delete a file or an empty directory
+/// # ARGUMENT
+/// - path: deleted path
+/// - is_dir: the path is a directory
+/// # RETURN VALUE
+/// Ok(()): deleted
+/// Err(...): delete error (e.g. a directory not empty)
+pub fn delete(path: &str, is_dir: bool) -> io::Result<()> {
+    if is_dir { fs::remove_dir(path) } else { fs::remove_file(path) }
+}
+
+impl Explorer {
+    /// # CONTENT
+    /// init Explorer of a kind (the listing is read by Explorer::refresh)
+    /// # ARGUMENT
+    /// - kind: buffer kind
+    /// # RETURN VALUE
+    /// inited Explorer
+    pub fn new(kind: Kind) -> Self {
+        Self {
+            kind,
+            entries: Vec::new(),
+        }
+    }
+
+    /// # CONTENT
+    /// read the listing again
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// Ok(rows): buffer content
+    /// Err(...): read error
+    pub fn refresh(&mut self) -> io::Result<Vec<String>> {
+        let mut rows = Vec::new();
+        self.entries = Vec::new();
+        match &self.kind {
+            Kind::Directory(dir) => {
+                let parent = parent(dir);
+                if let Ok(metadata) = fs::metadata(&parent) {
+                    rows.push(row("..", &metadata));
+                    self.entries.push((parent, true));
+                }
+                for (name, is_dir, metadata) in entries(dir)? {
+                    rows.push(row(&name, &metadata));
+                    self.entries.push((join(dir, &name), is_dir));
+                }
+            }
+            Kind::Tree(root, expanded) => tree(root, 0, expanded, &mut rows, &mut self.entries),
+        }
+        if rows.is_empty() {
+            rows.push(String::new());
+        }
+        Ok(rows)
+    }
+
+    /// # CONTENT
+    /// get the entry of a row
+    /// # ARGUMENT
+    /// - y: row index
+    /// # RETURN VALUE
+    /// Some((path, directory)): listed entry
+    /// None: no entry (e.g. an empty directory)
+    pub fn entry(&self, y: usize) -> Option<(String, bool)> {
+        self.entries.get(y).cloned()
+    }
+
+    /// # CONTENT
+    /// get the entry of a row renamed or deleted by a cmd
+    /// # ARGUMENT
+    /// - y: row index
+    /// # RETURN VALUE
+    /// Some((path, directory)): listed entry (not the parent entry of a directory listing)
+    /// None: no entry
+    pub fn target(&self, y: usize) -> Option<(String, bool)> {
+        match self.kind {
+            Kind::Directory(_) if y == 0 => None,
+            _ => self.entry(y),
+        }
+    }
+
+    /// # CONTENT
+    /// get the directory new entries are created in
+    /// # ARGUMENT
+    /// - y: row index of center
+    /// # RETURN VALUE
+    /// the listed dir (Directory), or the expanded dir or parent dir of the row (Tree)
+    pub fn dir(&self, y: usize) -> String {
+        match &self.kind {
+            Kind::Directory(dir) => dir.clone(),
+            Kind::Tree(root, expanded) => match self.entry(y) {
+                Some((path, true)) if expanded.contains(&path) => path,
+                Some((path, _)) => path::Path::new(&path).parent()
+                    .map(|parent| parent.to_string_lossy().to_string())
+                    .filter(|parent| !parent.is_empty())
+                    .unwrap_or(root.clone()),
+                None => root.clone(),
+            },
+        }
+    }
+
+    /// # CONTENT
+    /// expand or collapse a dir of a tree
+    /// # ARGUMENT
+    /// - path: dir path
+    /// # RETURN VALUE
+    /// none
+    pub fn toggle(&mut self, path: &str) {
+        if let Kind::Tree(_, expanded) = &mut self.kind {
+            match expanded.iter().position(|p| p == path) {
+                Some(i) => {
+                    expanded.remove(i);
+                }
+                None => expanded.push(path.to_string()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std:: {
+            env,
+            process,
+        },
+    };
+
+    fn temp_dir(name: &str) -> String {
+        let dir = env::temp_dir().join(format!("center_explorer_{}_{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir.to_string_lossy().to_string()
+    }
+
+    fn names(rows: &[String]) -> Vec<&str> {
+        rows.iter().map(|row| row.rsplit("  ").next().unwrap_or_default()).collect()
+    }
+
+    #[test]
+    fn directory_listing() {
+        let dir = temp_dir("listing");
+        fs::write(join(&dir, "b.txt"), "").unwrap();
+        fs::write(join(&dir, "a.txt"), "abc").unwrap();
+        fs::create_dir(join(&dir, "z")).unwrap();
+        let mut explorer = Explorer::new(Kind::Directory(dir.clone()));
+        let rows = explorer.refresh().unwrap();
+        assert_eq!(names(&rows), ["../", "z/", "a.txt", "b.txt"]);
+        assert!(rows[1].starts_with('d') && rows[1].ends_with("       -  z/"));
+        assert!(rows[2].starts_with('-') && rows[2].ends_with("       3  a.txt"));
+        assert_eq!(explorer.entry(0), Some((parent(&dir), true)));
+        assert_eq!(explorer.entry(1), Some((join(&dir, "z"), true)));
+        assert_eq!(explorer.target(0), None);
+        assert_eq!(explorer.target(2), Some((join(&dir, "a.txt"), false)));
+        assert_eq!(explorer.dir(3), dir);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn empty_directory() {
+        let dir = temp_dir("empty");
+        let mut explorer = Explorer::new(Kind::Directory(dir.clone()));
+        assert_eq!(names(&explorer.refresh().unwrap()), ["../"]);
+        assert_eq!((explorer.target(0), explorer.entry(1)), (None, None));
+        let mut explorer = Explorer::new(Kind::Tree(dir.clone(), Vec::new()));
+        assert_eq!(explorer.refresh().unwrap(), [""]);
+        assert_eq!(explorer.target(0), None);
+        assert_eq!(explorer.dir(0), dir);
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(Explorer::new(Kind::Directory(dir)).refresh().is_err());
+    }
+
+    #[test]
+    fn create_and_delete() {
+        let dir = temp_dir("create");
+        create(&join(&dir, "x/y/z.txt")).unwrap();
+        assert!(fs::metadata(join(&dir, "x/y/z.txt")).unwrap().is_file());
+        assert_eq!(create(&join(&dir, "x/y/z.txt")).unwrap_err().kind(), io::ErrorKind::AlreadyExists);
+        create(&join(&dir, "d/")).unwrap();
+        assert!(fs::metadata(join(&dir, "d")).unwrap().is_dir());
+        assert!(delete(&join(&dir, "x/y"), true).is_err());
+        delete(&join(&dir, "x/y/z.txt"), false).unwrap();
+        delete(&join(&dir, "x/y"), true).unwrap();
+        delete(&join(&dir, "d"), true).unwrap();
+        let mut explorer = Explorer::new(Kind::Directory(dir.clone()));
+        assert_eq!(names(&explorer.refresh().unwrap()), ["../", "x/"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn tree_toggle() {
+        let dir = temp_dir("tree");
+        create(&join(&dir, "a/f")).unwrap();
+        create(&join(&dir, "g")).unwrap();
+        let mut explorer = Explorer::new(Kind::Tree(dir.clone(), Vec::new()));
+        assert_eq!(explorer.refresh().unwrap(), ["▸ a/", "  g"]);
+        assert_eq!(explorer.dir(0), dir);
+        explorer.toggle(&join(&dir, "a"));
+        assert_eq!(explorer.refresh().unwrap(), ["▾ a/", "    f", "  g"]);
+        assert_eq!(explorer.target(1), Some((join(&dir, "a/f"), false)));
+        assert_eq!([0, 1, 2].map(|y| explorer.dir(y)), [join(&dir, "a"), join(&dir, "a"), dir.clone()]);
+        explorer.toggle(&join(&dir, "a"));
+        assert_eq!(explorer.refresh().unwrap(), ["▸ a/", "  g"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -2,7 +2,16 @@
 //! program to control file
 
 use {
+    crate:: {
+        completion,
+        diagnostic,
+        explorer,
+        location,
+        search,
+        syntax,
+    },
     std:: {
+        collections::BTreeSet,
         fs,
         io:: {
             self,
@@ -11,14 +20,81 @@ use {
     },
 };
 
+/// # CONTENT
+/// undo step count limit of a file (the oldest step is dropped)
+const MAX_UNDO_STEPS: usize = 1000;
+
+/// # CONTENT
+/// searched pattern, File::changes and match pos (x, y)
+type Matches = (String, u64, Vec<(usize, usize)>);
+
+/// # CONTENT
+/// File::changes and completed words of the content
+type Words = (u64, BTreeSet<String>);
+
+/// # CONTENT
+/// changed rows restored by an undo or redo
+/// # FIELD
+/// - top: index of the first changed row
+/// - count: changed row count in the current content
+/// - rows: rows put back in place of the changed rows
+/// - pos: center pos (x, y) restored
+struct Step {
+    top: usize,
+    count: usize,
+    rows: Vec<String>,
+    pos: (usize, usize),
+}
+
+/// # CONTENT
+/// get the rows differing between two contents
+/// # ARGUMENT
+/// - old: content before
+/// - new: content after
+/// # RETURN VALUE
+/// Some((top, old bottom, new bottom)): rows top..old bottom of old became top..new bottom of new
+/// None: same contents
+fn changed_rows(old: &[String], new: &[String]) -> Option<(usize, usize, usize)> {
+    let top = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    if top == old.len() && top == new.len() {
+        return None;
+    }
+    let bottom = old[top..].iter().rev().zip(new[top..].iter().rev()).take_while(|(a, b)| a == b).count();
+    Some((top, old.len() - bottom, new.len() - bottom))
+}
+
 /// # CONTENT
 /// file handle
 /// # FIELD
 /// - path: file path
 /// - content: file content
+/// - syntax: syntax info of content
+/// - undo: changed rows of each edit (restoring the content before it)
+/// - redo: changed rows of each undo (restoring the content before it)
+/// - base: content when the last edit started (kept by applying the changed rows)
+/// - pending: center pos when the running edit started (None if no edit started since the last step)
+/// - explorer: listing state of a directory or tree buffer (None for a file)
+/// - locations: location of each row of a location list buffer (None for a file)
+/// - diagnostics: diagnostics of every source sorted by start pos
+/// - built: content the build and linter diagnostics refer to (None without them)
+/// - changes: change count of content (bumped by every edit, undo, redo and read)
+/// - matches: searched pattern, File::changes and match pos of the last File::update_matches
+/// - words: File::changes and words of the last File::words
 pub struct File {
     pub path: Option<String>,
     pub content: Vec<String>,
+    pub syntax: syntax::Syntax,
+    pub explorer: Option<explorer::Explorer>,
+    pub locations: Option<Vec<location::Location>>,
+    pub diagnostics: Vec<diagnostic::Diagnostic>,
+    built: Option<Vec<String>>,
+    undo: Vec<Step>,
+    redo: Vec<Step>,
+    base: Vec<String>,
+    pending: Option<(usize, usize)>,
+    changes: u64,
+    matches: Option<Matches>,
+    words: Option<Words>,
 }
 
 impl File {
@@ -30,8 +106,20 @@ impl File {
     /// inited File
     pub fn new(_path: Option<String>) -> Self {
         Self {
+            syntax: syntax::Syntax::new(&_path),
             path: _path,
             content: Vec::new(), // set in File::read()
+            explorer: None,
+            locations: None,
+            diagnostics: Vec::new(),
+            built: None,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            base: Vec::new(),
+            pending: None,
+            changes: 0,
+            matches: None,
+            words: None,
         }
     }
 
@@ -44,24 +132,535 @@ impl File {
     pub fn get_content(&self) -> &Vec<String> { &self.content }
 
     /// # CONTENT
-    /// get file content
+    /// get File::content to edit (counted as a change)
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// File::content
+    pub fn content_mut(&mut self) -> &mut Vec<String> {
+        self.changes += 1;
+        &mut self.content
+    }
+
+    /// # CONTENT
+    /// get char count of a row
+    /// # ARGUMENT
+    /// - y: row index
+    /// # RETURN VALUE
+    /// char count
+    pub fn row_len(&self, y: usize) -> usize {
+        self.content[y].chars().count()
+    }
+
+    /// # CONTENT
+    /// convert char index to byte index
+    /// # ARGUMENT
+    /// - x: char index
+    /// - y: row index
+    /// # RETURN VALUE
+    /// byte index (row byte length if x is at or after the end)
+    pub fn byte_x(&self, x: usize, y: usize) -> usize {
+        self.content[y].char_indices().nth(x).map(|(i, _)| i).unwrap_or(self.content[y].len())
+    }
+
+    /// # CONTENT
+    /// insert str at char index
+    /// # ARGUMENT
+    /// - x: char index
+    /// - y: row index
+    /// - s: inserted str
+    /// # RETURN VALUE
+    /// none
+    pub fn insert(&mut self, x: usize, y: usize, s: &str) {
+        let byte_x = self.byte_x(x, y);
+        self.content_mut()[y].insert_str(byte_x, s);
+    }
+
+    /// # CONTENT
+    /// remove char at char index
+    /// # ARGUMENT
+    /// - x: char index
+    /// - y: row index
+    /// # RETURN VALUE
+    /// Some(char): removed char
+    /// None: x is at or after the end
+    pub fn remove(&mut self, x: usize, y: usize) -> Option<char> {
+        if x < self.row_len(y) {
+            let byte_x = self.byte_x(x, y);
+            Some(self.content_mut()[y].remove(byte_x))
+        } else {
+            None
+        }
+    }
+
+    /// # CONTENT
+    /// convert indentation between tabs and spaces
+    /// # ARGUMENT
+    /// - tab_width: display col count of a tab stop
+    /// - expand_tab: true: tabs to spaces, false: leading spaces to tabs
+    /// # RETURN VALUE
+    /// none
+    pub fn retab(&mut self, tab_width: usize, expand_tab: bool) {
+        for row in self.content_mut().iter_mut() {
+            if expand_tab {
+                let mut expanded = String::new();
+                let mut visual_x = 0;
+                for c in row.chars() {
+                    if c == '\t' {
+                        let width = tab_width - visual_x % tab_width;
+                        expanded.push_str(&" ".repeat(width));
+                        visual_x += width;
+                    } else {
+                        expanded.push(c);
+                        visual_x += 1;
+                    }
+                }
+                *row = expanded;
+            } else {
+                let indent_len = row.len() - row.trim_start_matches([' ', '\t']).len();
+                let indent_width = row[..indent_len].chars().fold(0, |visual_x, c| {
+                    if c == '\t' { visual_x + tab_width - visual_x % tab_width } else { visual_x + 1 }
+                });
+                *row = format!("{}{}{}", "\t".repeat(indent_width / tab_width), " ".repeat(indent_width % tab_width), &row[indent_len..]);
+            }
+        }
+    }
+
+    /// # CONTENT
+    /// record the rows changed since the last edit started as an undo step
+    /// (content changed without an edit, as by a read, only updates File::base until an edit is recorded)
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// none
+    fn commit(&mut self) {
+        let pos = self.pending.take();
+        let Some((top, old_bottom, new_bottom)) = changed_rows(&self.base, &self.content) else {
+            return;
+        };
+        let rows: Vec<String> = self.base.splice(top..old_bottom, self.content[top..new_bottom].iter().cloned()).collect();
+        self.redo.clear();
+        if pos.is_none() && self.undo.is_empty() {
+            return;
+        }
+        self.undo.push(Step { top, count: new_bottom - top, rows, pos: pos.unwrap_or((0, top)) });
+        if self.undo.len() > MAX_UNDO_STEPS {
+            self.undo.remove(0);
+        }
+    }
+
+    /// # CONTENT
+    /// put the rows of a step back
+    /// # ARGUMENT
+    /// - step: applied step
+    /// - pos: center pos (x, y) restored by the inverse step
+    /// # RETURN VALUE
+    /// inverse step
+    fn apply(&mut self, step: Step, pos: (usize, usize)) -> Step {
+        let range = step.top..step.top + step.count;
+        let count = step.rows.len();
+        self.base.splice(range.clone(), step.rows.iter().cloned());
+        let rows = self.content_mut().splice(range, step.rows).collect();
+        Step { top: step.top, count, rows, pos }
+    }
+
+    /// # CONTENT
+    /// start an undo step (the changed rows are recorded when the next step starts or on undo)
+    /// # ARGUMENT
+    /// - x: center pos x
+    /// - y: center pos y
+    /// # RETURN VALUE
+    /// none
+    pub fn save_undo(&mut self, x: usize, y: usize) {
+        self.commit();
+        self.pending = Some((x, y));
+        self.redo.clear();
+    }
+
+    /// # CONTENT
+    /// restore File::content of the last undo step
+    /// # ARGUMENT
+    /// - x: center pos x
+    /// - y: center pos y
+    /// # RETURN VALUE
+    /// Some((x, y)): restored center pos
+    /// None: no undo step
+    pub fn undo(&mut self, x: usize, y: usize) -> Option<(usize, usize)> {
+        self.commit();
+        let step = self.undo.pop()?;
+        let restored = step.pos;
+        let inverse = self.apply(step, (x, y));
+        self.redo.push(inverse);
+        Some(restored)
+    }
+
+    /// # CONTENT
+    /// restore File::content of the last undone step
+    /// # ARGUMENT
+    /// - x: center pos x
+    /// - y: center pos y
+    /// # RETURN VALUE
+    /// Some((x, y)): restored center pos
+    /// None: no redo step
+    pub fn redo(&mut self, x: usize, y: usize) -> Option<(usize, usize)> {
+        self.commit();
+        let step = self.redo.pop()?;
+        let restored = step.pos;
+        let inverse = self.apply(step, (x, y));
+        self.undo.push(inverse);
+        Some(restored)
+    }
+
+    /// # CONTENT
+    /// init File of an explorer buffer
+    /// # ARGUMENT
+    /// - kind: explorer kind
+    /// - name: buffer name
+    /// # RETURN VALUE
+    /// Ok(file): listed File
+    /// Err(...): read error
+    pub fn explorer(kind: explorer::Kind, name: String) -> io::Result<Self> {
+        let mut file_handle = Self::new(Some(name));
+        file_handle.explorer = Some(explorer::Explorer::new(kind));
+        file_handle.refresh()?;
+        Ok(file_handle)
+    }
+
+    /// # CONTENT
+    /// init File of a location list buffer
+    /// # ARGUMENT
+    /// - locations: listed locations
+    /// - name: buffer name
+    /// # RETURN VALUE
+    /// listed File
+    pub fn locations(locations: Vec<location::Location>, name: String) -> Self {
+        let mut file_handle = Self::new(Some(name));
+        file_handle.content = location::rows(&locations);
+        file_handle.locations = Some(locations);
+        file_handle
+    }
+
+    /// # CONTENT
+    /// replace the diagnostics of a source
+    /// # ARGUMENT
+    /// - source: producer of the diagnostics
+    /// - diagnostics: new diagnostics of the source
+    /// # RETURN VALUE
+    /// none
+    pub fn set_diagnostics(&mut self, source: &str, diagnostics: Vec<diagnostic::Diagnostic>) {
+        self.diagnostics.retain(|d| d.source != source);
+        self.diagnostics.extend(diagnostics);
+        self.diagnostics.sort_by_key(|d| (d.start.1, d.start.0));
+        if source != diagnostic::SOURCE_LSP {
+            let built = self.diagnostics.iter().any(|d| d.source != diagnostic::SOURCE_LSP);
+            self.built = built.then(|| self.content.clone());
+        }
+    }
+
+    /// # CONTENT
+    /// move the build and linter diagnostics with the rows inserted or removed since they were set
+    /// (the ones on changed rows are dropped, a language server publishes its own again)
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// true if the build and linter diagnostics changed
+    pub fn update_diagnostics(&mut self) -> bool {
+        let Some(built) = self.built.as_mut() else {
+            return false;
+        };
+        let Some((top, old_bottom, new_bottom)) = changed_rows(built, &self.content) else {
+            return false;
+        };
+        built.splice(top..old_bottom, self.content[top..new_bottom].iter().cloned());
+        self.diagnostics.retain_mut(|d| {
+            if d.source == diagnostic::SOURCE_LSP || d.end.1 < top {
+                return true;
+            }
+            if d.start.1 < old_bottom {
+                return false;
+            }
+            d.start.1 = d.start.1 + new_bottom - old_bottom;
+            d.end.1 = d.end.1 + new_bottom - old_bottom;
+            true
+        });
+        self.diagnostics.sort_by_key(|d| (d.start.1, d.start.0));
+        true
+    }
+
+    /// # CONTENT
+    /// read the listing of an explorer buffer again
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// Ok(()): ok (also for a file)
+    /// Err(...): read error
+    pub fn refresh(&mut self) -> io::Result<()> {
+        if let Some(explorer) = self.explorer.as_mut() {
+            self.content = explorer.refresh()?;
+            self.changes += 1;
+        }
+        Ok(())
+    }
+
+    /// # CONTENT
+    /// get file content (a directory is read as an explorer listing)
     /// # ARGUMENT
     /// none
     /// # RETURN VALUE
     /// Ok(()): ok
     pub fn read(&mut self) -> io::Result<()> {
         match self.path.clone() {
+            Some(n) if fs::metadata(&n).is_ok_and(|m| m.is_dir()) => {
+                self.explorer = Some(explorer::Explorer::new(explorer::Kind::Directory(n)));
+                self.refresh()?;
+            }
             Some(n) => {
                 let fh = fs::File::open(&n)?;
                 let fh_br =  io::BufReader::new(fh);
                 for line in fh_br.lines() {
                     self.content.push(line?);
                 }
+                if self.content.is_empty() {
+                    self.content.push(String::new()); // an empty file has an empty row
+                }
             }
             None => {
                 self.content.push(String::new());
             }
         }
+        self.changes += 1;
+        self.update_syntax();
         Ok(())
     }
+
+    /// # CONTENT
+    /// write file content
+    /// # ARGUMENT
+    /// - path: written path (File::path if None, and becomes File::path if File::path is None)
+    /// # RETURN VALUE
+    /// Ok(path): written path
+    /// Err(...): no path or write error
+    pub fn write(&mut self, path: Option<String>) -> io::Result<String> {
+        if (self.explorer.is_some() || self.locations.is_some()) && path.is_none() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "list buffer"));
+        }
+        let Some(path) = path.or(self.path.clone()) else {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "no file name"));
+        };
+        let mut text = self.content.join("\n");
+        text.push('\n');
+        fs::write(&path, text)?;
+        if self.path.is_none() {
+            self.syntax = syntax::Syntax::new(&Some(path.clone()));
+            self.path = Some(path.clone());
+        }
+        Ok(path)
+    }
+
+    /// # CONTENT
+    /// reparse File::content into File::syntax
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// none
+    pub fn update_syntax(&mut self) {
+        self.syntax.update(&self.content);
+    }
+
+    /// # CONTENT
+    /// find the matches of a search pattern unless the pattern and content are unchanged since the last call
+    /// # ARGUMENT
+    /// - pattern: searched text ("" drops the matches)
+    /// # RETURN VALUE
+    /// none
+    pub fn update_matches(&mut self, pattern: &str) {
+        if pattern.is_empty() {
+            self.matches = None;
+            return;
+        }
+        if self.matches.as_ref().is_some_and(|(p, changes, _)| p == pattern && *changes == self.changes) {
+            return;
+        }
+        self.matches = Some((pattern.to_string(), self.changes, search::find_all(&self.content, pattern)));
+    }
+
+    /// # CONTENT
+    /// get the matches found by File::update_matches
+    /// # ARGUMENT
+    /// - pattern: searched text
+    /// # RETURN VALUE
+    /// Some(matches): match start pos (x, y) in order
+    /// None: the matches of another pattern or none were found
+    pub fn matches(&self, pattern: &str) -> Option<&[(usize, usize)]> {
+        self.matches.as_ref().filter(|(p, _, _)| p == pattern).map(|(_, _, matches)| matches.as_slice())
+    }
+
+    /// # CONTENT
+    /// get the words of the content offered by completion (indexed again only after the content changed)
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// sorted words
+    pub fn words(&mut self) -> &BTreeSet<String> {
+        if self.words.as_ref().is_none_or(|(changes, _)| *changes != self.changes) {
+            let words = self.content.iter()
+                .flat_map(|row| row.split(|c| !completion::word_char(c)))
+                .filter(|word| !word.is_empty())
+                .map(|word| word.to_string())
+                .collect();
+            self.words = Some((self.changes, words));
+        }
+        &self.words.get_or_insert_default().1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(rows: &[&str]) -> File {
+        let mut file_handle = File::new(None);
+        file_handle.content = rows.iter().map(|row| row.to_string()).collect();
+        file_handle
+    }
+
+    #[test]
+    fn changed_rows_range() {
+        let old: Vec<String> = ["a", "b", "c"].iter().map(|row| row.to_string()).collect();
+        let new: Vec<String> = ["a", "x", "y", "c"].iter().map(|row| row.to_string()).collect();
+        assert_eq!(changed_rows(&old, &old), None);
+        assert_eq!(changed_rows(&old, &new), Some((1, 2, 3)));
+        assert_eq!(changed_rows(&new, &old), Some((1, 3, 2)));
+        assert_eq!(changed_rows(&old, &old[..2]), Some((2, 3, 2)));
+    }
+
+    #[test]
+    fn retab_rows() {
+        let mut file_handle = file(&["\tx", "a\tb", "  \t y", ""]);
+        file_handle.retab(4, true);
+        assert_eq!(file_handle.content, ["    x", "a   b", "     y", ""]);
+        let mut file_handle = file(&["      x", "  \t y", "a  b", "\t\tz", ""]);
+        file_handle.retab(4, false);
+        assert_eq!(file_handle.content, ["\t  x", "\t y", "a  b", "\t\tz", ""]);
+        file_handle.retab(2, true);
+        assert_eq!(file_handle.content, ["    x", "   y", "a  b", "    z", ""]);
+    }
+
+    #[test]
+    fn undo_redo_steps() {
+        let mut file_handle = file(&["a", "b", "c"]);
+        file_handle.save_undo(0, 1);
+        file_handle.content[1] = "x".to_string();
+        file_handle.save_undo(0, 2);
+        file_handle.content.remove(2);
+        assert_eq!(file_handle.undo(0, 1), Some((0, 2)));
+        assert_eq!(file_handle.content, ["a", "x", "c"]);
+        assert_eq!(file_handle.undo(0, 2), Some((0, 1)));
+        assert_eq!(file_handle.content, ["a", "b", "c"]);
+        assert_eq!(file_handle.undo(0, 1), None);
+        assert_eq!(file_handle.redo(0, 0), Some((0, 2)));
+        assert_eq!(file_handle.content, ["a", "x", "c"]);
+        assert_eq!(file_handle.redo(0, 0), Some((0, 1)));
+        assert_eq!(file_handle.content, ["a", "x"]);
+        assert_eq!(file_handle.redo(0, 0), None);
+    }
+
+    #[test]
+    fn edit_clears_redo() {
+        let mut file_handle = file(&["a"]);
+        file_handle.save_undo(0, 0);
+        file_handle.content[0] = "b".to_string();
+        file_handle.undo(0, 0);
+        file_handle.save_undo(0, 0);
+        file_handle.content[0] = "c".to_string();
+        assert_eq!(file_handle.redo(0, 0), None);
+        assert_eq!(file_handle.undo(0, 0), Some((0, 0)));
+        assert_eq!(file_handle.content, ["a"]);
+    }
+
+    #[test]
+    fn matches_follow_pattern_and_content() {
+        let mut file_handle = file(&["ab", "b"]);
+        file_handle.update_matches("b");
+        assert_eq!(file_handle.matches("b"), Some(&[(1, 0), (0, 1)][..]));
+        assert_eq!(file_handle.matches("a"), None);
+        file_handle.content_mut().push("bb".to_string());
+        file_handle.update_matches("b");
+        assert_eq!(file_handle.matches("b").map(|matches| matches.len()), Some(4));
+        file_handle.update_matches("");
+        assert_eq!(file_handle.matches("b"), None);
+    }
+
+    #[test]
+    fn caches_follow_changes() {
+        let mut file_handle = file(&["ab", "b"]);
+        file_handle.update_matches("b");
+        assert_eq!(file_handle.words().len(), 2);
+        // a write past File::content_mut is not a counted change
+        file_handle.content[1] = "bbb c".to_string();
+        file_handle.update_matches("b");
+        assert_eq!(file_handle.matches("b").map(|matches| matches.len()), Some(2));
+        assert_eq!(file_handle.words().len(), 2);
+        file_handle.save_undo(0, 0);
+        file_handle.insert(0, 0, "b");
+        file_handle.update_matches("b");
+        assert_eq!(file_handle.matches("b").map(|matches| matches.len()), Some(5));
+        assert_eq!(file_handle.words().len(), 3);
+        let changes = file_handle.changes;
+        assert_eq!(file_handle.remove(9, 0), None);
+        file_handle.update_matches("b");
+        assert_eq!(file_handle.changes, changes);
+        file_handle.undo(0, 0);
+        file_handle.update_matches("b");
+        assert_eq!(file_handle.matches("b").map(|matches| matches.len()), Some(4));
+        file_handle.redo(0, 0);
+        file_handle.update_matches("b");
+        assert_eq!(file_handle.matches("b").map(|matches| matches.len()), Some(5));
+        file_handle.retab(4, true);
+        assert!(file_handle.changes > changes + 2);
+    }
+
+    #[test]
+    fn words_follow_content() {
+        let mut file_handle = file(&["foo(bar, foo)", "", "fn_1 é"]);
+        assert_eq!(file_handle.words().iter().collect::<Vec<_>>(), ["bar", "fn_1", "foo", "é"]);
+        file_handle.content_mut()[1] = "baz".to_string();
+        assert!(file_handle.words().contains("baz"));
+    }
+
+    #[test]
+    fn build_diagnostics_follow_rows() {
+        let diagnostic = |y: usize, source: &'static str| diagnostic::Diagnostic {
+            severity: diagnostic::Severity::Error,
+            start: (0, y),
+            end: (0, y),
+            message: String::new(),
+            source,
+        };
+        let mut file_handle = file(&["a", "b", "c", "d"]);
+        file_handle.set_diagnostics(diagnostic::SOURCE_MAKE, vec![diagnostic(0, diagnostic::SOURCE_MAKE), diagnostic(1, diagnostic::SOURCE_MAKE), diagnostic(3, diagnostic::SOURCE_MAKE)]);
+        file_handle.set_diagnostics(diagnostic::SOURCE_LSP, vec![diagnostic(1, diagnostic::SOURCE_LSP)]);
+        assert!(!file_handle.update_diagnostics());
+        file_handle.content.insert(1, "x".to_string());
+        file_handle.content.insert(1, "y".to_string());
+        assert!(file_handle.update_diagnostics());
+        let rows: Vec<(usize, &str)> = file_handle.diagnostics.iter().map(|d| (d.start.1, d.source)).collect();
+        assert_eq!(rows, [(0, diagnostic::SOURCE_MAKE), (1, diagnostic::SOURCE_LSP), (3, diagnostic::SOURCE_MAKE), (5, diagnostic::SOURCE_MAKE)]);
+        file_handle.content[3] = "bb".to_string();
+        file_handle.content.remove(0);
+        assert!(file_handle.update_diagnostics());
+        let rows: Vec<(usize, &str)> = file_handle.diagnostics.iter().map(|d| (d.start.1, d.source)).collect();
+        assert_eq!(rows, [(1, diagnostic::SOURCE_LSP), (4, diagnostic::SOURCE_MAKE)]);
+    }
+
+    #[test]
+    fn undo_steps_capped() {
+        let mut file_handle = file(&["0"]);
+        for i in 1..=MAX_UNDO_STEPS + 10 {
+            file_handle.save_undo(0, 0);
+            file_handle.content[0] = i.to_string();
+        }
+        while file_handle.undo(0, 0).is_some() {}
+        assert_eq!(file_handle.content, ["10"]);
+    }
 }
@@ -0,0 +1,422 @@
+//! # CONTENT
+//! open file buffers
+
+use {
+    std:: {
+        fs,
+        io,
+    },
+    crate:: {
+        diagnostic,
+        display,
+        file,
+        location,
+    },
+};
+
+/// # CONTENT
+/// buffer cmd requested by Cmd and run by Files::run
+/// - Add: show a generated buffer (replacing an open buffer of the same name) and a message (buffer info if empty)
+/// - Close: close the current buffer
+/// - Diagnostics: list the diagnostics of open files in a location list buffer
+/// - Jump: open a path, move center to a pos (x, y) and show a message (buffer info if empty)
+/// - List: list open buffers
+/// - Name: switch to a buffer by number or (part of) path
+/// - Next: switch to the next buffer
+/// - Open: open a path (or switch to it if already open)
+/// - Prev: switch to the previous buffer
+pub enum Request {
+    Add(Box<file::File>, String),
+    Close,
+    Diagnostics,
+    Jump(String, (usize, usize), String),
+    List,
+    Name(String),
+    Next,
+    Open(String),
+    Prev,
+}
+
+/// # CONTENT
+/// open file buffers
+/// # FIELD
+/// - files: open files and their remembered center pos (x, y)
+/// - current: index of the shown file
+/// - diagnostics: (path, diagnostic) of build and linter output (attached to files opened later too)
+pub struct Files {
+    files: Vec<(file::File, (usize, usize))>,
+    current: usize,
+    diagnostics: Vec<(String, diagnostic::Diagnostic)>,
+}
+
+/// # CONTENT
+/// read a file (a missing path is a new empty file)
+/// # ARGUMENT
+/// - path: file path (None for an unnamed file)
+/// # RETURN VALUE
+/// Ok(file): read file
+/// Err(...): read error
+pub fn load(path: Option<String>) -> io::Result<file::File> {
+    let mut file_handle = file::File::new(path);
+    match file_handle.read() {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            file_handle.content = vec![String::new()];
+            file_handle.update_syntax();
+        }
+        result => result?,
+    }
+    Ok(file_handle)
+}
+
+/// # CONTENT
+/// check if two paths name the same file
+/// # ARGUMENT
+/// - a: path
+/// - b: path
+/// # RETURN VALUE
+/// true if equal or canonicalized to the same path
+fn same_path(a: &str, b: &str) -> bool {
+    a == b || matches!((fs::canonicalize(a), fs::canonicalize(b)), (Ok(a), Ok(b)) if a == b)
+}
+
+/// # CONTENT
+/// attach the diagnostics of a source to a file (replacing the attached ones of the source)
+/// # ARGUMENT
+/// - diagnostics: (path, diagnostic) of build and linter output
+/// - file_handle: file to attach to
+/// - source: attached source
+/// # RETURN VALUE
+/// none
+fn attach(diagnostics: &[(String, diagnostic::Diagnostic)], file_handle: &mut file::File, source: &str) {
+    let Some(path) = file_handle.path.as_deref() else {
+        return;
+    };
+    let attached = diagnostics.iter().filter(|(p, d)| d.source == source && same_path(p, path)).map(|(_, d)| d.clone()).collect();
+    file_handle.set_diagnostics(source, attached);
+}
+
+impl Files {
+    /// # CONTENT
+    /// init Files reading paths
+    /// # ARGUMENT
+    /// - paths: file paths (an unnamed file if empty)
+    /// # RETURN VALUE
+    /// Ok(files): inited Files
+    /// Err(...): read error
+    pub fn open(paths: &[String]) -> io::Result<Self> {
+        let mut files = Vec::new();
+        for path in paths {
+            files.push((load(Some(path.clone()))?, (0, 0)));
+        }
+        if files.is_empty() {
+            files.push((load(None)?, (0, 0)));
+        }
+        Ok(Self {
+            files,
+            current: 0,
+            diagnostics: Vec::new(),
+        })
+    }
+
+    /// # CONTENT
+    /// get the shown file
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// shown file
+    pub fn current(&self) -> &file::File {
+        &self.files[self.current].0
+    }
+
+    /// # CONTENT
+    /// get the shown file to edit
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// shown file
+    pub fn current_mut(&mut self) -> &mut file::File {
+        &mut self.files[self.current].0
+    }
+
+    /// # CONTENT
+    /// get the shown file index
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// index of the shown file
+    pub fn index(&self) -> usize {
+        self.current
+    }
+
+    /// # CONTENT
+    /// get the open file count
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// file count
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    /// # CONTENT
+    /// get a file
+    /// # ARGUMENT
+    /// - index: file index
+    /// # RETURN VALUE
+    /// file
+    pub fn get(&self, index: usize) -> &file::File {
+        &self.files[index].0
+    }
+
+    /// # CONTENT
+    /// get a file to edit
+    /// # ARGUMENT
+    /// - index: file index
+    /// # RETURN VALUE
+    /// file
+    pub fn get_mut(&mut self, index: usize) -> &mut file::File {
+        &mut self.files[index].0
+    }
+
+    /// # CONTENT
+    /// add a file without showing it
+    /// # ARGUMENT
+    /// - file_handle: added file
+    /// # RETURN VALUE
+    /// index of the added file
+    pub fn add(&mut self, file_handle: file::File) -> usize {
+        self.files.push((file_handle, (0, 0)));
+        self.files.len() - 1
+    }
+
+    /// # CONTENT
+    /// replace the diagnostics of a source of build or linter output
+    /// # ARGUMENT
+    /// - source: producer of the output
+    /// - diagnostics: (path, diagnostic) parsed from the output
+    /// # RETURN VALUE
+    /// none
+    pub fn set_diagnostics(&mut self, source: &'static str, diagnostics: Vec<(String, diagnostic::Diagnostic)>) {
+        self.update_diagnostics();
+        self.diagnostics.retain(|(_, d)| d.source != source);
+        self.diagnostics.extend(diagnostics);
+        for (file_handle, _) in self.files.iter_mut() {
+            attach(&self.diagnostics, file_handle, source);
+        }
+    }
+
+    /// # CONTENT
+    /// move the build and linter diagnostics of edited files with their rows (see File::update_diagnostics)
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// none
+    pub fn update_diagnostics(&mut self) {
+        for (file_handle, _) in self.files.iter_mut() {
+            if !file_handle.update_diagnostics() {
+                continue;
+            }
+            let Some(path) = file_handle.path.as_deref() else {
+                continue;
+            };
+            self.diagnostics.retain(|(p, _)| !same_path(p, path));
+            let kept = file_handle.diagnostics.iter().filter(|d| d.source != diagnostic::SOURCE_LSP);
+            self.diagnostics.extend(kept.map(|d| (path.to_string(), d.clone())));
+        }
+    }
+
+    /// # CONTENT
+    /// find a file
+    /// # ARGUMENT
+    /// - f: condition of the file
+    /// # RETURN VALUE
+    /// Some(index): index of the first matching file
+    /// None: no matching file
+    pub fn position(&self, f: impl Fn(&file::File) -> bool) -> Option<usize> {
+        self.files.iter().position(|(file_handle, _)| f(file_handle))
+    }
+
+    /// # CONTENT
+    /// show another file without touching the center pos (the window pane keeps it)
+    /// # ARGUMENT
+    /// - index: index of the shown file
+    /// # RETURN VALUE
+    /// none
+    pub fn select(&mut self, index: usize) {
+        self.current = index;
+    }
+
+    /// # CONTENT
+    /// show another file (remembering the center pos of the shown one)
+    /// # ARGUMENT
+    /// - index: index of the shown file
+    /// # RETURN VALUE
+    /// none
+    fn switch(&mut self, index: usize, display_handle: &mut display::Display) {
+        self.files[self.current].1 = (display_handle.center_x, display_handle.center_y);
+        self.current = index;
+        let (x, y) = self.files[index].1;
+        let content = &self.files[index].0.content;
+        display_handle.center_y = y.min(content.len() - 1);
+        display_handle.center_x = x.min(content[display_handle.center_y].chars().count());
+    }
+
+    /// # CONTENT
+    /// get the file index of a buffer name
+    /// # ARGUMENT
+    /// - name: buffer number (1 origin), path or part of a path
+    /// # RETURN VALUE
+    /// Ok(index)
+    /// Err(message): no or several matching buffers
+    fn find(&self, name: &str) -> Result<usize, String> {
+        if let Ok(n) = name.parse::<usize>() {
+            return if (1..=self.files.len()).contains(&n) { Ok(n - 1) } else { Err(format!("no buffer {}", n)) };
+        }
+        let path = |i: usize| self.files[i].0.path.clone().unwrap_or_default();
+        if let Some(i) = (0..self.files.len()).find(|&i| path(i) == name) {
+            return Ok(i);
+        }
+        let found: Vec<usize> = (0..self.files.len()).filter(|&i| path(i).contains(name)).collect();
+        match found.as_slice() {
+            [i] => Ok(*i),
+            [] => Err(format!("no buffer matching {}", name)),
+            _ => Err(format!("several buffers matching {}", name)),
+        }
+    }
+
+    /// # CONTENT
+    /// get the paths of the buffers
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// paths in buffer order (buffers without a path are skipped)
+    pub fn paths(&self) -> Vec<String> {
+        self.files.iter().filter_map(|(file_handle, _)| file_handle.path.clone()).collect()
+    }
+
+    /// # CONTENT
+    /// get printable buffer list
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// rows of "number  current mark  path  row count"
+    fn list(&self) -> Vec<String> {
+        self.files.iter().enumerate().map(|(i, (file_handle, _))| {
+            format!(
+                "{:>3} {} {} ({} rows)",
+                i + 1,
+                if i == self.current { "%" } else { " " },
+                file_handle.path.as_deref().unwrap_or("[no name]"),
+                file_handle.content.len(),
+            )
+        }).collect()
+    }
+
+    /// # CONTENT
+    /// run a buffer cmd
+    /// # ARGUMENT
+    /// - request: buffer cmd
+    /// # RETURN VALUE
+    /// message of the result
+    pub fn run(&mut self, request: Request, display_handle: &mut display::Display) -> String {
+        let len = self.files.len();
+        match request {
+            Request::Add(file_handle, added) => {
+                match self.files.iter().position(|(f, _)| f.path == file_handle.path) {
+                    Some(i) => {
+                        self.switch(i, display_handle);
+                        self.files[i].0 = *file_handle;
+                        display_handle.center_x = 0;
+                        display_handle.center_y = 0;
+                    }
+                    None => {
+                        self.files.push((*file_handle, (0, 0)));
+                        self.switch(len, display_handle);
+                    }
+                }
+                if !added.is_empty() {
+                    return added;
+                }
+            }
+            Request::Close if len == 1 => return "close: last buffer".to_string(),
+            Request::Close => {
+                let closed = self.current;
+                self.switch(if closed + 1 < len { closed + 1 } else { closed - 1 }, display_handle);
+                self.files.remove(closed);
+                if self.current > closed {
+                    self.current -= 1;
+                }
+            }
+            Request::Diagnostics => {
+                let locations: Vec<location::Location> = self.files.iter()
+                    .filter_map(|(file_handle, _)| file_handle.path.as_ref().filter(|_| file_handle.locations.is_none()).map(|path| (path, &file_handle.diagnostics)))
+                    .flat_map(|(path, diagnostics)| diagnostics.iter().map(|d| location::Location { path: path.clone(), y: d.start.1, x: d.start.0, text: d.summary() }))
+                    .collect();
+                if locations.is_empty() {
+                    return "diagnostics: no diagnostics in open files".to_string();
+                }
+                let count = locations.len();
+                return self.run(Request::Add(Box::new(file::File::locations(locations, "[diagnostics]".to_string())), format!("diagnostics: {} in open files", count)), display_handle);
+            }
+            Request::Jump(path, (x, y), jumped) => {
+                let message = self.run(Request::Open(path), display_handle);
+                if message.starts_with("edit:") {
+                    return message;
+                }
+                let content = &self.current().content;
+                display_handle.center_y = y.min(content.len() - 1);
+                display_handle.center_x = x.min(content[display_handle.center_y].chars().count());
+                return if jumped.is_empty() { message } else { jumped };
+            }
+            Request::List => return self.list().join("\n"),
+            Request::Name(name) => match self.find(&name) {
+                Ok(i) => self.switch(i, display_handle),
+                Err(e) => return format!("buffer: {}", e),
+            },
+            Request::Next => self.switch((self.current + 1) % len, display_handle),
+            Request::Open(path) => match self.files.iter().position(|(f, _)| f.path.as_deref() == Some(path.as_str())) {
+                Some(i) => self.switch(i, display_handle),
+                None => match load(Some(path)) {
+                    Ok(mut file_handle) => {
+                        for source in [diagnostic::SOURCE_LINT, diagnostic::SOURCE_MAKE] {
+                            attach(&self.diagnostics, &mut file_handle, source);
+                        }
+                        self.files.push((file_handle, (0, 0)));
+                        self.switch(len, display_handle);
+                    }
+                    Err(e) => return format!("edit: {}", e),
+                },
+            },
+            Request::Prev => self.switch((self.current + len - 1) % len, display_handle),
+        }
+        format!("buffer {} of {}: {}", self.current + 1, self.files.len(), self.current().path.as_deref().unwrap_or("[no name]"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std:: {
+            env,
+            fs,
+            process,
+        },
+    };
+
+    #[test]
+    fn open_empty_file() {
+        let path = env::temp_dir().join(format!("center_empty_{}.txt", process::id())).to_string_lossy().to_string();
+        fs::write(&path, "").unwrap();
+        let mut files_handle = Files::open(&[]).unwrap();
+        let mut display_handle = display::Display::new();
+        display_handle.center_x = 3;
+        files_handle.run(Request::Open(path.clone()), &mut display_handle);
+        assert_eq!(files_handle.current().content, [""]);
+        assert_eq!((display_handle.center_x, display_handle.center_y), (0, 0));
+        files_handle.run(Request::Prev, &mut display_handle);
+        files_handle.run(Request::Next, &mut display_handle);
+        assert_eq!(files_handle.current().path.as_deref(), Some(path.as_str()));
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,467 @@
+//! # CONTENT
+//! fuzzy file finder walking a directory
+
+use {
+    std:: {
+        fs,
+        io::Read,
+        path,
+    },
+};
+
+/// # CONTENT
+/// walked file count limit
+const MAX_FILES: usize = 50000;
+
+/// # CONTENT
+/// preview read byte limit
+const MAX_PREVIEW: u64 = 65536;
+
+/// # CONTENT
+/// score of a matched char
+const SCORE_MATCH: isize = 16;
+
+/// # CONTENT
+/// bonus of a match at the start of a word (after '/', '_', '-', '.' or ' ')
+const BONUS_BOUNDARY: isize = 8;
+
+/// # CONTENT
+/// bonus of a match at a lower to upper case change
+const BONUS_CAMEL: isize = 7;
+
+/// # CONTENT
+/// bonus of a match right after the previous match
+const BONUS_CONSECUTIVE: isize = 4;
+
+/// # CONTENT
+/// bonus of a match in the file name
+const BONUS_FILE_NAME: isize = 2;
+
+/// # CONTENT
+/// penalty of each skipped char between matches
+const PENALTY_GAP: isize = 1;
+
+/// # CONTENT
+/// .gitignore rule
+/// # FIELD
+/// - base: directory of the .gitignore (relative path ending with '/', or empty for the root)
+/// - pattern: glob pattern
+/// - negate: '!' rule (un-ignores)
+/// - dir_only: rule ending with '/' (matches directories only)
+/// - anchored: rule containing '/' (matches the path from base, not the name)
+struct Rule {
+    base: String,
+    pattern: Vec<char>,
+    negate: bool,
+    dir_only: bool,
+    anchored: bool,
+}
+
+/// # CONTENT
+/// parse a .gitignore
+/// # ARGUMENT
+/// - base: directory of the .gitignore (relative path ending with '/', or empty for the root)
+/// - text: .gitignore content
+/// # RETURN VALUE
+/// rules in file order
+fn parse_gitignore(base: &str, text: &str) -> Vec<Rule> {
+    text.lines().filter_map(|line| {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let (negate, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let anchored = line.contains('/');
+        Some(Rule {
+            base: base.to_string(),
+            pattern: line.trim_start_matches('/').chars().collect(),
+            negate,
+            dir_only,
+            anchored,
+        })
+    }).collect()
+}
+
+/// # CONTENT
+/// match a gitignore glob ('*' and '?' do not match '/', '**' matches directories, [...] is a char class)
+/// # ARGUMENT
+/// - pattern: glob pattern
+/// - text: matched text
+/// # RETURN VALUE
+/// true: matched
+fn glob(pattern: &[char], text: &[char]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        ['*', '*', '/', rest @ ..] => glob(rest, text) || (0..text.len()).any(|i| text[i] == '/' && glob(rest, &text[i + 1..])),
+        ['*', '*', rest @ ..] => (0..=text.len()).any(|i| glob(rest, &text[i..])),
+        ['*', rest @ ..] => (0..=text.len()).take_while(|&i| i == 0 || text[i - 1] != '/').any(|i| glob(rest, &text[i..])),
+        ['?', rest @ ..] => text.first().is_some_and(|&c| c != '/') && glob(rest, &text[1..]),
+        ['[', class @ ..] if class.iter().skip(1).any(|&c| c == ']') => {
+            let end = class.iter().skip(1).position(|&c| c == ']').map_or(0, |i| i + 1);
+            let (negate, set) = match &class[..end] {
+                ['!' | '^', set @ ..] => (true, set),
+                set => (false, set),
+            };
+            let Some(&c) = text.first() else {
+                return false;
+            };
+            let mut found = false;
+            let mut i = 0;
+            while i < set.len() {
+                if i + 2 < set.len() && set[i + 1] == '-' {
+                    found |= (set[i]..=set[i + 2]).contains(&c);
+                    i += 3;
+                } else {
+                    found |= set[i] == c;
+                    i += 1;
+                }
+            }
+            found != negate && c != '/' && glob(&class[end + 1..], &text[1..])
+        }
+        ['\\', c, rest @ ..] | [c, rest @ ..] => text.first() == Some(c) && glob(rest, &text[1..]),
+    }
+}
+
+/// # CONTENT
+/// check if a path is ignored (the last matching rule decides)
+/// # ARGUMENT
+/// - rules: rules from the root to the deepest directory
+/// - rel: path relative to the walked root
+/// - is_dir: the path is a directory
+/// # RETURN VALUE
+/// true: ignored
+fn ignored(rules: &[Rule], rel: &str, is_dir: bool) -> bool {
+    let name = rel.rsplit('/').next().unwrap_or(rel);
+    for rule in rules.iter().rev() {
+        let Some(sub) = rel.strip_prefix(rule.base.as_str()) else {
+            continue;
+        };
+        if rule.dir_only && !is_dir {
+            continue;
+        }
+        let target: Vec<char> = if rule.anchored { sub.chars().collect() } else { name.chars().collect() };
+        if glob(&rule.pattern, &target) {
+            return !rule.negate;
+        }
+    }
+    false
+}
+
+/// # CONTENT
+/// walk a directory for files (skipping .git and ignored paths, not following symlinked directories)
+/// # ARGUMENT
+/// - root: walked root
+/// - dir: walked directory relative to root (empty or ending with '/')
+/// - rules: .gitignore rules of the parent directories
+/// - paths: found file paths relative to root (pushed)
+/// # RETURN VALUE
+/// none
+fn walk(root: &path::Path, dir: &str, rules: &mut Vec<Rule>, paths: &mut Vec<String>) {
+    let len = rules.len();
+    if let Ok(text) = fs::read_to_string(root.join(dir).join(".gitignore")) {
+        rules.extend(parse_gitignore(dir, &text));
+    }
+    let Ok(entries) = fs::read_dir(root.join(dir)) else {
+        rules.truncate(len);
+        return;
+    };
+    let mut entries: Vec<(String, bool)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| Some((entry.file_name().into_string().ok()?, entry.file_type().ok()?.is_dir())))
+        .collect();
+    entries.sort();
+    for (name, is_dir) in entries {
+        let rel = format!("{}{}", dir, name);
+        if paths.len() >= MAX_FILES || name == ".git" || ignored(rules, &rel, is_dir) {
+            continue;
+        }
+        if is_dir {
+            walk(root, &format!("{}/", rel), rules, paths);
+        } else {
+            paths.push(rel);
+        }
+    }
+    rules.truncate(len);
+}
+
+/// # CONTENT
+/// list files under a directory (skipping .git and ignored paths)
+/// # ARGUMENT
+/// - root: walked root
+/// # RETURN VALUE
+/// file paths relative to root in walk order
+pub fn files(root: &str) -> Vec<String> {
+    let mut paths = Vec::new();
+    walk(path::Path::new(root), "", &mut Vec::new(), &mut paths);
+    paths
+}
+
+/// # CONTENT
+/// score a path by a query matched as a subsequence
+/// (case-insensitive unless the query has an upper case char)
+/// # ARGUMENT
+/// - query: typed query
+/// - path: scored path
+/// # RETURN VALUE
+/// Some((score, matched char indexes)): the query is a subsequence of the path
+/// None: no match
+pub fn score(query: &str, path: &str) -> Option<(isize, Vec<usize>)> {
+    let case = query.chars().any(|c| c.is_uppercase());
+    let fold = |c: char| if case { c } else { c.to_lowercase().next().unwrap_or(c) };
+    let q: Vec<char> = query.chars().map(fold).collect();
+    let chars: Vec<char> = path.chars().collect();
+    let t: Vec<char> = chars.iter().map(|&c| fold(c)).collect();
+    let (m, n) = (q.len(), t.len());
+    if m == 0 {
+        return Some((0, Vec::new()));
+    }
+    let name_start = chars.iter().rposition(|&c| c == '/').map(|i| i + 1).unwrap_or(0);
+    let bonus = |j: usize| {
+        let boundary = j == 0 || matches!(chars[j - 1], '/' | '_' | '-' | '.' | ' ');
+        let camel = j > 0 && chars[j - 1].is_lowercase() && chars[j].is_uppercase();
+        SCORE_MATCH
+            + if boundary { BONUS_BOUNDARY } else if camel { BONUS_CAMEL } else { 0 }
+            + if j >= name_start { BONUS_FILE_NAME } else { 0 }
+    };
+
+    // scores[i][j]: best score with q[i] matched at t[j], prev[i][j]: t index of q[i - 1]
+    let mut scores: Vec<Vec<Option<isize>>> = vec![vec![None; n]; m];
+    let mut prev = vec![vec![0; n]; m];
+    for j in 0..n {
+        if t[j] == q[0] {
+            scores[0][j] = Some(bonus(j));
+        }
+    }
+    for i in 1..m {
+        // best (score + gap penalty * k, k) of matches of q[i - 1] at k < j - 1
+        let mut run: Option<(isize, usize)> = None;
+        for j in 1..n {
+            if j >= 2
+                && let Some(s) = scores[i - 1][j - 2]
+                && run.is_none_or(|(r, _)| s + PENALTY_GAP * (j - 2) as isize >= r) {
+                run = Some((s + PENALTY_GAP * (j - 2) as isize, j - 2));
+            }
+            if t[j] != q[i] {
+                continue;
+            }
+            let gapped = run.map(|(r, k)| (r - PENALTY_GAP * (j - 1) as isize, k));
+            let consecutive = scores[i - 1][j - 1].map(|s| (s + BONUS_CONSECUTIVE, j - 1));
+            if let Some((s, k)) = gapped.into_iter().chain(consecutive).max() {
+                scores[i][j] = Some(s + bonus(j));
+                prev[i][j] = k;
+            }
+        }
+    }
+    let (best, mut j) = (0..n).filter_map(|j| scores[m - 1][j].map(|s| (s, j))).max()?;
+    let mut matched = vec![j; m];
+    for i in (1..m).rev() {
+        j = prev[i][j];
+        matched[i - 1] = j;
+    }
+    Some((best, matched))
+}
+
+/// # CONTENT
+/// fuzzy finder state
+/// # FIELD
+/// - root: walked root
+/// - paths: walked file paths relative to root
+/// - matches: (path, matched char indexes) ranked by score
+/// - selected: index of the highlighted match
+pub struct Finder {
+    root: String,
+    paths: Vec<String>,
+    pub matches: Vec<(String, Vec<usize>)>,
+    pub selected: usize,
+}
+
+impl Finder {
+    /// # CONTENT
+    /// init Finder walking a directory
+    /// # ARGUMENT
+    /// - root: walked root
+    /// # RETURN VALUE
+    /// inited Finder (all paths match the empty query)
+    pub fn new(root: &str) -> Self {
+        let mut finder = Self {
+            root: root.to_string(),
+            paths: files(root),
+            matches: Vec::new(),
+            selected: 0,
+        };
+        finder.update("");
+        finder
+    }
+
+    /// # CONTENT
+    /// get walked file count
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// file count
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// # CONTENT
+    /// rank paths by a query (higher score, then shorter path first)
+    /// # ARGUMENT
+    /// - query: typed query
+    /// # RETURN VALUE
+    /// none
+    pub fn update(&mut self, query: &str) {
+        let mut scored: Vec<(isize, &String, Vec<usize>)> = self.paths.iter()
+            .filter_map(|path| score(query, path).map(|(s, matched)| (s, path, matched)))
+            .collect();
+        scored.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.len().cmp(&b.1.len())).then(a.1.cmp(b.1)));
+        self.matches = scored.into_iter().map(|(_, path, matched)| (path.clone(), matched)).collect();
+        self.selected = 0;
+    }
+
+    /// # CONTENT
+    /// move the highlighted match
+    /// # ARGUMENT
+    /// - down: true: next match, false: previous match
+    /// # RETURN VALUE
+    /// none
+    pub fn select(&mut self, down: bool) {
+        self.selected = if down { (self.selected + 1).min(self.matches.len().saturating_sub(1)) } else { self.selected.saturating_sub(1) };
+    }
+
+    /// # CONTENT
+    /// get the path of the highlighted match
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// Some(path): path joined to root
+    /// None: no match
+    pub fn path(&self) -> Option<String> {
+        let (rel, _) = self.matches.get(self.selected)?;
+        Some(if self.root == "." { rel.clone() } else { path::Path::new(&self.root).join(rel).to_string_lossy().to_string() })
+    }
+
+    /// # CONTENT
+    /// get the first rows of the highlighted file
+    /// # ARGUMENT
+    /// - rows: row count
+    /// # RETURN VALUE
+    /// preview rows (a note for an unreadable or binary file)
+    pub fn preview(&self, rows: usize) -> Vec<String> {
+        let Some(path) = self.path() else {
+            return Vec::new();
+        };
+        let mut bytes = Vec::new();
+        if let Err(e) = fs::File::open(&path).and_then(|file| file.take(MAX_PREVIEW).read_to_end(&mut bytes)) {
+            return vec![format!("({})", e)];
+        }
+        if bytes.contains(&0) {
+            return vec!["(binary file)".to_string()];
+        }
+        String::from_utf8_lossy(&bytes).lines().take(rows).map(|row| row.to_string()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, text: &str) -> bool {
+        glob(&pattern.chars().collect::<Vec<char>>(), &text.chars().collect::<Vec<char>>())
+    }
+
+    fn is_ignored(gitignore: &str, rel: &str, is_dir: bool) -> bool {
+        ignored(&parse_gitignore("", gitignore), rel, is_dir)
+    }
+
+    #[test]
+    fn glob_wildcards() {
+        assert!(matches("*.rs", "main.rs"));
+        assert!(!matches("*.rs", "src/main.rs"));
+        assert!(matches("a?c", "abc"));
+        assert!(!matches("a?c", "a/c"));
+        assert!(matches("[a-c]x", "bx"));
+        assert!(!matches("[!a-c]x", "bx"));
+        assert!(matches(r"\*", "*"));
+        assert!(!matches(r"\*", "a"));
+    }
+
+    #[test]
+    fn glob_double_star() {
+        assert!(matches("**/a", "a"));
+        assert!(matches("**/a", "x/y/a"));
+        assert!(!matches("**/a", "xa"));
+        assert!(matches("a/**", "a/x/y"));
+        assert!(matches("a/**/b", "a/b"));
+        assert!(matches("a/**/b", "a/x/y/b"));
+        assert!(!matches("a/**/b", "a/xb"));
+    }
+
+    #[test]
+    fn gitignore_rules() {
+        let rules = parse_gitignore("sub/", "# comment\n\n!keep.log  \n\\!bang\nbuild/\n/root.txt\ndoc/*.md\n");
+        let parsed: Vec<(String, bool, bool, bool)> = rules.iter().map(|r| (r.pattern.iter().collect(), r.negate, r.dir_only, r.anchored)).collect();
+        assert_eq!(parsed, [
+            ("keep.log".to_string(), true, false, false),
+            ("!bang".to_string(), false, false, false),
+            ("build".to_string(), false, true, false),
+            ("root.txt".to_string(), false, false, true),
+            ("doc/*.md".to_string(), false, false, true),
+        ]);
+        assert!(rules.iter().all(|r| r.base == "sub/"));
+    }
+
+    #[test]
+    fn gitignore_matching() {
+        // negation (the last matching rule decides)
+        assert!(is_ignored("*.log\n!keep.log", "a.log", false));
+        assert!(!is_ignored("*.log\n!keep.log", "x/keep.log", false));
+        assert!(is_ignored("!keep.log\n*.log", "keep.log", false));
+        // dir_only
+        assert!(is_ignored("build/", "x/build", true));
+        assert!(!is_ignored("build/", "x/build", false));
+        // anchored rules match the path from the base, others the name
+        assert!(is_ignored("/root.txt", "root.txt", false));
+        assert!(!is_ignored("/root.txt", "x/root.txt", false));
+        assert!(is_ignored("root.txt", "x/root.txt", false));
+        assert!(is_ignored("doc/*.md", "doc/a.md", false));
+        assert!(!is_ignored("doc/*.md", "x/doc/a.md", false));
+        assert!(is_ignored("**/doc/*.md", "x/doc/a.md", false));
+        // rules of a subdirectory only apply under it
+        let rules = parse_gitignore("sub/", "/a");
+        assert!(ignored(&rules, "sub/a", false));
+        assert!(!ignored(&rules, "a", false));
+    }
+
+    #[test]
+    fn score_matches() {
+        assert_eq!(score("", "a"), Some((0, Vec::new())));
+        assert_eq!(score("ba", "ab"), None);
+        assert_eq!(score("ab", "xaxb").map(|(_, matched)| matched), Some(vec![1, 3]));
+        assert!(score("AB", "ab").is_none());
+        assert!(score("ab", "AB").is_some());
+    }
+
+    #[test]
+    fn score_order() {
+        let rank = |query: &str, a: &str, b: &str| score(query, a).unwrap().0 > score(query, b).unwrap().0;
+        // consecutive over gapped
+        assert!(rank("ab", "xabx", "xaxb"));
+        // word boundary over mid-word
+        assert!(rank("b", "a_b", "ab"));
+        // camel case over mid-word
+        assert!(rank("b", "aB", "ab"));
+        // file name over directory
+        assert!(rank("a", "x/a", "a/x"));
+        // a short gap over a long gap
+        assert!(rank("ab", "axb", "axxxb"));
+        // the best alignment is picked
+        assert_eq!(score("ab", "axxxxxxxxxxab").map(|(_, matched)| matched), Some(vec![11, 12]));
+    }
+}
@@ -0,0 +1,280 @@
+//! # CONTENT
+//! project-wide regex search
+
+use {
+    std:: {
+        fs,
+        path,
+        sync:: {
+            atomic:: {
+                AtomicUsize,
+                Ordering,
+            },
+            mpsc,
+        },
+        thread,
+    },
+    crate:: {
+        finder,
+        location,
+    },
+    regex:: {
+        Regex,
+        RegexBuilder,
+    },
+};
+
+/// # CONTENT
+/// match count limit (files are not searched after it is reached)
+pub const MAX_MATCHES: usize = 10000;
+
+/// # CONTENT
+/// snippet char count limit
+const MAX_SNIPPET: usize = 200;
+
+/// # CONTENT
+/// searched file size limit (larger files are skipped)
+const MAX_FILE_SIZE: u64 = 16 * 1024 * 1024;
+
+/// # CONTENT
+/// result of a search
+/// # FIELD
+/// - matches: matches in walk order (at most MAX_MATCHES)
+/// - searched: searched file count
+/// - skipped: file count over MAX_FILE_SIZE
+/// - failed: file count of panicked worker threads (their matches are lost)
+pub struct Summary {
+    pub matches: Vec<location::Location>,
+    pub searched: usize,
+    pub skipped: usize,
+    pub failed: usize,
+}
+
+/// # CONTENT
+/// search running in a thread
+/// # FIELD
+/// - pattern: searched pattern
+/// - receiver: summary sent when the search ends
+pub struct Job {
+    pub pattern: String,
+    receiver: mpsc::Receiver<Summary>,
+}
+
+/// # CONTENT
+/// compile a grep pattern (case-insensitive unless it has an uppercase char)
+/// # ARGUMENT
+/// - pattern: regex pattern
+/// # RETURN VALUE
+/// Ok(regex): compiled pattern
+/// Err(message): invalid pattern
+pub fn regex(pattern: &str) -> Result<Regex, String> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(!pattern.chars().any(|c| c.is_uppercase()))
+        .build()
+        .map_err(|e| e.to_string())
+}
+
+/// # CONTENT
+/// search a file
+/// # ARGUMENT
+/// - path: searched path
+/// - regex: searched pattern
+/// - limit: match count limit
+/// # RETURN VALUE
+/// Some(matches): matches of each row (none for an unreadable or binary file)
+/// None: skipped file over MAX_FILE_SIZE
+fn search_file(path: &str, regex: &Regex, limit: usize) -> Option<Vec<location::Location>> {
+    if fs::metadata(path).is_ok_and(|metadata| metadata.len() > MAX_FILE_SIZE) {
+        return None;
+    }
+    let Ok(bytes) = fs::read(path) else {
+        return Some(Vec::new());
+    };
+    if bytes[..bytes.len().min(8192)].contains(&0) {
+        return Some(Vec::new());
+    }
+    let text = String::from_utf8_lossy(&bytes);
+    let mut matches = Vec::new();
+    for (y, row) in text.lines().enumerate() {
+        for m in regex.find_iter(row) {
+            if matches.len() == limit {
+                return Some(matches);
+            }
+            matches.push(location::Location {
+                path: path.to_string(),
+                y,
+                x: row[..m.start()].chars().count(),
+                text: row.trim().chars().take(MAX_SNIPPET).collect(),
+            });
+        }
+    }
+    Some(matches)
+}
+
+/// # CONTENT
+/// search every file under a directory (skipping ignored, binary and large files) in worker threads
+/// # ARGUMENT
+/// - root: searched directory
+/// - regex: searched pattern
+/// # RETURN VALUE
+/// Summary
+pub fn search(root: &str, regex: &Regex) -> Summary {
+    let paths: Vec<String> = finder::files(root).into_iter()
+        .map(|rel| if root == "." { rel } else { path::Path::new(root).join(rel).to_string_lossy().to_string() })
+        .collect();
+    let workers = thread::available_parallelism().map_or(4, |n| n.get());
+    let chunk = paths.len().div_ceil(workers).max(1);
+    let found = AtomicUsize::new(0);
+    let mut summary = Summary { matches: Vec::new(), searched: 0, skipped: 0, failed: 0 };
+    thread::scope(|scope| {
+        let found = &found;
+        let handles: Vec<_> = paths.chunks(chunk)
+            .map(|chunk| (chunk.len(), scope.spawn(move || {
+                let mut results = Vec::new();
+                for path in chunk {
+                    let limit = MAX_MATCHES.saturating_sub(found.load(Ordering::Relaxed));
+                    if limit == 0 {
+                        break;
+                    }
+                    let result = search_file(path, regex, limit);
+                    found.fetch_add(result.as_ref().map_or(0, |matches| matches.len()), Ordering::Relaxed);
+                    results.push(result);
+                }
+                results
+            })))
+            .collect();
+        for (len, handle) in handles {
+            match handle.join() {
+                Ok(results) => {
+                    for result in results {
+                        match result {
+                            Some(matches) => {
+                                let room = MAX_MATCHES - summary.matches.len();
+                                summary.matches.extend(matches.into_iter().take(room));
+                                summary.searched += 1;
+                            }
+                            None => summary.skipped += 1,
+                        }
+                    }
+                }
+                Err(_) => summary.failed += len,
+            }
+        }
+    });
+    summary
+}
+
+impl Job {
+    /// # CONTENT
+    /// start a search of every file under a directory in a thread
+    /// # ARGUMENT
+    /// - root: searched directory
+    /// - regex: searched pattern
+    /// # RETURN VALUE
+    /// running Job
+    pub fn spawn(root: &str, regex: Regex) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let (root, pattern) = (root.to_string(), regex.as_str().to_string());
+        thread::spawn(move || {
+            let _ = sender.send(search(&root, &regex));
+        });
+        Self {
+            pattern,
+            receiver,
+        }
+    }
+
+    /// # CONTENT
+    /// get the summary if the search ended (without waiting)
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// Some(Ok(summary)): ended search
+    /// Some(Err(message)): lost thread
+    /// None: still running
+    pub fn poll(&self) -> Option<Result<Summary, String>> {
+        match self.receiver.try_recv() {
+            Ok(summary) => Some(Ok(summary)),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => Some(Err("search thread exited without matches".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::*,
+        std:: {
+            env,
+            process,
+            time,
+        },
+    };
+
+    fn temp_dir(name: &str) -> path::PathBuf {
+        let dir = env::temp_dir().join(format!("center_grep_{}_{}", name, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn regex_smartcase() {
+        assert!(regex("foo").unwrap().is_match("a FOO"));
+        assert!(regex("Foo").unwrap().is_match("a Foo"));
+        assert!(!regex("Foo").unwrap().is_match("a foo"));
+        assert!(regex("(").is_err());
+    }
+
+    #[test]
+    fn search_file_matches() {
+        let dir = temp_dir("file");
+        let text = dir.join("text.txt").to_string_lossy().to_string();
+        fs::write(&text, "ab foo\n  éé foo foo  \n").unwrap();
+        let regex = regex("foo").unwrap();
+        let matches = search_file(&text, &regex, MAX_MATCHES).unwrap();
+        let found: Vec<(usize, usize, &str)> = matches.iter().map(|m| (m.x, m.y, m.text.as_str())).collect();
+        assert_eq!(found, [(3, 0, "ab foo"), (5, 1, "éé foo foo"), (9, 1, "éé foo foo")]);
+        assert_eq!(search_file(&text, &regex, 2).unwrap().len(), 2);
+
+        let binary = dir.join("binary.bin").to_string_lossy().to_string();
+        fs::write(&binary, b"foo\0foo\n").unwrap();
+        assert_eq!(search_file(&binary, &regex, MAX_MATCHES).map(|matches| matches.len()), Some(0));
+
+        let large = dir.join("large.txt").to_string_lossy().to_string();
+        fs::File::create(&large).unwrap().set_len(MAX_FILE_SIZE + 1).unwrap();
+        assert!(search_file(&large, &regex, MAX_MATCHES).is_none());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn search_dir() {
+        let dir = temp_dir("dir");
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("a.txt"), "foo\nbar\n").unwrap();
+        fs::write(dir.join("sub").join("b.txt"), "x foo\n").unwrap();
+        fs::write(dir.join("c.bin"), b"foo\0").unwrap();
+        fs::File::create(dir.join("large.txt")).unwrap().set_len(MAX_FILE_SIZE + 1).unwrap();
+        let root = dir.to_string_lossy().to_string();
+        let summary = search(&root, &regex("foo").unwrap());
+        let mut found: Vec<(String, usize, usize)> = summary.matches.iter()
+            .map(|m| (path::Path::new(&m.path).strip_prefix(&dir).unwrap().to_string_lossy().to_string(), m.x, m.y))
+            .collect();
+        found.sort();
+        let b = path::Path::new("sub").join("b.txt").to_string_lossy().to_string();
+        assert_eq!(found, [("a.txt".to_string(), 0, 0), (b, 2, 0)]);
+        assert_eq!((summary.searched, summary.skipped, summary.failed), (3, 1, 0));
+
+        let job = Job::spawn(&root, regex("bar").unwrap());
+        let summary = loop {
+            if let Some(result) = job.poll() {
+                break result.unwrap();
+            }
+            thread::sleep(time::Duration::from_millis(10));
+        };
+        assert_eq!(summary.matches.len(), 1);
+        assert_eq!(job.pattern, "bar");
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
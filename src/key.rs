@@ -0,0 +1,259 @@
+//! # CONTENT
+//! key event passed to Cmd::key
+
+use {
+    crossterm:: {
+        event,
+    },
+};
+
+/// # CONTENT
+/// key code
+#[derive(Clone, Copy, PartialEq)]
+pub enum Code {
+    Backspace,
+    Char(char),
+    Delete,
+    Down,
+    End,
+    Enter,
+    Escape,
+    Home,
+    Left,
+    PageDown,
+    PageUp,
+    Right,
+    Tab,
+    Up,
+}
+
+/// # CONTENT
+/// key names in key notation ("<Down>", "<C-x>", ...)
+const NAMES: &[(Code, &str)] = &[
+    (Code::Backspace, "BS"),
+    (Code::Char(' '), "Space"),
+    (Code::Char('<'), "lt"),
+    (Code::Delete, "Del"),
+    (Code::Down, "Down"),
+    (Code::End, "End"),
+    (Code::Enter, "Enter"),
+    (Code::Escape, "Esc"),
+    (Code::Home, "Home"),
+    (Code::Left, "Left"),
+    (Code::PageDown, "PageDown"),
+    (Code::PageUp, "PageUp"),
+    (Code::Right, "Right"),
+    (Code::Tab, "Tab"),
+    (Code::Up, "Up"),
+];
+
+/// # CONTENT
+/// key event
+/// # FIELD
+/// - code: key code
+/// - ctrl: control modifier
+/// - alt: alt modifier
+/// - shift: shift modifier (already applied to Code::Char)
+#[derive(Clone, Copy, PartialEq)]
+pub struct Key {
+    pub code: Code,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+}
+
+impl Key {
+    /// # CONTENT
+    /// init Key without modifiers
+    /// # ARGUMENT
+    /// - code: key code
+    /// # RETURN VALUE
+    /// inited Key
+    pub fn new(code: Code) -> Self {
+        Self {
+            code,
+            ctrl: false,
+            alt: false,
+            shift: false,
+        }
+    }
+
+    /// # CONTENT
+    /// get Key from a terminal key event
+    /// # ARGUMENT
+    /// - e: terminal key event
+    /// # RETURN VALUE
+    /// Some(Key): handled key press
+    /// None: key release or unhandled key
+    pub fn from_event(e: event::KeyEvent) -> Option<Self> {
+        if e.kind == event::KeyEventKind::Release {
+            return None;
+        }
+        let code = match e.code {
+            event::KeyCode::Backspace => Code::Backspace,
+            event::KeyCode::Char(c) => Code::Char(c),
+            event::KeyCode::Delete => Code::Delete,
+            event::KeyCode::Down => Code::Down,
+            event::KeyCode::End => Code::End,
+            event::KeyCode::Enter => Code::Enter,
+            event::KeyCode::Esc => Code::Escape,
+            event::KeyCode::Home => Code::Home,
+            event::KeyCode::Left => Code::Left,
+            event::KeyCode::PageDown => Code::PageDown,
+            event::KeyCode::PageUp => Code::PageUp,
+            event::KeyCode::Right => Code::Right,
+            event::KeyCode::BackTab | event::KeyCode::Tab => Code::Tab,
+            event::KeyCode::Up => Code::Up,
+            _ => return None,
+        };
+        Some(Self {
+            code,
+            ctrl: e.modifiers.contains(event::KeyModifiers::CONTROL),
+            alt: e.modifiers.contains(event::KeyModifiers::ALT),
+            shift: e.modifiers.contains(event::KeyModifiers::SHIFT) && !matches!(code, Code::Char(_)) || e.code == event::KeyCode::BackTab,
+        })
+    }
+
+    /// # CONTENT
+    /// get Keys typing a str ('\n' is Enter, '\t' is Tab)
+    /// # ARGUMENT
+    /// - s: typed str
+    /// # RETURN VALUE
+    /// Keys
+    pub fn keys(s: &str) -> Vec<Self> {
+        s.chars().map(|c| Self::new(match c {
+            '\n' => Code::Enter,
+            '\t' => Code::Tab,
+            _ => Code::Char(c),
+        })).collect()
+    }
+
+    /// # CONTENT
+    /// get typed char of Key
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// Some(char): char without ctrl/alt, '\n' for Enter or '\t' for Tab
+    /// None: other key
+    pub fn char(&self) -> Option<char> {
+        if self.ctrl || self.alt {
+            return None;
+        }
+        match self.code {
+            Code::Char(c) => Some(c),
+            Code::Enter => Some('\n'),
+            Code::Tab => Some('\t'),
+            _ => None,
+        }
+    }
+
+    /// # CONTENT
+    /// parse key notation ("gg", "<C-w>j", "<Down>", "<lt>" for '<')
+    /// # ARGUMENT
+    /// - s: key notation
+    /// # RETURN VALUE
+    /// Ok(keys)
+    /// Err(message): empty sequence or unknown key name
+    pub fn parse(s: &str) -> Result<Vec<Self>, String> {
+        let mut keys = Vec::new();
+        let mut rest = s;
+        while let Some(c) = rest.chars().next() {
+            let Some(end) = rest.find('>').filter(|&end| c == '<' && end > 1) else {
+                keys.extend(Self::keys(&c.to_string()));
+                rest = &rest[c.len_utf8()..];
+                continue;
+            };
+            let mut key = Self::new(Code::Char(' '));
+            let mut name = &rest[1..end];
+            while name.len() > 2 {
+                match &name[..2] {
+                    "C-" | "c-" => key.ctrl = true,
+                    "A-" | "a-" | "M-" | "m-" => key.alt = true,
+                    "S-" | "s-" => key.shift = true,
+                    _ => break,
+                }
+                name = &name[2..];
+            }
+            key.code = match NAMES.iter().find(|(_, n)| n.eq_ignore_ascii_case(name)) {
+                Some(&(code, _)) => code,
+                None if name.chars().count() == 1 => Code::Char(name.chars().next().unwrap_or(' ')),
+                None => return Err(format!("unknown key: {}", &rest[..=end])),
+            };
+            if let Code::Char(c) = key.code && key.shift {
+                key.code = Code::Char(c.to_uppercase().next().unwrap_or(c));
+                key.shift = false;
+            }
+            keys.push(key);
+            rest = &rest[end + 1..];
+        }
+        if keys.is_empty() {
+            return Err("empty key sequence".to_string());
+        }
+        Ok(keys)
+    }
+
+    /// # CONTENT
+    /// get key notation of Key
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// key notation ("j", "<C-w>", "<Down>", ...)
+    pub fn notation(&self) -> String {
+        let modifiers = format!(
+            "{}{}{}",
+            if self.ctrl { "C-" } else { "" },
+            if self.alt { "A-" } else { "" },
+            if self.shift { "S-" } else { "" },
+        );
+        match (NAMES.iter().find(|(c, _)| *c == self.code), self.code) {
+            (None, Code::Char(c)) if modifiers.is_empty() => c.to_string(),
+            (None, Code::Char(c)) => format!("<{}{}>", modifiers, c),
+            (Some((_, name)), _) => format!("<{}{}>", modifiers, name),
+            (None, _) => String::new(),
+        }
+    }
+
+    /// # CONTENT
+    /// get key notation of keys
+    /// # ARGUMENT
+    /// - keys: key sequence
+    /// # RETURN VALUE
+    /// key notation
+    pub fn notations(keys: &[Self]) -> String {
+        keys.iter().map(|k| k.notation()).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(s: &str) -> String {
+        Key::notations(&Key::parse(s).unwrap())
+    }
+
+    #[test]
+    fn parse_notation_round_trip() {
+        for s in ["gg", "<C-w>j", "<Down>", "<lt>", "<Space>", "<S-Tab>", "<A-x>", "<C-A-S-Down>", "<C-W>", "é"] {
+            assert_eq!(round_trip(s), s);
+        }
+        assert_eq!(round_trip("<down><c-HOME>"), "<Down><C-Home>");
+        assert_eq!(round_trip("<S-a>"), "A");
+        assert_eq!(round_trip("<"), "<lt>");
+        assert_eq!(round_trip("<>"), "<lt>>");
+        assert_eq!(round_trip(" "), "<Space>");
+        assert_eq!(Key::parse("<Foo>x").err().as_deref(), Some("unknown key: <Foo>"));
+        assert_eq!(Key::parse("").err().as_deref(), Some("empty key sequence"));
+    }
+
+    #[test]
+    fn parsed_keys() {
+        let keys = Key::parse("<C-w>j<Enter>").unwrap();
+        assert!(keys[0] == Key { code: Code::Char('w'), ctrl: true, alt: false, shift: false });
+        assert!(keys[1] == Key::new(Code::Char('j')));
+        assert!(keys[2] == Key::new(Code::Enter));
+        assert_eq!(keys.iter().map(|k| k.char()).collect::<Vec<_>>(), [None, Some('j'), Some('\n')]);
+        assert!(Key::keys("a\t\n").iter().map(|k| k.code).eq([Code::Char('a'), Code::Tab, Code::Enter]));
+        assert_eq!(Key::new(Code::Escape).notation(), "<Esc>");
+    }
+}
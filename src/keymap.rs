@@ -0,0 +1,459 @@
+//! # CONTENT
+//! key bindings of each mode
+
+use {
+    std:: {
+        mem,
+    },
+    crate:: {
+        key,
+    },
+};
+
+/// # CONTENT
+/// mode names
+pub const MODES: &[&str] = &["append", "confirm", "default", "finder", "line", "replace", "search", "visual"];
+
+/// # CONTENT
+/// (mode, parent mode) of modes using the bindings of the parent for keys they do not bind
+const PARENTS: &[(&str, &str)] = &[("visual", "default")];
+
+/// # CONTENT
+/// default bindings of (mode, key sequence, cmd)
+/// (keys not bound in append, line, replace and search mode are typed as text)
+const DEFAULTS: &[(&str, &str, &str)] = &[
+    ("append",  "<BS>",           "backspace"),
+    ("append",  "<Enter>",        "completion_accept"),
+    ("append",  "<Tab>",          "completion_next"),
+    ("append",  "<S-Tab>",        "completion_prev"),
+    ("append",  "<Del>",          "delete"),
+    ("append",  "<Down>",         "down"),
+    ("append",  "<End>",          "end"),
+    ("append",  "<Esc>",          "finish"),
+    ("append",  "<Home>",         "home"),
+    ("append",  "<Left>",         "left"),
+    ("append",  "<Right>",        "right"),
+    ("append",  "<Up>",           "up"),
+    ("confirm", "a",              "all"),
+    ("confirm", "n",              "no"),
+    ("confirm", "q",              "quit"),
+    ("confirm", "<Esc>",          "quit"),
+    ("confirm", "y",              "yes"),
+    ("default", "a",              "append"),
+    ("default", "b",              "backspace_left"),
+    ("default", "<BS>",           "backspace_left"),
+    ("default", "B",              "backspace_right"),
+    ("default", "<Del>",          "backspace_right"),
+    ("default", "gb",             "buffer_next"),
+    ("default", "gB",             "buffer_prev"),
+    ("default", "j",              "center_down"),
+    ("default", "<Down>",         "center_down"),
+    ("default", "J",              "center_end_file"),
+    ("default", "<C-End>",        "center_end_file"),
+    ("default", "K",              "center_end_line"),
+    ("default", "<End>",          "center_end_line"),
+    ("default", "^",              "center_first_non_blank"),
+    ("default", "h",              "center_left"),
+    ("default", "<Left>",         "center_left"),
+    ("default", "gg",             "center_line"),
+    ("default", "%",              "center_matching_bracket"),
+    ("default", "gj",             "center_page_down"),
+    ("default", "<PageDown>",     "center_page_down"),
+    ("default", "gi",             "center_page_up"),
+    ("default", "<PageUp>",       "center_page_up"),
+    ("default", "}",              "center_paragraph_next"),
+    ("default", "{",              "center_paragraph_prev"),
+    ("default", "k",              "center_right"),
+    ("default", "<Right>",        "center_right"),
+    ("default", "I",              "center_start_file"),
+    ("default", "<C-Home>",       "center_start_file"),
+    ("default", "H",              "center_start_line"),
+    ("default", "<Home>",         "center_start_line"),
+    ("default", "i",              "center_up"),
+    ("default", "<Up>",           "center_up"),
+    ("default", "gw",             "center_word_backward"),
+    ("default", "<C-Left>",       "center_word_backward"),
+    ("default", "gW",             "center_word_backward_big"),
+    ("default", "e",              "center_word_end"),
+    ("default", "E",              "center_word_end_big"),
+    ("default", "w",              "center_word_forward"),
+    ("default", "<C-Right>",      "center_word_forward"),
+    ("default", "W",              "center_word_forward_big"),
+    ("default", "dd",             "delete_row"),
+    ("default", "]d",             "diagnostic_next"),
+    ("default", "[d",             "diagnostic_prev"),
+    ("default", "gf",             "explorer_open"),
+    ("default", "-",              "explorer_parent"),
+    ("default", "<C-t>",          "explorer_tree"),
+    ("default", "<C-p>",          "finder"),
+    ("default", "<Tab>",          "indent"),
+    ("default", ":",              "line"),
+    ("default", "gl",             "location_open"),
+    ("default", "gd",             "lsp_definition"),
+    ("default", "gh",             "lsp_hover"),
+    ("default", "gr",             "lsp_references"),
+    ("default", "n",              "newline"),
+    ("default", "o:expand_tab",   "option_expand_tab"),
+    ("default", "o:hard_tab",     "option_hard_tab"),
+    ("default", "o:row_num",      "option_row_num"),
+    ("default", "o:tw",           "option_tab_width"),
+    ("default", "o:tab_width_2",  "option_tab_width_2"),
+    ("default", "o:tab_width_4",  "option_tab_width_4"),
+    ("default", "o:tab_width_8",  "option_tab_width_8"),
+    ("default", "o:virtual_text", "option_virtual_text"),
+    ("default", "o:whitespace",   "option_whitespace"),
+    ("default", "o:wrap",         "option_wrap"),
+    ("default", "o:move_by_row",  "option_wrap_move"),
+    ("default", "p",              "paste"),
+    ("default", "P",              "paste_before"),
+    ("default", "]q",             "quickfix_next"),
+    ("default", "[q",             "quickfix_prev"),
+    ("default", "q",              "quit"),
+    ("default", "U",              "redo"),
+    ("default", "<C-r>",          "redo"),
+    ("default", "\"",             "register"),
+    ("default", ".",              "repeat"),
+    ("default", "r",              "replace"),
+    ("default", "<Enter>",        "reset"),
+    ("default", "<Esc>",          "reset"),
+    ("default", "c:space_to_tab", "retab_space_to_tab"),
+    ("default", "c:tab_to_space", "retab_tab_to_space"),
+    ("default", "?",              "search_backward"),
+    ("default", "/",              "search_forward"),
+    ("default", ";",              "search_next"),
+    ("default", ",",              "search_prev"),
+    ("default", "sc",             "syntax_child"),
+    ("default", "sn",             "syntax_next_sibling"),
+    ("default", "sp",             "syntax_parent"),
+    ("default", "<C-w>t",         "tab_new"),
+    ("default", "gt",             "tab_next"),
+    ("default", "gT",             "tab_prev"),
+    ("default", "t:one_dark",     "theme_change_to_one_dark"),
+    ("default", "t:one_light",    "theme_change_to_one_light"),
+    ("default", "u",              "undo"),
+    ("default", "<C-v>",          "visual_block"),
+    ("default", "v",              "visual_char"),
+    ("default", "V",              "visual_line"),
+    ("default", "<C-w>c",         "window_close"),
+    ("default", "<C-w>j",         "window_down"),
+    ("default", "<C-w>h",         "window_left"),
+    ("default", "<C-w><lt>",      "window_narrower"),
+    ("default", "<C-w>w",         "window_next"),
+    ("default", "<C-w>k",         "window_right"),
+    ("default", "<C-w>-",         "window_shorter"),
+    ("default", "<C-w>s",         "window_split"),
+    ("default", "<C-w>+",         "window_taller"),
+    ("default", "<C-w>i",         "window_up"),
+    ("default", "<C-w>v",         "window_vsplit"),
+    ("default", "<C-w>>",         "window_wider"),
+    ("default", "yy",             "yank_row"),
+    ("finder",  "<BS>",           "backspace"),
+    ("finder",  "<Esc>",          "cancel"),
+    ("finder",  "<C-n>",          "down"),
+    ("finder",  "<Down>",         "down"),
+    ("finder",  "<Enter>",        "execute"),
+    ("finder",  "<C-p>",          "up"),
+    ("finder",  "<Up>",           "up"),
+    ("line",    "<BS>",           "backspace"),
+    ("line",    "<Esc>",          "cancel"),
+    ("line",    "<Tab>",          "complete"),
+    ("line",    "<Del>",          "delete"),
+    ("line",    "<End>",          "end"),
+    ("line",    "<Enter>",        "execute"),
+    ("line",    "<Home>",         "home"),
+    ("line",    "<Left>",         "left"),
+    ("line",    "<Right>",        "right"),
+    ("replace", "<BS>",           "cancel"),
+    ("replace", "<Esc>",          "cancel"),
+    ("replace", "<Enter>",        "delete"),
+    ("search",  "<BS>",           "backspace"),
+    ("search",  "<Esc>",          "cancel"),
+    ("search",  "<Del>",          "delete"),
+    ("search",  "<End>",          "end"),
+    ("search",  "<Enter>",        "execute"),
+    ("search",  "<Home>",         "home"),
+    ("search",  "<Left>",         "left"),
+    ("search",  "<Right>",        "right"),
+    ("visual",  "<Esc>",          "cancel"),
+    ("visual",  "c",              "change"),
+    ("visual",  "d",              "delete"),
+    ("visual",  "x",              "delete"),
+    ("visual",  ">",              "indent"),
+    ("visual",  "u",              "lowercase"),
+    ("visual",  "<lt>",           "outdent"),
+    ("visual",  "U",              "uppercase"),
+    ("visual",  "<C-v>",          "visual_block"),
+    ("visual",  "v",              "visual_char"),
+    ("visual",  "V",              "visual_line"),
+    ("visual",  "y",              "yank"),
+];
+
+/// # CONTENT
+/// result of resolving pending keys
+/// - Cmd: keys are bound to the cmd
+/// - Pending: keys are a prefix of longer bindings (wait for more keys or the timeout)
+/// - Unbound: keys are not bound (returned to be handled by the mode)
+pub enum Resolved {
+    Cmd(&'static str),
+    Pending,
+    Unbound(Vec<key::Key>),
+}
+
+/// # CONTENT
+/// key bindings
+/// # FIELD
+/// - bindings: (mode, key sequence, cmd)
+pub struct Keymap {
+    bindings: Vec<(&'static str, Vec<key::Key>, &'static str)>,
+}
+
+/// # CONTENT
+/// get the static name of a mode
+/// # ARGUMENT
+/// - mode: mode name
+/// # RETURN VALUE
+/// Ok(name)
+/// Err(message): unknown mode
+fn static_mode(mode: &str) -> Result<&'static str, String> {
+    MODES.iter().find(|m| **m == mode).copied().ok_or(format!("unknown mode: {}", mode))
+}
+
+/// # CONTENT
+/// get the parent mode of a mode
+/// # ARGUMENT
+/// - mode: mode name
+/// # RETURN VALUE
+/// Some(parent): mode inherits the bindings of parent
+/// None: no parent
+fn parent(mode: &str) -> Option<&'static str> {
+    PARENTS.iter().find(|(m, _)| *m == mode).map(|&(_, p)| p)
+}
+
+impl Keymap {
+    /// # CONTENT
+    /// init Keymap with the default bindings
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// inited Keymap
+    pub fn new() -> Self {
+        Self {
+            bindings: DEFAULTS.iter().map(|&(mode, seq, cmd)| {
+                (mode, key::Key::parse(seq).unwrap_or_default(), cmd)
+            }).collect(),
+        }
+    }
+
+    /// # CONTENT
+    /// get cmd names (including the cmds of the parent mode)
+    /// # ARGUMENT
+    /// - mode: mode name (None for every mode)
+    /// # RETURN VALUE
+    /// sorted cmd names
+    pub fn cmds(mode: Option<&str>) -> Vec<&'static str> {
+        let mut cmds: Vec<&'static str> = DEFAULTS.iter()
+            .filter(|(m, _, _)| mode.is_none_or(|mode| *m == mode || parent(mode) == Some(*m)))
+            .map(|&(_, _, cmd)| cmd)
+            .collect();
+        cmds.sort();
+        cmds.dedup();
+        cmds
+    }
+
+    /// # CONTENT
+    /// get bindings active in a mode
+    /// # ARGUMENT
+    /// - mode: mode name
+    /// # RETURN VALUE
+    /// bindings of the mode and bindings of the parent mode not starting with a key sequence of the mode
+    fn bindings(&self, mode: &str) -> Vec<&(&'static str, Vec<key::Key>, &'static str)> {
+        let own = self.bindings.iter().filter(|(m, _, _)| *m == mode);
+        let inherited = self.bindings.iter().filter(|(m, keys, _)| {
+            parent(mode) == Some(*m) && !self.bindings.iter().any(|(own_m, own_keys, _)| *own_m == mode && keys.starts_with(own_keys))
+        });
+        own.chain(inherited).collect()
+    }
+
+    /// # CONTENT
+    /// bind a key sequence (replaces the binding of the same sequence)
+    /// # ARGUMENT
+    /// - mode: mode name
+    /// - seq: key notation
+    /// - cmd: cmd name
+    /// # RETURN VALUE
+    /// Ok(())
+    /// Err(message): unknown mode, key or cmd
+    pub fn map(&mut self, mode: &str, seq: &str, cmd: &str) -> Result<(), String> {
+        let mode = static_mode(mode)?;
+        let keys = key::Key::parse(seq)?;
+        let cmd = Self::cmds(Some(mode)).into_iter().find(|c| *c == cmd).ok_or(format!("unknown {} cmd: {}", mode, cmd))?;
+        self.bindings.retain(|(m, k, _)| *m != mode || *k != keys);
+        self.bindings.push((mode, keys, cmd));
+        Ok(())
+    }
+
+    /// # CONTENT
+    /// unbind a key sequence
+    /// # ARGUMENT
+    /// - mode: mode name
+    /// - seq: key notation
+    /// # RETURN VALUE
+    /// Ok(())
+    /// Err(message): unknown mode or key, or not bound
+    pub fn unmap(&mut self, mode: &str, seq: &str) -> Result<(), String> {
+        let mode = static_mode(mode)?;
+        let keys = key::Key::parse(seq)?;
+        let len = self.bindings.len();
+        self.bindings.retain(|(m, k, _)| *m != mode || *k != keys);
+        if self.bindings.len() == len {
+            return Err(format!("not bound in {}: {}", mode, seq));
+        }
+        Ok(())
+    }
+
+    /// # CONTENT
+    /// resolve pending keys followed by a key
+    /// (an exact binding sharing its prefix with longer bindings waits for Keymap::flush)
+    /// # ARGUMENT
+    /// - mode: mode name
+    /// - pending: keys entered before (updated)
+    /// - k: entered key
+    /// # RETURN VALUE
+    /// Resolved
+    pub fn resolve(&self, mode: &str, pending: &mut Vec<key::Key>, k: key::Key) -> Resolved {
+        pending.push(k);
+        let longer = self.bindings(mode).iter().any(|(_, keys, _)| keys.len() > pending.len() && keys.starts_with(pending));
+        if longer {
+            return Resolved::Pending;
+        }
+        self.flush(mode, pending)
+    }
+
+    /// # CONTENT
+    /// resolve pending keys without waiting for more keys (on timeout)
+    /// # ARGUMENT
+    /// - mode: mode name
+    /// - pending: keys entered before (cleared)
+    /// # RETURN VALUE
+    /// Resolved::Cmd or Resolved::Unbound
+    pub fn flush(&self, mode: &str, pending: &mut Vec<key::Key>) -> Resolved {
+        let keys = mem::take(pending);
+        match self.bindings(mode).into_iter().find(|(_, k, _)| *k == keys) {
+            Some(&(_, _, cmd)) => Resolved::Cmd(cmd),
+            None => Resolved::Unbound(keys),
+        }
+    }
+
+    /// # CONTENT
+    /// get bindings sharing a prefix with a shorter binding
+    /// (the shorter one runs only after the pending timeout)
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// descriptions of ambiguous bindings
+    pub fn ambiguous(&self) -> Vec<String> {
+        let mut found = Vec::new();
+        for (mode, short, short_cmd) in &self.bindings {
+            for (_, long, long_cmd) in self.bindings.iter().filter(|(m, k, _)| m == mode && k.len() > short.len() && k.starts_with(short)) {
+                found.push(format!(
+                    "{}: {} ({}) is a prefix of {} ({})",
+                    mode,
+                    key::Key::notations(short),
+                    short_cmd,
+                    key::Key::notations(long),
+                    long_cmd,
+                ));
+            }
+        }
+        found
+    }
+
+    /// # CONTENT
+    /// get printable bindings
+    /// # ARGUMENT
+    /// - mode: mode name (None for every mode, or active bindings of the mode)
+    /// - prefix: listed key sequences start with it
+    /// # RETURN VALUE
+    /// rows of "mode  keys  cmd" sorted by mode and cmd
+    pub fn list(&self, mode: Option<&str>, prefix: &[key::Key]) -> Vec<String> {
+        let bindings = match mode {
+            Some(mode) => self.bindings(mode),
+            None => self.bindings.iter().collect(),
+        };
+        let mut bindings: Vec<_> = bindings.into_iter()
+            .filter(|(_, keys, _)| keys.starts_with(prefix))
+            .map(|(m, keys, cmd)| (*m, *cmd, key::Key::notations(keys)))
+            .collect();
+        bindings.sort();
+        bindings.into_iter().map(|(m, cmd, keys)| format!("{:<8} {:<16} {}", m, keys, cmd)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(keymap: &Keymap, mode: &str, keys: &str) -> Option<&'static str> {
+        let mut pending = Vec::new();
+        let mut resolved = None;
+        for k in key::Key::parse(keys).unwrap() {
+            resolved = match keymap.resolve(mode, &mut pending, k) {
+                Resolved::Cmd(cmd) => Some(cmd),
+                Resolved::Pending => Some("(pending)"),
+                Resolved::Unbound(_) => None,
+            };
+        }
+        resolved
+    }
+
+    #[test]
+    fn resolve_sequences() {
+        let keymap = Keymap::new();
+        assert_eq!(resolve(&keymap, "default", "j"), Some("center_down"));
+        assert_eq!(resolve(&keymap, "default", "<Down>"), Some("center_down"));
+        assert_eq!(resolve(&keymap, "default", "g"), Some("(pending)"));
+        assert_eq!(resolve(&keymap, "default", "gg"), Some("center_line"));
+        assert_eq!(resolve(&keymap, "default", "<C-w>v"), Some("window_vsplit"));
+        assert_eq!(resolve(&keymap, "default", "gz"), None);
+        assert_eq!(resolve(&keymap, "append", "j"), None);
+        assert_eq!(resolve(&keymap, "line", "<Left>"), Some("left"));
+        assert_eq!(resolve(&keymap, "search", "<Home>"), Some("home"));
+
+        let mut pending = key::Key::parse("g").unwrap();
+        assert!(matches!(keymap.flush("default", &mut pending), Resolved::Unbound(keys) if keys.len() == 1));
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn parent_fallback() {
+        let keymap = Keymap::new();
+        assert_eq!(resolve(&keymap, "visual", "j"), Some("center_down"));
+        assert_eq!(resolve(&keymap, "visual", "gg"), Some("center_line"));
+        // "d" of visual mode hides "dd" of default mode
+        assert_eq!(resolve(&keymap, "visual", "d"), Some("delete"));
+        assert_eq!(resolve(&keymap, "confirm", "j"), None);
+        assert!(Keymap::cmds(Some("visual")).contains(&"center_down"));
+        assert!(!Keymap::cmds(Some("line")).contains(&"center_down"));
+    }
+
+    #[test]
+    fn map_and_ambiguous() {
+        let mut keymap = Keymap::new();
+        assert!(keymap.ambiguous().is_empty());
+        keymap.map("default", "g", "undo").unwrap();
+        assert_eq!(resolve(&keymap, "default", "g"), Some("(pending)"));
+        let mut pending = key::Key::parse("g").unwrap();
+        assert!(matches!(keymap.flush("default", &mut pending), Resolved::Cmd("undo")));
+        assert!(keymap.ambiguous().contains(&"default: g (undo) is a prefix of gg (center_line)".to_string()));
+
+        keymap.map("visual", "j", "center_up").unwrap();
+        assert_eq!(resolve(&keymap, "visual", "j"), Some("center_up"));
+        assert_eq!(resolve(&keymap, "default", "j"), Some("center_down"));
+        keymap.unmap("default", "j").unwrap();
+        assert_eq!(resolve(&keymap, "default", "j"), None);
+
+        assert_eq!(keymap.map("nomode", "j", "undo").err().as_deref(), Some("unknown mode: nomode"));
+        assert_eq!(keymap.map("line", "j", "undo").err().as_deref(), Some("unknown line cmd: undo"));
+        assert_eq!(keymap.unmap("default", "j").err().as_deref(), Some("not bound in default: j"));
+    }
+}
@@ -0,0 +1,362 @@
+//! # CONTENT
+//! cmd line (":...") tokenizer, parser and completion
+
+use {
+    std:: {
+        fs,
+        path,
+    },
+    crate:: {
+        display,
+        key,
+        keymap,
+    },
+};
+
+/// # CONTENT
+/// argument type of a cmd line cmd
+#[derive(Clone, Copy, PartialEq)]
+pub enum Arg {
+    Buffer,
+    Cmd,
+    Key,
+    Mode,
+    Number,
+    Path,
+    Raw,
+    Theme,
+}
+
+/// # CONTENT
+/// cmd line cmd
+/// # FIELD
+/// - name: cmd name
+/// - alias: short cmd name
+/// - args: argument types and whether each is required
+/// - range: accepts a row range before the name
+pub struct Command {
+    pub name: &'static str,
+    pub alias: &'static str,
+    pub args: &'static [(Arg, bool)],
+    pub range: bool,
+}
+
+/// # CONTENT
+/// cmd registry (a bare row number goes to the row)
+pub const COMMANDS: &[Command] = &[
+    Command { name: "buffer",       alias: "b",  args: &[(Arg::Buffer, true)],                                range: false },
+    Command { name: "buffer_close", alias: "bc", args: &[],                                                   range: false },
+    Command { name: "buffer_next",  alias: "bn", args: &[],                                                   range: false },
+    Command { name: "buffer_prev",  alias: "bp", args: &[],                                                   range: false },
+    Command { name: "buffers",      alias: "ls", args: &[],                                                   range: false },
+    Command { name: "close",        alias: "cl", args: &[],                                                   range: false },
+    Command { name: "create",       alias: "cr", args: &[(Arg::Path, true)],                                  range: false },
+    Command { name: "delete",       alias: "dl", args: &[],                                                   range: false },
+    Command { name: "diagnostics",  alias: "dg", args: &[],                                                   range: false },
+    Command { name: "edit",         alias: "e",  args: &[(Arg::Path, true)],                                  range: false },
+    Command { name: "explore",      alias: "ex", args: &[(Arg::Path, false)],                                 range: false },
+    Command { name: "find",         alias: "fi", args: &[(Arg::Path, false)],                                 range: false },
+    Command { name: "goto",         alias: "g",  args: &[(Arg::Number, true)],                                range: false },
+    Command { name: "grep",         alias: "gr", args: &[(Arg::Raw, true)],                                   range: false },
+    Command { name: "lint",         alias: "lt", args: &[],                                                   range: false },
+    Command { name: "lint_program", alias: "lp", args: &[(Arg::Raw, true)],                                   range: false },
+    Command { name: "lsp_rename",   alias: "lr", args: &[(Arg::Raw, true)],                                   range: false },
+    Command { name: "lsp_server",   alias: "lv", args: &[(Arg::Raw, true)],                                   range: false },
+    Command { name: "make",         alias: "mk", args: &[],                                                   range: false },
+    Command { name: "make_program", alias: "mp", args: &[(Arg::Raw, true)],                                   range: false },
+    Command { name: "map",          alias: "m",  args: &[(Arg::Mode, false), (Arg::Key, false), (Arg::Cmd, false)], range: false },
+    Command { name: "nohlsearch",   alias: "nh", args: &[],                                                   range: false },
+    Command { name: "quickfix",     alias: "qf", args: &[],                                                   range: false },
+    Command { name: "quit",         alias: "q",  args: &[],                                                   range: false },
+    Command { name: "registers",    alias: "r",  args: &[],                                                   range: false },
+    Command { name: "rename",       alias: "rn", args: &[(Arg::Path, true)],                                  range: false },
+    Command { name: "resize",       alias: "rs", args: &[(Arg::Number, true)],                                range: false },
+    Command { name: "split",        alias: "sp", args: &[(Arg::Path, false)],                                 range: false },
+    Command { name: "substitute",   alias: "s",  args: &[(Arg::Raw, true)],                                   range: true  },
+    Command { name: "tab",          alias: "tb", args: &[(Arg::Number, true)],                                range: false },
+    Command { name: "tab_close",    alias: "tc", args: &[],                                                   range: false },
+    Command { name: "tab_new",      alias: "tn", args: &[(Arg::Path, false)],                                 range: false },
+    Command { name: "tab_next",     alias: "tk", args: &[],                                                   range: false },
+    Command { name: "tab_prev",     alias: "th", args: &[],                                                   range: false },
+    Command { name: "tab_width",    alias: "tw", args: &[(Arg::Number, true)],                                range: false },
+    Command { name: "theme",        alias: "t",  args: &[(Arg::Theme, true)],                                 range: false },
+    Command { name: "timeout",      alias: "to", args: &[(Arg::Number, true)],                                range: false },
+    Command { name: "tree",         alias: "tr", args: &[(Arg::Path, false)],                                 range: false },
+    Command { name: "unmap",        alias: "um", args: &[(Arg::Mode, true), (Arg::Key, true)],                range: false },
+    Command { name: "vresize",      alias: "vr", args: &[(Arg::Number, true)],                                range: false },
+    Command { name: "vsplit",       alias: "vs", args: &[(Arg::Path, false)],                                 range: false },
+    Command { name: "write",        alias: "w",  args: &[(Arg::Path, false)],                                 range: false },
+];
+
+/// # CONTENT
+/// parsed cmd line
+/// # FIELD
+/// - command: cmd in the registry
+/// - range: row range text before the name (e.g. "%", "3,$")
+/// - args: arguments (Raw is the whole rest of the line)
+pub struct Line {
+    pub command: &'static Command,
+    pub range: String,
+    pub args: Vec<String>,
+}
+
+/// # CONTENT
+/// split text into tokens ("..." and '...' quote, "\" escapes)
+/// # ARGUMENT
+/// - s: text
+/// # RETURN VALUE
+/// Ok(tokens)
+/// Err(message): unterminated quote
+pub fn tokenize(s: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut token: Option<String> = None;
+    let mut quote: Option<char> = None;
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                token.get_or_insert_with(String::new);
+            }
+            (_, '\\') => token.get_or_insert_with(String::new).push(chars.next().unwrap_or('\\')),
+            (None, _) if c.is_whitespace() => tokens.extend(token.take()),
+            _ => token.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if quote.is_some() {
+        return Err("unterminated quote".to_string());
+    }
+    tokens.extend(token);
+    Ok(tokens)
+}
+
+/// # CONTENT
+/// split a cmd line into (range, name, rest)
+/// # ARGUMENT
+/// - s: cmd line without ':'
+/// # RETURN VALUE
+/// (range, name, rest)
+fn split(s: &str) -> (&str, &str, &str) {
+    let s = s.trim_start();
+    let range_len = s.len() - s.trim_start_matches(|c: char| c.is_ascii_digit() || ".,$%".contains(c)).len();
+    let (range, s) = s.split_at(range_len);
+    let name_len = s.len() - s.trim_start_matches(|c: char| c.is_ascii_alphabetic() || c == '_').len();
+    let (name, rest) = s.split_at(name_len);
+    (range, name, rest)
+}
+
+/// # CONTENT
+/// find a cmd by name or alias
+/// # ARGUMENT
+/// - name: cmd name
+/// # RETURN VALUE
+/// Some(Command): found cmd
+/// None: unknown cmd
+fn command(name: &str) -> Option<&'static Command> {
+    COMMANDS.iter().find(|c| c.name == name || c.alias == name)
+}
+
+/// # CONTENT
+/// get argument type name for messages
+/// # ARGUMENT
+/// - arg: argument type
+/// # RETURN VALUE
+/// type name
+fn arg_name(arg: Arg) -> &'static str {
+    match arg {
+        Arg::Buffer => "buffer",
+        Arg::Cmd => "cmd",
+        Arg::Key => "key",
+        Arg::Mode => "mode",
+        Arg::Number => "number",
+        Arg::Path => "path",
+        Arg::Raw => "text",
+        Arg::Theme => "theme",
+    }
+}
+
+/// # CONTENT
+/// parse a cmd line
+/// # ARGUMENT
+/// - s: cmd line without ':'
+/// # RETURN VALUE
+/// Ok(Line)
+/// Err(message): parse error
+pub fn parse(s: &str) -> Result<Line, String> {
+    let (range, name, rest) = split(s);
+    if name.is_empty() && rest.trim().is_empty() && !range.is_empty() {
+        // bare row number
+        return Ok(Line { command: command("goto").unwrap(), range: String::new(), args: vec![range.to_string()] });
+    }
+    let command = command(name).ok_or(format!("unknown cmd: {}", name))?;
+    if !range.is_empty() && !command.range {
+        return Err(format!("{}: no range allowed", command.name));
+    }
+    let args = match command.args.first() {
+        Some(&(Arg::Raw, _)) if rest.trim().is_empty() => Vec::new(),
+        Some(&(Arg::Raw, _)) => vec![rest.to_string()],
+        _ => tokenize(rest)?,
+    };
+    if args.len() > command.args.len() {
+        return Err(format!("{}: trailing arguments", command.name));
+    }
+    for (i, &(arg, required)) in command.args.iter().enumerate() {
+        match args.get(i) {
+            None if required => return Err(format!("{}: missing {}", command.name, arg_name(arg))),
+            Some(a) if arg == Arg::Key && let Err(e) = key::Key::parse(a) => {
+                return Err(format!("{}: {}", command.name, e));
+            }
+            Some(a) if arg == Arg::Mode && !keymap::MODES.contains(&a.as_str()) => {
+                return Err(format!("{}: unknown mode: {}", command.name, a));
+            }
+            Some(a) if arg == Arg::Number && a.parse::<usize>().is_err() => {
+                return Err(format!("{}: invalid number: {}", command.name, a));
+            }
+            Some(a) if arg == Arg::Theme && !display::Theme::NAMES.contains(&a.as_str()) => {
+                return Err(format!("{}: unknown theme: {}", command.name, a));
+            }
+            _ => {}
+        }
+    }
+    Ok(Line { command, range: range.to_string(), args })
+}
+
+/// # CONTENT
+/// get path completions of a token
+/// # ARGUMENT
+/// - token: path prefix
+/// # RETURN VALUE
+/// completed paths (directories end with '/')
+fn complete_path(token: &str) -> Vec<String> {
+    let (dir, prefix) = match token.rfind('/') {
+        Some(i) => (&token[..=i], &token[i + 1..]),
+        None => ("", token),
+    };
+    let Ok(entries) = fs::read_dir(if dir.is_empty() { path::Path::new(".") } else { path::Path::new(dir) }) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<String> = entries.flatten().filter_map(|e| {
+        let name = e.file_name().into_string().ok()?;
+        if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+            return None;
+        }
+        let slash = if e.path().is_dir() { "/" } else { "" };
+        Some(format!("{}{}{}", dir, name, slash))
+    }).collect();
+    paths.sort();
+    paths
+}
+
+/// # CONTENT
+/// get completions of the last token of a cmd line
+/// # ARGUMENT
+/// - s: cmd line without ':'
+/// - buffers: paths of the open buffers
+/// # RETURN VALUE
+/// completed cmd lines
+pub fn complete(s: &str, buffers: &[String]) -> Vec<String> {
+    let (range, name, rest) = split(s);
+    if rest.is_empty() {
+        // cmd name
+        let head = &s[..s.len() - name.len()];
+        let mut names: Vec<String> = COMMANDS.iter()
+            .filter(|c| c.name.starts_with(name) && (range.is_empty() || c.range))
+            .map(|c| format!("{}{}", head, c.name))
+            .collect();
+        names.sort();
+        return names;
+    }
+    let Some(command) = command(name) else {
+        return Vec::new();
+    };
+    let Ok(tokens) = tokenize(rest) else {
+        return Vec::new();
+    };
+    let new_token = rest.ends_with(char::is_whitespace) || tokens.is_empty();
+    let index = if new_token { tokens.len() } else { tokens.len() - 1 };
+    let token = if new_token { "" } else { tokens[index].as_str() };
+    let head = match s.rfind(char::is_whitespace) {
+        Some(i) if !new_token => &s[..=i],
+        _ => s,
+    };
+    let candidates: Vec<String> = match command.args.get(index) {
+        Some(&(Arg::Buffer, _)) => buffers.iter().filter(|b| b.contains(token)).cloned().collect(),
+        Some(&(Arg::Cmd, _)) => keymap::Keymap::cmds(tokens.first().map(|m| m.as_str())).into_iter().filter(|c| c.starts_with(token)).map(|c| c.to_string()).collect(),
+        Some(&(Arg::Mode, _)) => keymap::MODES.iter().filter(|m| m.starts_with(token)).map(|m| m.to_string()).collect(),
+        Some(&(Arg::Path, _)) => complete_path(token),
+        Some(&(Arg::Theme, _)) => display::Theme::NAMES.iter().filter(|t| t.starts_with(token)).map(|t| t.to_string()).collect(),
+        _ => Vec::new(),
+    };
+    candidates.into_iter().map(|c| format!("{}{}", head, c.replace(' ', "\\ "))).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(s: &str) -> (&'static str, String, Vec<String>) {
+        let line = parse(s).unwrap();
+        (line.command.name, line.range, line.args)
+    }
+
+    fn error(s: &str) -> String {
+        parse(s).err().unwrap()
+    }
+
+    #[test]
+    fn tokenize_quotes_and_escapes() {
+        assert_eq!(tokenize("  a  b ").unwrap(), ["a", "b"]);
+        assert_eq!(tokenize(r#"a "b c" 'd "e"'"#).unwrap(), ["a", "b c", r#"d "e""#]);
+        assert_eq!(tokenize(r"a\ b c\\").unwrap(), ["a b", r"c\"]);
+        assert_eq!(tokenize(r#"x"y z"w"#).unwrap(), ["xy zw"]);
+        assert_eq!(tokenize(r#""""#).unwrap(), [""]);
+        assert!(tokenize("").unwrap().is_empty());
+        assert!(tokenize("'a").is_err());
+    }
+
+    #[test]
+    fn split_range_name_rest() {
+        assert_eq!(split(" %s/a/b/"), ("%", "s", "/a/b/"));
+        assert_eq!(split("3,$substitute x"), ("3,$", "substitute", " x"));
+        assert_eq!(split("12"), ("12", "", ""));
+        assert_eq!(split("tab_new a"), ("", "tab_new", " a"));
+    }
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!(parsed("12"), ("goto", String::new(), vec!["12".to_string()]));
+        assert_eq!(parsed("e a.rs"), ("edit", String::new(), vec!["a.rs".to_string()]));
+        assert_eq!(parsed("write"), ("write", String::new(), vec![]));
+        assert_eq!(parsed("%s/a b/c/"), ("substitute", "%".to_string(), vec!["/a b/c/".to_string()]));
+        assert_eq!(parsed("grep  a \"b\" "), ("grep", String::new(), vec!["  a \"b\" ".to_string()]));
+        assert_eq!(parsed("map default <C-x> quit"), ("map", String::new(), vec!["default".to_string(), "<C-x>".to_string(), "quit".to_string()]));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(error("nope"), "unknown cmd: nope");
+        assert_eq!(error("3write"), "write: no range allowed");
+        assert_eq!(error("write a b"), "write: trailing arguments");
+        assert_eq!(error("edit"), "edit: missing path");
+        assert_eq!(error("grep"), "grep: missing text");
+        assert_eq!(error("grep   "), "grep: missing text");
+        assert_eq!(error("goto x"), "goto: invalid number: x");
+        assert_eq!(error("unmap nomode x"), "unmap: unknown mode: nomode");
+        assert_eq!(error("theme x"), "theme: unknown theme: x");
+        assert!(error("map default <Nope> quit").starts_with("map: "));
+        assert_eq!(error("edit 'a"), "unterminated quote");
+    }
+
+    #[test]
+    fn complete_names_and_args() {
+        assert_eq!(complete("tab_", &[]), ["tab_close", "tab_new", "tab_next", "tab_prev", "tab_width"]);
+        assert_eq!(complete("%su", &[]), ["%substitute"]);
+        assert_eq!(complete("unmap vi", &[]), ["unmap visual"]);
+        assert_eq!(complete("theme ", &[]), ["theme one_dark", "theme one_light"]);
+        let buffers = ["src/a.rs".to_string(), "src/b.rs".to_string(), "my dir/a.md".to_string()];
+        assert_eq!(complete("buffer a.", &buffers), ["buffer src/a.rs", "buffer my\\ dir/a.md"]);
+        assert_eq!(complete("b ", &buffers).len(), 3);
+        assert!(complete("nope x", &buffers).is_empty());
+    }
+}
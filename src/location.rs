@@ -0,0 +1,30 @@
+//! # CONTENT
+//! file locations listed in a buffer (grep results, quickfix list)
+
+/// # CONTENT
+/// file location
+/// # FIELD
+/// - path: file path
+/// - y: row index
+/// - x: char index
+/// - text: snippet or message shown with the location
+#[derive(Clone)]
+pub struct Location {
+    pub path: String,
+    pub y: usize,
+    pub x: usize,
+    pub text: String,
+}
+
+/// # CONTENT
+/// get the listing rows of locations
+/// # ARGUMENT
+/// - locations: listed locations
+/// # RETURN VALUE
+/// rows of "path:line:col: text" (1 origin line and col)
+pub fn rows(locations: &[Location]) -> Vec<String> {
+    if locations.is_empty() {
+        return vec![String::new()];
+    }
+    locations.iter().map(|l| format!("{}:{}:{}: {}", l.path, l.y + 1, l.x + 1, l.text)).collect()
+}
@@ -0,0 +1,1149 @@
+//! # CONTENT
+//! language server protocol client (a configured server per language run over stdio)
+
+use {
+    std:: {
+        collections::HashMap,
+        env,
+        fs,
+        io:: {
+            self,
+            BufRead,
+            Read,
+            Write,
+        },
+        path,
+        process,
+        sync::mpsc,
+        thread,
+        time,
+    },
+    crate:: {
+        completion,
+        diagnostic,
+        file,
+        files,
+        location,
+        shell,
+    },
+    serde_json:: {
+        json,
+        Value,
+    },
+};
+
+/// # CONTENT
+/// wait for server messages between key events while a server runs (milliseconds)
+pub const POLL_MILLIS: u64 = 50;
+
+/// # CONTENT
+/// wait for the shutdown response, then for the exit, of a dropped server (milliseconds each)
+const SHUTDOWN_MILLIS: u64 = 300;
+
+/// # CONTENT
+/// row count limit of a hover message
+const MAX_HOVER_ROWS: usize = 20;
+
+/// # CONTENT
+/// names of completion item kinds (index is the protocol number - 1)
+const COMPLETION_KINDS: &[&str] = &[
+    "text", "method", "function", "constructor", "field", "variable", "class", "interface", "module", "property",
+    "unit", "value", "enum", "keyword", "snippet", "color", "file", "reference", "folder", "enum member",
+    "constant", "struct", "event", "operator", "type parameter",
+];
+
+/// # CONTENT
+/// (file extension, language id) of languages a server can be configured for
+const LANGUAGES: &[(&str, &str)] = &[
+    ("c", "c"),
+    ("cpp", "cpp"),
+    ("go", "go"),
+    ("h", "c"),
+    ("hpp", "cpp"),
+    ("js", "javascript"),
+    ("json", "json"),
+    ("md", "markdown"),
+    ("py", "python"),
+    ("rs", "rust"),
+    ("toml", "toml"),
+    ("ts", "typescript"),
+];
+
+/// # CONTENT
+/// text edit (of a rename, or a change sent to a server)
+/// # FIELD
+/// - start: first pos (x, y) of the replaced range
+/// - end: pos (x, y) after the replaced range
+/// - text: inserted text
+pub struct Edit {
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+    pub text: String,
+}
+
+/// # CONTENT
+/// server cmd requested by Cmd and run by Lsp::run at the center of the shown buffer
+/// - Completion: add completion items to the popup
+/// - Definition: jump to the definition of the symbol
+/// - Hover: show the hover text of the symbol
+/// - References: list the references of the symbol
+/// - Rename: rename the symbol in every file
+pub enum Request {
+    Completion,
+    Definition,
+    Hover,
+    References,
+    Rename(String),
+}
+
+/// # CONTENT
+/// server result run by the main loop
+/// - Completion: add items to the completion popup of a row
+/// - Diagnostics: replace the diagnostics of an open path
+/// - Edit: apply edits to paths (opening them if needed)
+/// - Jump: open a path and move center to a pos (x, y)
+/// - Locations: show a location list buffer of a name
+/// - Message: show a message
+pub enum Response {
+    Completion(usize, Vec<completion::Item>),
+    Diagnostics(String, Vec<diagnostic::Diagnostic>),
+    Edit(Vec<(String, Vec<Edit>)>),
+    Jump(String, (usize, usize)),
+    Locations(String, Vec<location::Location>),
+    Message(String),
+}
+
+/// # CONTENT
+/// sent request waiting for its response
+enum Pending {
+    Completion(usize),
+    Definition,
+    Hover,
+    Initialize,
+    References,
+    Rename,
+}
+
+/// # CONTENT
+/// document opened in a server
+/// # FIELD
+/// - uri: document uri
+/// - version: version of the last sent content
+/// - content: last sent content (diffed against File::content for incremental sync)
+struct Document {
+    uri: String,
+    version: i64,
+    content: Vec<String>,
+}
+
+/// # CONTENT
+/// running server of a language
+/// # FIELD
+/// - language: language id
+/// - process: server process
+/// - stdin: pipe of sent messages
+/// - receiver: messages read from the server stdout by a reader thread
+/// - next_id: id of the next request
+/// - pending: requests waiting for their response
+/// - ready: the initialize response arrived
+/// - utf16: positions count UTF-16 code units (otherwise chars)
+/// - sync: text document sync kind of the server (0: none, 1: full, 2: incremental)
+/// - documents: opened documents by path
+struct Client {
+    language: String,
+    process: process::Child,
+    stdin: process::ChildStdin,
+    receiver: mpsc::Receiver<Value>,
+    next_id: u64,
+    pending: HashMap<u64, Pending>,
+    ready: bool,
+    utf16: bool,
+    sync: u64,
+    documents: HashMap<String, Document>,
+}
+
+/// # CONTENT
+/// language servers of open buffers
+/// # FIELD
+/// - clients: running servers
+/// - failed: languages whose server failed to start or exited (not started again until Lsp::restart)
+pub struct Lsp {
+    clients: Vec<Client>,
+    failed: Vec<String>,
+}
+
+/// # CONTENT
+/// get the language id of a path
+/// # ARGUMENT
+/// - path: file path
+/// # RETURN VALUE
+/// Some(language): language id of the extension
+/// None: unknown extension
+pub fn language(path: &str) -> Option<&'static str> {
+    let extension = path::Path::new(path).extension()?.to_str()?;
+    LANGUAGES.iter().find(|(e, _)| *e == extension).map(|&(_, language)| language)
+}
+
+/// # CONTENT
+/// get the file uri of a path
+/// # ARGUMENT
+/// - path: file path (relative to the current dir or absolute)
+/// # RETURN VALUE
+/// "file://" uri of the absolute path (percent-encoded)
+fn uri(path: &str) -> String {
+    let absolute = fs::canonicalize(path)
+        .or_else(|_| env::current_dir().map(|dir| dir.join(path)))
+        .unwrap_or_else(|_| path::PathBuf::from(path));
+    encode(&absolute.to_string_lossy(), cfg!(windows))
+}
+
+/// # CONTENT
+/// get the file uri of an absolute path
+/// # ARGUMENT
+/// - absolute: absolute path
+/// - windows: the path is a Windows path ("\" separators, a drive and maybe a "\\?\" prefix)
+/// # RETURN VALUE
+/// "file://" uri (percent-encoded, "file:///C:/..." for a Windows path)
+fn encode(absolute: &str, windows: bool) -> String {
+    let mut path = absolute.to_string();
+    if windows {
+        path = path.replace('\\', "/");
+        if let Some(rest) = path.strip_prefix("//?/") {
+            path = rest.to_string();
+        }
+        if !path.starts_with('/') {
+            path.insert(0, '/');
+        }
+    }
+    let mut uri = String::from("file://");
+    for b in path.bytes() {
+        if b.is_ascii_alphanumeric() || b"/-_.~:".contains(&b) {
+            uri.push(b as char);
+        } else {
+            uri.push_str(&format!("%{:02X}", b));
+        }
+    }
+    uri
+}
+
+/// # CONTENT
+/// get the path of a file uri
+/// # ARGUMENT
+/// - uri: "file://" uri
+/// # RETURN VALUE
+/// decoded path (relative to the current dir if under it)
+fn uri_path(uri: &str) -> String {
+    let decoded = decode(uri, cfg!(windows));
+    match env::current_dir() {
+        Ok(dir) => path::Path::new(&decoded).strip_prefix(dir)
+            .map(|relative| relative.to_string_lossy().to_string())
+            .unwrap_or(decoded),
+        Err(_) => decoded,
+    }
+}
+
+/// # CONTENT
+/// get the absolute path of a file uri
+/// # ARGUMENT
+/// - uri: "file://" uri
+/// - windows: get a Windows path ("C:\..." from "file:///C:/...")
+/// # RETURN VALUE
+/// decoded absolute path
+fn decode(uri: &str, windows: bool) -> String {
+    let encoded = uri.strip_prefix("file://").unwrap_or(uri).as_bytes();
+    let mut bytes = Vec::new();
+    let mut i = 0;
+    while i < encoded.len() {
+        match (encoded[i], encoded.get(i + 1..i + 3).and_then(|h| u8::from_str_radix(&String::from_utf8_lossy(h), 16).ok())) {
+            (b'%', Some(b)) => {
+                bytes.push(b);
+                i += 3;
+            }
+            (b, _) => {
+                bytes.push(b);
+                i += 1;
+            }
+        }
+    }
+    let decoded = String::from_utf8_lossy(&bytes).to_string();
+    if !windows {
+        return decoded;
+    }
+    let path = match decoded.as_bytes() {
+        [b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => &decoded[1..],
+        _ => &decoded,
+    };
+    path.replace('/', "\\")
+}
+
+/// # CONTENT
+/// get document text sent to a server
+/// # ARGUMENT
+/// - rows: content rows
+/// # RETURN VALUE
+/// rows ending with "\n" (as written by File::write)
+fn text(rows: &[String]) -> String {
+    rows.iter().map(|row| format!("{}\n", row)).collect()
+}
+
+/// # CONTENT
+/// get the changed range of content (rows only, or chars of a single changed row)
+/// # ARGUMENT
+/// - old: sent content
+/// - new: current content
+/// # RETURN VALUE
+/// Some(edit): the old range (char indexes) replaced by the new text
+/// None: no change
+fn change(old: &[String], new: &[String]) -> Option<Edit> {
+    if old == new {
+        return None;
+    }
+    let top = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let bottom = old[top..].iter().rev().zip(new[top..].iter().rev()).take_while(|(a, b)| a == b).count();
+    let (old_rows, new_rows) = (&old[top..old.len() - bottom], &new[top..new.len() - bottom]);
+    if let ([a], [b]) = (old_rows, new_rows) {
+        let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+        let prefix = a.iter().zip(&b).take_while(|(a, b)| a == b).count();
+        let suffix = a[prefix..].iter().rev().zip(b[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+        return Some(Edit { start: (prefix, top), end: (a.len() - suffix, top), text: b[prefix..b.len() - suffix].iter().collect() });
+    }
+    Some(Edit { start: (0, top), end: (0, old.len() - bottom), text: text(new_rows) })
+}
+
+/// # CONTENT
+/// get the protocol col of a char index
+/// # ARGUMENT
+/// - row: row content
+/// - x: char index
+/// - utf16: count UTF-16 code units (otherwise chars)
+/// # RETURN VALUE
+/// UTF-16 code unit or char index
+fn col(row: &str, x: usize, utf16: bool) -> usize {
+    if utf16 { row.chars().take(x).map(char::len_utf16).sum() } else { x }
+}
+
+/// # CONTENT
+/// get the char index of a protocol col
+/// # ARGUMENT
+/// - row: row content
+/// - col: UTF-16 code unit or char index
+/// - utf16: col counts UTF-16 code units (otherwise chars)
+/// # RETURN VALUE
+/// char index (a col inside a surrogate pair is the index of its char)
+fn char_x(row: &str, col: usize, utf16: bool) -> usize {
+    if !utf16 {
+        return col;
+    }
+    let mut units = 0;
+    row.chars().take_while(|c| {
+        units += c.len_utf16();
+        units <= col
+    }).count()
+}
+
+/// # CONTENT
+/// get the byte index of a pos clamped to content (a pos after the last row is the end of it)
+/// # ARGUMENT
+/// - content: file content
+/// - pos: (x, y)
+/// # RETURN VALUE
+/// (byte index, y)
+fn clamp(content: &[String], pos: (usize, usize)) -> (usize, usize) {
+    let (x, y) = pos;
+    match content.get(y) {
+        Some(row) => (row.char_indices().nth(x).map_or(row.len(), |(i, _)| i), y),
+        None => (content[content.len() - 1].len(), content.len() - 1),
+    }
+}
+
+/// # CONTENT
+/// apply edits to a file (saving an undo step)
+/// # ARGUMENT
+/// - file_handle: edited file
+/// - edits: non-overlapping edits
+/// # RETURN VALUE
+/// none
+pub fn apply(file_handle: &mut file::File, edits: &mut [Edit]) {
+    let Some(first) = edits.first() else {
+        return;
+    };
+    file_handle.save_undo(first.start.0, first.start.1.min(file_handle.content.len() - 1));
+    edits.sort_by_key(|edit| (edit.start.1, edit.start.0));
+    let content = file_handle.content_mut();
+    for edit in edits.iter().rev() {
+        let (start_x, start_y) = clamp(content, edit.start);
+        let (end_x, end_y) = clamp(content, edit.end);
+        let replaced = format!("{}{}{}", &content[start_y][..start_x], edit.text, &content[end_y][end_x..]);
+        content.splice(start_y..=end_y, replaced.split('\n').map(|row| row.to_string()).collect::<Vec<_>>());
+    }
+}
+
+impl Client {
+    /// # CONTENT
+    /// start a server and send the initialize request
+    /// # ARGUMENT
+    /// - language: language id
+    /// - command: shell cmd running the server
+    /// # RETURN VALUE
+    /// Ok(client): started server
+    /// Err(...): spawn error
+    fn start(language: &str, command: &str) -> io::Result<Self> {
+        let mut process = shell::command(command)
+            .stdin(process::Stdio::piped())
+            .stdout(process::Stdio::piped())
+            .stderr(process::Stdio::null())
+            .spawn()?;
+        let (Some(stdin), Some(stdout)) = (process.stdin.take(), process.stdout.take()) else {
+            return Err(io::Error::other("no stdio pipe"));
+        };
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let mut reader = io::BufReader::new(stdout);
+            loop {
+                let mut length = 0;
+                let mut header = String::new();
+                loop {
+                    header.clear();
+                    if reader.read_line(&mut header).unwrap_or(0) == 0 {
+                        return;
+                    }
+                    let header = header.trim_end();
+                    if header.is_empty() {
+                        break;
+                    }
+                    if let Some(n) = header.strip_prefix("Content-Length:") {
+                        length = n.trim().parse().unwrap_or(0);
+                    }
+                }
+                let mut body = vec![0; length];
+                if reader.read_exact(&mut body).is_err() {
+                    return;
+                }
+                if let Ok(message) = serde_json::from_slice(&body)
+                    && sender.send(message).is_err() {
+                    return;
+                }
+            }
+        });
+        let mut client = Self {
+            language: language.to_string(),
+            process,
+            stdin,
+            receiver,
+            next_id: 1,
+            pending: HashMap::new(),
+            ready: false,
+            utf16: true,
+            sync: 2,
+            documents: HashMap::new(),
+        };
+        let root = uri(".");
+        client.request(Pending::Initialize, "initialize", json!({
+            "processId": process::id(),
+            "rootUri": root,
+            "workspaceFolders": [{ "uri": root, "name": root.rsplit('/').next().unwrap_or_default() }],
+            "capabilities": {
+                "general": { "positionEncodings": ["utf-32", "utf-16"] },
+                "textDocument": {
+                    "synchronization": { "dynamicRegistration": false },
+                    "completion": { "completionItem": { "snippetSupport": false } },
+                    "definition": { "linkSupport": true },
+                    "references": {},
+                    "hover": { "contentFormat": ["plaintext", "markdown"] },
+                    "rename": { "prepareSupport": false },
+                    "publishDiagnostics": { "relatedInformation": false },
+                },
+                "workspace": { "workspaceEdit": { "documentChanges": true } },
+            },
+        }));
+        Ok(client)
+    }
+
+    /// # CONTENT
+    /// send a message
+    /// # ARGUMENT
+    /// - message: JSON-RPC message (write errors are left to the reader thread noticing the exit)
+    /// # RETURN VALUE
+    /// none
+    fn send(&mut self, mut message: Value) {
+        message["jsonrpc"] = json!("2.0");
+        let body = message.to_string();
+        let _ = write!(self.stdin, "Content-Length: {}\r\n\r\n{}", body.len(), body);
+        let _ = self.stdin.flush();
+    }
+
+    /// # CONTENT
+    /// send a request
+    /// # ARGUMENT
+    /// - pending: kind of the awaited response
+    /// - method: method name
+    /// - params: method params
+    /// # RETURN VALUE
+    /// none
+    fn request(&mut self, pending: Pending, method: &str, params: Value) {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.pending.insert(id, pending);
+        self.send(json!({ "id": id, "method": method, "params": params }));
+    }
+
+    /// # CONTENT
+    /// send a notification
+    /// # ARGUMENT
+    /// - method: method name
+    /// - params: method params
+    /// # RETURN VALUE
+    /// none
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(json!({ "method": method, "params": params }));
+    }
+
+    /// # CONTENT
+    /// get the protocol col of a char index
+    /// # ARGUMENT
+    /// - row: row content
+    /// - x: char index
+    /// # RETURN VALUE
+    /// UTF-16 code unit or char index
+    fn col(&self, row: &str, x: usize) -> usize {
+        col(row, x, self.utf16)
+    }
+
+    /// # CONTENT
+    /// get the char index of a protocol pos
+    /// # ARGUMENT
+    /// - content: rows of the document (None if unknown)
+    /// - pos: protocol pos ({"line", "character"})
+    /// # RETURN VALUE
+    /// (x, y)
+    fn pos(&self, content: Option<&[String]>, pos: &Value) -> (usize, usize) {
+        let y = pos["line"].as_u64().unwrap_or(0) as usize;
+        let col = pos["character"].as_u64().unwrap_or(0) as usize;
+        let x = content.and_then(|content| content.get(y)).map_or(col, |row| char_x(row, col, self.utf16));
+        (x, y)
+    }
+
+    /// # CONTENT
+    /// get the path and last sent content of a uri
+    /// # ARGUMENT
+    /// - uri: document uri
+    /// # RETURN VALUE
+    /// (path, content of an opened document)
+    fn document(&self, uri: &str) -> (String, Option<&[String]>) {
+        match self.documents.iter().find(|(_, document)| document.uri == uri) {
+            Some((path, document)) => (path.clone(), Some(&document.content)),
+            None => (uri_path(uri), None),
+        }
+    }
+
+    /// # CONTENT
+    /// convert a protocol location (Location or LocationLink)
+    /// # ARGUMENT
+    /// - value: protocol location
+    /// # RETURN VALUE
+    /// Some(location): location with the text of its row
+    /// None: not a location
+    fn location(&self, value: &Value) -> Option<location::Location> {
+        let uri = value["uri"].as_str().or(value["targetUri"].as_str())?;
+        let range = if value["targetSelectionRange"].is_object() { &value["targetSelectionRange"] } else { &value["range"] };
+        let (path, content) = self.document(uri);
+        let read;
+        let content = match content {
+            Some(content) => Some(content),
+            None => {
+                read = fs::read_to_string(&path).map(|text| text.lines().map(|row| row.to_string()).collect::<Vec<_>>()).ok();
+                read.as_deref()
+            }
+        };
+        let (x, y) = self.pos(content, &range["start"]);
+        let text = content.and_then(|content| content.get(y)).map(|row| row.trim().to_string()).unwrap_or_default();
+        Some(location::Location { path, y, x, text })
+    }
+
+    /// # CONTENT
+    /// convert text edits of a document
+    /// # ARGUMENT
+    /// - uri: document uri
+    /// - edits: protocol TextEdit array
+    /// # RETURN VALUE
+    /// (path, edits)
+    fn edits(&self, uri: &str, edits: &Value) -> (String, Vec<Edit>) {
+        let (path, content) = self.document(uri);
+        let edits = edits.as_array().into_iter().flatten().map(|edit| Edit {
+            start: self.pos(content, &edit["range"]["start"]),
+            end: self.pos(content, &edit["range"]["end"]),
+            text: edit["newText"].as_str().unwrap_or_default().to_string(),
+        }).collect();
+        (path, edits)
+    }
+
+    /// # CONTENT
+    /// open, change or close documents of the open files of the language
+    /// # ARGUMENT
+    /// - files: open buffers
+    /// # RETURN VALUE
+    /// none
+    fn sync(&mut self, files: &files::Files) {
+        if !self.ready {
+            return;
+        }
+        let mut open = Vec::new();
+        for i in 0..files.len() {
+            let file_handle = files.get(i);
+            let Some(path) = file_handle.path.clone() else {
+                continue;
+            };
+            if file_handle.explorer.is_some() || language(&path) != Some(self.language.as_str()) {
+                continue;
+            }
+            open.push(path.clone());
+            let Some(document) = self.documents.get(&path) else {
+                let uri = uri(&path);
+                self.notify("textDocument/didOpen", json!({
+                    "textDocument": { "uri": uri, "languageId": self.language, "version": 1, "text": text(&file_handle.content) },
+                }));
+                self.documents.insert(path, Document { uri, version: 1, content: file_handle.content.clone() });
+                continue;
+            };
+            let Some(edit) = change(&document.content, &file_handle.content) else {
+                continue;
+            };
+            let version = document.version + 1;
+            let changes = match self.sync {
+                2 => {
+                    let col = |(x, y): (usize, usize)| json!({ "line": y, "character": document.content.get(y).map_or(0, |row| self.col(row, x)) });
+                    json!([{ "range": { "start": col(edit.start), "end": col(edit.end) }, "text": edit.text }])
+                }
+                _ => json!([{ "text": text(&file_handle.content) }]),
+            };
+            let uri = document.uri.clone();
+            if self.sync != 0 {
+                self.notify("textDocument/didChange", json!({ "textDocument": { "uri": uri, "version": version }, "contentChanges": changes }));
+            }
+            self.documents.insert(path, Document { uri, version, content: file_handle.content.clone() });
+        }
+        let closed: Vec<String> = self.documents.keys().filter(|path| !open.contains(path)).cloned().collect();
+        for path in closed {
+            if let Some(document) = self.documents.remove(&path) {
+                self.notify("textDocument/didClose", json!({ "textDocument": { "uri": document.uri } }));
+            }
+        }
+    }
+
+    /// # CONTENT
+    /// handle the messages read from the server
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// Ok(responses): results to run
+    /// Err(()): the server exited
+    fn poll(&mut self) -> Result<Vec<Response>, ()> {
+        let mut responses = Vec::new();
+        loop {
+            let message = match self.receiver.try_recv() {
+                Ok(message) => message,
+                Err(mpsc::TryRecvError::Empty) => return Ok(responses),
+                Err(mpsc::TryRecvError::Disconnected) => return Err(()),
+            };
+            match (message.get("id").cloned(), message["method"].as_str()) {
+                (Some(id), Some(method)) => {
+                    // server request: answer the ones needing a value, accept the others
+                    let result = match method {
+                        "workspace/configuration" => json!(message["params"]["items"].as_array().map_or(Vec::new(), |items| vec![Value::Null; items.len()])),
+                        _ => Value::Null,
+                    };
+                    self.send(json!({ "id": id, "result": result }));
+                }
+                (None, Some("textDocument/publishDiagnostics")) => {
+                    let params = &message["params"];
+                    let (path, content) = self.document(params["uri"].as_str().unwrap_or_default());
+                    let diagnostics = params["diagnostics"].as_array().into_iter().flatten().map(|item| diagnostic::Diagnostic {
+                        severity: diagnostic::Severity::from_number(item["severity"].as_u64()),
+                        start: self.pos(content, &item["range"]["start"]),
+                        end: self.pos(content, &item["range"]["end"]),
+                        message: item["message"].as_str().unwrap_or_default().to_string(),
+                        source: diagnostic::SOURCE_LSP,
+                    }).collect();
+                    responses.push(Response::Diagnostics(path, diagnostics));
+                }
+                (None, Some("window/showMessage")) => {
+                    responses.push(Response::Message(format!("lsp: {}", message["params"]["message"].as_str().unwrap_or_default())));
+                }
+                (Some(id), None) => {
+                    let Some(pending) = id.as_u64().and_then(|id| self.pending.remove(&id)) else {
+                        continue;
+                    };
+                    if let Some(error) = message["error"]["message"].as_str() {
+                        responses.push(Response::Message(format!("lsp: {}", error)));
+                        continue;
+                    }
+                    responses.extend(self.response(pending, &message["result"]));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// # CONTENT
+    /// convert the result of a request
+    /// # ARGUMENT
+    /// - pending: kind of the request
+    /// - result: response result
+    /// # RETURN VALUE
+    /// Some(response): result to run
+    /// None: nothing to run
+    fn response(&mut self, pending: Pending, result: &Value) -> Option<Response> {
+        match pending {
+            Pending::Initialize => {
+                let capabilities = &result["capabilities"];
+                self.utf16 = capabilities["positionEncoding"].as_str() != Some("utf-32");
+                self.sync = match &capabilities["textDocumentSync"] {
+                    Value::Number(n) => n.as_u64().unwrap_or(1),
+                    sync => sync["change"].as_u64().unwrap_or(1),
+                };
+                self.ready = true;
+                self.notify("initialized", json!({}));
+                None
+            }
+            Pending::Completion(y) => {
+                let values = if result.is_array() { result } else { &result["items"] };
+                let items = values.as_array().into_iter().flatten().map(|item| {
+                    let label = item["label"].as_str().unwrap_or_default();
+                    let text = item["textEdit"]["newText"].as_str().or(item["insertText"].as_str())
+                        .filter(|_| item["insertTextFormat"].as_u64() != Some(2))
+                        .unwrap_or(label);
+                    let kind = item["kind"].as_u64().and_then(|n| COMPLETION_KINDS.get(n.saturating_sub(1) as usize)).copied().unwrap_or_default();
+                    completion::Item { label: text.to_string(), detail: item["detail"].as_str().unwrap_or(kind).to_string() }
+                }).collect();
+                Some(Response::Completion(y, items))
+            }
+            Pending::Definition => {
+                let locations: Vec<location::Location> = match result {
+                    Value::Array(values) => values.iter().filter_map(|value| self.location(value)).collect(),
+                    value => self.location(value).into_iter().collect(),
+                };
+                match locations.as_slice() {
+                    [] => Some(Response::Message("lsp_definition: no definition".to_string())),
+                    [location] => Some(Response::Jump(location.path.clone(), (location.x, location.y))),
+                    _ => Some(Response::Locations("[definitions]".to_string(), locations)),
+                }
+            }
+            Pending::Hover => {
+                let contents = &result["contents"];
+                let text = match contents {
+                    Value::String(s) => s.clone(),
+                    Value::Array(values) => values.iter().map(|value| value.as_str().or(value["value"].as_str()).unwrap_or_default()).collect::<Vec<_>>().join("\n"),
+                    value => value["value"].as_str().unwrap_or_default().to_string(),
+                };
+                let rows: Vec<&str> = text.lines().filter(|row| !row.trim().is_empty() && !row.starts_with("```")).take(MAX_HOVER_ROWS).collect();
+                Some(Response::Message(if rows.is_empty() { "lsp_hover: no info".to_string() } else { rows.join("\n") }))
+            }
+            Pending::References => {
+                let locations: Vec<location::Location> = result.as_array().into_iter().flatten().filter_map(|value| self.location(value)).collect();
+                if locations.is_empty() {
+                    return Some(Response::Message("lsp_references: no references".to_string()));
+                }
+                Some(Response::Locations("[references]".to_string(), locations))
+            }
+            Pending::Rename => {
+                let mut changes = Vec::new();
+                if let Some(document_changes) = result["documentChanges"].as_array() {
+                    for change in document_changes.iter().filter(|change| change["edits"].is_array()) {
+                        changes.push(self.edits(change["textDocument"]["uri"].as_str().unwrap_or_default(), &change["edits"]));
+                    }
+                } else if let Some(map) = result["changes"].as_object() {
+                    for (uri, edits) in map {
+                        changes.push(self.edits(uri, edits));
+                    }
+                }
+                if changes.is_empty() {
+                    return Some(Response::Message("lsp_rename: nothing renamed".to_string()));
+                }
+                Some(Response::Edit(changes))
+            }
+        }
+    }
+}
+
+impl Drop for Client {
+    fn drop(&mut self) {
+        // shutdown request, then exit notification (the server is killed if it does not exit in time)
+        if self.ready {
+            let id = self.next_id;
+            self.next_id += 1;
+            self.send(json!({ "id": id, "method": "shutdown" }));
+            let deadline = time::Instant::now() + time::Duration::from_millis(SHUTDOWN_MILLIS);
+            while let Some(timeout) = deadline.checked_duration_since(time::Instant::now()) {
+                match self.receiver.recv_timeout(timeout) {
+                    Ok(message) if message["id"].as_u64() == Some(id) && message.get("method").is_none() => break,
+                    Ok(_) => {}
+                    Err(_) => break,
+                }
+            }
+        }
+        self.notify("exit", Value::Null);
+        let deadline = time::Instant::now() + time::Duration::from_millis(SHUTDOWN_MILLIS);
+        while matches!(self.process.try_wait(), Ok(None)) && time::Instant::now() < deadline {
+            thread::sleep(time::Duration::from_millis(10));
+        }
+        let _ = self.process.kill();
+        let _ = self.process.wait();
+    }
+}
+
+impl Lsp {
+    /// # CONTENT
+    /// init Lsp without servers (started by Lsp::sync)
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// inited Lsp
+    pub fn new() -> Self {
+        Self {
+            clients: Vec::new(),
+            failed: Vec::new(),
+        }
+    }
+
+    /// # CONTENT
+    /// check if a server runs (the main loop waits for its messages)
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// true if a server runs
+    pub fn running(&self) -> bool {
+        !self.clients.is_empty()
+    }
+
+    /// # CONTENT
+    /// start the configured servers of open files and sync their documents
+    /// # ARGUMENT
+    /// - servers: configured (language id, shell cmd) pairs
+    /// - files: open buffers
+    /// # RETURN VALUE
+    /// messages of servers failing to start
+    pub fn sync(&mut self, servers: &[(String, String)], files: &files::Files) -> Vec<String> {
+        let mut messages = Vec::new();
+        for i in 0..files.len() {
+            let Some(language) = files.get(i).path.as_deref().and_then(language) else {
+                continue;
+            };
+            if self.clients.iter().any(|client| client.language == language) || self.failed.iter().any(|l| l == language) {
+                continue;
+            }
+            let Some((_, command)) = servers.iter().find(|(l, _)| l == language) else {
+                continue;
+            };
+            match Client::start(language, command) {
+                Ok(client) => self.clients.push(client),
+                Err(e) => {
+                    self.failed.push(language.to_string());
+                    messages.push(format!("lsp: {}: {}", language, e));
+                }
+            }
+        }
+        for client in self.clients.iter_mut() {
+            client.sync(files);
+        }
+        messages
+    }
+
+    /// # CONTENT
+    /// stop the server of a language and start it again by the next Lsp::sync (also after a failure)
+    /// # ARGUMENT
+    /// - language: language id
+    /// # RETURN VALUE
+    /// results to run (the diagnostics of the stopped server are cleared)
+    pub fn restart(&mut self, language: &str) -> Vec<Response> {
+        self.failed.retain(|l| l != language);
+        let mut responses = Vec::new();
+        if let Some(i) = self.clients.iter().position(|client| client.language == language) {
+            let client = self.clients.remove(i);
+            for path in client.documents.keys() {
+                responses.push(Response::Diagnostics(path.clone(), Vec::new()));
+            }
+        }
+        responses
+    }
+
+    /// # CONTENT
+    /// handle the messages of every server
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// results to run (a server exiting is reported as a message and its diagnostics are cleared)
+    pub fn poll(&mut self) -> Vec<Response> {
+        let mut responses = Vec::new();
+        let mut i = 0;
+        while i < self.clients.len() {
+            match self.clients[i].poll() {
+                Ok(r) => {
+                    responses.extend(r);
+                    i += 1;
+                }
+                Err(()) => {
+                    let client = self.clients.remove(i);
+                    for path in client.documents.keys() {
+                        responses.push(Response::Diagnostics(path.clone(), Vec::new()));
+                    }
+                    responses.push(Response::Message(format!("lsp: {} server exited", client.language)));
+                    self.failed.push(client.language.clone());
+                }
+            }
+        }
+        responses
+    }
+
+    /// # CONTENT
+    /// send a request at a pos of a file
+    /// # ARGUMENT
+    /// - request: server cmd
+    /// - file_handle: shown file (synced by Lsp::sync)
+    /// - pos: center pos (x, y)
+    /// # RETURN VALUE
+    /// message ("" if sent)
+    pub fn run(&mut self, request: Request, file_handle: &file::File, pos: (usize, usize)) -> String {
+        let Some(path) = file_handle.path.as_deref() else {
+            return "lsp: no file name".to_string();
+        };
+        let Some(client) = language(path).and_then(|language| self.clients.iter_mut().find(|client| client.language == language)) else {
+            return "lsp: no server for this file".to_string();
+        };
+        let Some(document) = client.documents.get(path).filter(|_| client.ready) else {
+            return "lsp: server starting".to_string();
+        };
+        let (x, y) = pos;
+        let params = json!({
+            "textDocument": { "uri": document.uri },
+            "position": { "line": y, "character": client.col(&file_handle.content[y], x) },
+        });
+        match request {
+            Request::Completion => client.request(Pending::Completion(y), "textDocument/completion", params),
+            Request::Definition => client.request(Pending::Definition, "textDocument/definition", params),
+            Request::Hover => client.request(Pending::Hover, "textDocument/hover", params),
+            Request::References => {
+                let mut params = params;
+                params["context"] = json!({ "includeDeclaration": true });
+                client.request(Pending::References, "textDocument/references", params);
+            }
+            Request::Rename(name) => {
+                let mut params = params;
+                params["newName"] = json!(name);
+                client.request(Pending::Rename, "textDocument/rename", params);
+            }
+        }
+        String::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(rows: &[&str]) -> Vec<String> {
+        rows.iter().map(|row| row.to_string()).collect()
+    }
+
+    #[test]
+    fn change_ranges() {
+        let old = rows(&["ab", "cd", "ef"]);
+        assert!(change(&old, &old).is_none());
+        let edit = change(&old, &rows(&["ab", "cXd", "ef"])).unwrap();
+        assert_eq!((edit.start, edit.end, edit.text.as_str()), ((1, 1), (1, 1), "X"));
+        let edit = change(&old, &rows(&["ab", "c", "ef"])).unwrap();
+        assert_eq!((edit.start, edit.end, edit.text.as_str()), ((1, 1), (2, 1), ""));
+        let edit = change(&old, &rows(&["ab", "x", "y", "ef"])).unwrap();
+        assert_eq!((edit.start, edit.end, edit.text.as_str()), ((0, 1), (0, 2), "x\ny\n"));
+        let edit = change(&old, &rows(&["ab", "ef"])).unwrap();
+        assert_eq!((edit.start, edit.end, edit.text.as_str()), ((0, 1), (0, 2), ""));
+    }
+
+    #[test]
+    fn clamp_pos() {
+        let content = rows(&["aé", "b"]);
+        assert_eq!(clamp(&content, (1, 0)), (1, 0));
+        assert_eq!(clamp(&content, (2, 0)), (3, 0));
+        assert_eq!(clamp(&content, (9, 1)), (1, 1));
+        assert_eq!(clamp(&content, (0, 5)), (1, 1));
+    }
+
+    #[test]
+    fn uri_encoding() {
+        assert_eq!(encode("/tmp/a b%.rs", false), "file:///tmp/a%20b%25.rs");
+        assert_eq!(decode("file:///tmp/a%20b%25.rs", false), "/tmp/a b%.rs");
+        assert_eq!(encode("C:\\src\\a b.rs", true), "file:///C:/src/a%20b.rs");
+        assert_eq!(encode("\\\\?\\C:\\src\\a.rs", true), "file:///C:/src/a.rs");
+        assert_eq!(decode("file:///C:/src/a%20b.rs", true), "C:\\src\\a b.rs");
+        assert_eq!(decode("file:///c%3A/src/a.rs", true), "c:\\src\\a.rs");
+        let path = if cfg!(windows) { "src\\main.rs" } else { "src/main.rs" };
+        assert_eq!(uri_path(&uri(path)), path);
+    }
+
+    #[test]
+    fn utf16_cols() {
+        let row = "a😀b";
+        assert_eq!(col(row, 2, true), 3);
+        assert_eq!(col(row, 2, false), 2);
+        assert_eq!(col(row, 9, true), 4);
+        assert_eq!(char_x(row, 3, true), 2);
+        assert_eq!(char_x(row, 2, true), 1);
+        assert_eq!(char_x(row, 3, false), 3);
+        assert_eq!(char_x(row, 9, true), 3);
+    }
+
+    /// marker arg of the test process run as the stub server
+    const STUB_MARKER: &str = "lsp_stub_marker";
+
+    fn stub_read(reader: &mut impl BufRead) -> Option<Value> {
+        let mut length = 0;
+        loop {
+            let mut header = String::new();
+            if reader.read_line(&mut header).ok()? == 0 {
+                return None;
+            }
+            match header.trim_end() {
+                "" => break,
+                header => if let Some(n) = header.strip_prefix("Content-Length:") {
+                    length = n.trim().parse().ok()?;
+                },
+            }
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).ok()?;
+        serde_json::from_slice(&body).ok()
+    }
+
+    fn stub_send(message: Value) {
+        let body = message.to_string();
+        let mut stdout = io::stdout().lock();
+        let _ = write!(stdout, "Content-Length: {}\r\n\r\n{}", body.len(), body);
+        let _ = stdout.flush();
+    }
+
+    fn range(start: (u64, u64), end: (u64, u64)) -> Value {
+        json!({ "start": { "line": start.0, "character": start.1 }, "end": { "line": end.0, "character": end.1 } })
+    }
+
+    /// scripted server (UTF-16 positions, incremental sync) run by the client test
+    #[test]
+    fn stub_server() {
+        if !env::args().any(|arg| arg == STUB_MARKER) {
+            return;
+        }
+        let mut reader = io::BufReader::new(io::stdin());
+        while let Some(message) = stub_read(&mut reader) {
+            let (id, params) = (message["id"].clone(), &message["params"]);
+            let uri = params["textDocument"]["uri"].clone();
+            match message["method"].as_str().unwrap_or_default() {
+                "initialize" => stub_send(json!({ "jsonrpc": "2.0", "id": id, "result": {
+                    "capabilities": { "positionEncoding": "utf-16", "textDocumentSync": 2 },
+                } })),
+                "textDocument/didOpen" => stub_send(json!({ "jsonrpc": "2.0", "method": "textDocument/publishDiagnostics", "params": {
+                    "uri": uri,
+                    "diagnostics": [{ "range": range((0, 3), (0, 4)), "severity": 1, "message": "opened" }],
+                } })),
+                "textDocument/didChange" => stub_send(json!({ "jsonrpc": "2.0", "method": "textDocument/publishDiagnostics", "params": {
+                    "uri": uri,
+                    "diagnostics": [{ "range": range((0, 0), (0, 0)), "severity": 2, "message": params["contentChanges"].to_string() }],
+                } })),
+                "textDocument/definition" => stub_send(json!({ "jsonrpc": "2.0", "id": id, "result": {
+                    "uri": uri, "range": range((1, 3), (1, 7)),
+                } })),
+                "textDocument/references" => stub_send(json!({ "jsonrpc": "2.0", "id": id, "result": [
+                    { "uri": uri, "range": range((0, 3), (0, 4)) },
+                    { "uri": uri, "range": range((1, 3), (1, 7)) },
+                ] })),
+                "textDocument/rename" => stub_send(json!({ "jsonrpc": "2.0", "id": id, "result": {
+                    "changes": { uri.as_str().unwrap_or_default(): [{ "range": range((0, 1), (0, 3)), "newText": params["newName"] }] },
+                } })),
+                "shutdown" => stub_send(json!({ "jsonrpc": "2.0", "id": id, "result": null })),
+                "exit" => process::exit(0),
+                _ => {}
+            }
+        }
+        process::exit(1);
+    }
+
+    fn wait(lsp: &mut Lsp) -> Vec<Response> {
+        let deadline = time::Instant::now() + time::Duration::from_secs(10);
+        loop {
+            let responses = lsp.poll();
+            if !responses.is_empty() {
+                return responses;
+            }
+            assert!(time::Instant::now() < deadline, "no response from the stub server");
+            thread::sleep(time::Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn client_stub_server() {
+        let path = env::temp_dir().join(format!("center_lsp_{}.rs", process::id())).to_string_lossy().to_string();
+        fs::write(&path, "a😀b x\nfn main() {}\n").unwrap();
+        let mut files = files::Files::open(std::slice::from_ref(&path)).unwrap();
+        let exe = env::current_exe().unwrap();
+        let command = format!("\"{}\" lsp::tests::stub_server {} --exact --nocapture --test-threads=1 -q", exe.display(), STUB_MARKER);
+        let servers = [("rust".to_string(), command)];
+        let mut lsp = Lsp::new();
+        assert!(lsp.sync(&servers, &files).is_empty());
+        let deadline = time::Instant::now() + time::Duration::from_secs(10);
+        while !lsp.clients[0].ready {
+            assert!(time::Instant::now() < deadline, "no initialize response");
+            assert!(lsp.poll().is_empty());
+            thread::sleep(time::Duration::from_millis(10));
+        }
+
+        // didOpen: diagnostics mapped from UTF-16 cols
+        lsp.sync(&servers, &files);
+        match wait(&mut lsp).as_slice() {
+            [Response::Diagnostics(p, diagnostics)] => {
+                assert_eq!(p, &path);
+                assert_eq!((diagnostics[0].start, diagnostics[0].end, diagnostics[0].message.as_str()), ((2, 0), (3, 0), "opened"));
+            }
+            _ => panic!("no diagnostics"),
+        }
+
+        // didChange: incremental range in UTF-16 cols of the sent content
+        files.get_mut(0).content[0] = "a😀bc x".to_string();
+        lsp.sync(&servers, &files);
+        match wait(&mut lsp).as_slice() {
+            [Response::Diagnostics(_, diagnostics)] => {
+                let changes: Value = serde_json::from_str(&diagnostics[0].message).unwrap();
+                assert_eq!(changes, json!([{ "range": range((0, 4), (0, 4)), "text": "c" }]));
+            }
+            _ => panic!("no diagnostics"),
+        }
+
+        // definition, references and rename
+        lsp.run(Request::Definition, files.get(0), (0, 1));
+        match wait(&mut lsp).as_slice() {
+            [Response::Jump(p, pos)] => assert_eq!((p, *pos), (&path, (3, 1))),
+            _ => panic!("no jump"),
+        }
+        lsp.run(Request::References, files.get(0), (0, 1));
+        match wait(&mut lsp).as_slice() {
+            [Response::Locations(name, locations)] => {
+                assert_eq!(name, "[references]");
+                assert_eq!(locations.iter().map(|l| (l.x, l.y, l.text.as_str())).collect::<Vec<_>>(), [(2, 0, "a😀bc x"), (3, 1, "fn main() {}")]);
+            }
+            _ => panic!("no references"),
+        }
+        lsp.run(Request::Rename("🙂".to_string()), files.get(0), (1, 0));
+        let mut responses = wait(&mut lsp);
+        match responses.as_mut_slice() {
+            [Response::Edit(changes)] => {
+                assert_eq!(changes.len(), 1);
+                assert_eq!(changes[0].0, path);
+                apply(files.get_mut(0), &mut changes[0].1);
+            }
+            _ => panic!("no edit"),
+        }
+        assert_eq!(files.get(0).content, rows(&["a🙂bc x", "fn main() {}"]));
+
+        // shutdown and exit
+        let start = time::Instant::now();
+        drop(lsp);
+        assert!(start.elapsed() < time::Duration::from_millis(SHUTDOWN_MILLIS * 2));
+        let _ = fs::remove_file(&path);
+    }
+}
@@ -2,13 +2,34 @@
 //! main program
 
 mod cmd;
+mod completion;
+mod diagnostic;
 mod display;
+mod explorer;
 mod file;
+mod files;
+mod finder;
+mod grep;
+mod key;
+mod keymap;
+mod line;
+mod location;
+mod lsp;
+mod motion;
+mod quickfix;
+mod register;
+mod search;
+mod shell;
+mod substitute;
+mod syntax;
+mod visual;
+mod window;
 
 use {
     std:: {
         env,
         io,
+        time,
     },
     crossterm:: {
         cursor,
@@ -31,8 +52,16 @@ fn main() -> io::Result<()> {
     let mut display_handle = display::Display::new();
     display_handle.resize()?;
     let args: Vec<String> = env::args().collect();
-    let mut file_handle = file::File::new(args.get(1).cloned());
-    file_handle.read()?;
+    let mut windows = window::Windows::new(files::Files::open(&args[1..])?);
+    let mut lsp_handle = lsp::Lsp::new();
+    cmd.config(&mut display_handle, windows.file_mut());
+    cmd.lsp_restart.clear(); // no server runs yet
+    if let Some(request) = cmd.request.take() {
+        cmd.message = windows.run_files(request, &mut display_handle);
+    }
+    for message in lsp_handle.sync(&cmd.lsp_servers, windows.files()) {
+        cmd.message = message;
+    }
 
     // init display
     terminal::enable_raw_mode()?;
@@ -43,30 +72,129 @@ fn main() -> io::Result<()> {
         cursor::MoveTo(0, 0),
         terminal::Clear(terminal::ClearType::All),
     )?;
+    display_handle.print(&cmd, &windows)?;
 
+    let mut key_time = time::Instant::now();
     loop {
-        match event::read()? {
-            event::Event::Key(key) => {
-                match key.code {
-                    event::KeyCode::Char(kc) => {
-                        if cmd.key(kc, &mut display_handle, &mut file_handle) == false {
-                            break;
-                        }
+        // wait for a key (and for server messages or program output while a server or program runs)
+        let key_timeout = cmd.timeout().map(|t| t.saturating_sub(key_time.elapsed()));
+        let polling = lsp_handle.running() || cmd.job_running();
+        let wait = match key_timeout {
+            Some(t) if polling => Some(t.min(time::Duration::from_millis(lsp::POLL_MILLIS))),
+            None if polling => Some(time::Duration::from_millis(lsp::POLL_MILLIS)),
+            t => t,
+        };
+        let mut changed = true;
+        if let Some(wait) = wait
+            && !event::poll(wait)? {
+            if key_timeout.is_some_and(|t| t <= wait) {
+                if !cmd.flush(&mut display_handle, windows.file_mut()) {
+                    break;
+                }
+            } else {
+                changed = false;
+            }
+        } else {
+            match event::read()? {
+                event::Event::Key(e) => {
+                    key_time = time::Instant::now();
+                    if let Some(k) = key::Key::from_event(e)
+                        && !cmd.key(k, &mut display_handle, windows.file_mut()) {
+                        break;
                     }
-                    event::KeyCode::Enter => {
-                        if cmd.key('\n', &mut display_handle, &mut file_handle) == false {
-                            break;
-                        }
+                }
+                event::Event::Resize(_, _) => {
+                    display_handle.resize()?;
+                    windows.fit(&mut display_handle);
+                }
+                _ => {}
+            }
+        }
+        if cmd.job_poll() {
+            changed = true;
+        }
+        if let Some(request) = cmd.window_request.take() {
+            cmd.message = windows.run(request, &mut display_handle);
+        }
+        if let Some((source, diagnostics)) = cmd.diagnostics.take() {
+            windows.files_mut().set_diagnostics(source, diagnostics);
+        }
+        if let Some(request) = cmd.request.take() {
+            cmd.message = windows.run_files(request, &mut display_handle);
+        }
+
+        // language servers
+        cmd.popup_update(windows.files_mut(), &display_handle);
+        cmd.buffers_update(windows.files());
+        let mut responses = Vec::new();
+        for language in cmd.lsp_restart.drain(..) {
+            responses.extend(lsp_handle.restart(&language));
+        }
+        for message in lsp_handle.sync(&cmd.lsp_servers, windows.files()) {
+            cmd.message = message;
+            changed = true;
+        }
+        if let Some(request) = cmd.lsp_request.take() {
+            let quiet = matches!(request, lsp::Request::Completion); // a popup opens without a server too
+            let message = lsp_handle.run(request, windows.file_mut(), (display_handle.center_x, display_handle.center_y));
+            if !message.is_empty() && !quiet {
+                cmd.message = message;
+            }
+        }
+        responses.extend(lsp_handle.poll());
+        for response in responses {
+            changed = true;
+            match response {
+                lsp::Response::Completion(y, items) => cmd.popup_add(y, items),
+                lsp::Response::Diagnostics(path, diagnostics) => {
+                    let files_handle = windows.files_mut();
+                    if let Some(i) = files_handle.position(|f| f.path.as_deref() == Some(path.as_str())) {
+                        files_handle.get_mut(i).set_diagnostics(diagnostic::SOURCE_LSP, diagnostics);
                     }
-                    _ => {}
                 }
+                lsp::Response::Edit(changes) => {
+                    let mut count = 0;
+                    for (path, mut edits) in changes {
+                        count += edits.len();
+                        let files_handle = windows.files_mut();
+                        let i = match files_handle.position(|f| f.path.as_deref() == Some(path.as_str())) {
+                            Some(i) => i,
+                            None => match files::load(Some(path.clone())) {
+                                Ok(file_handle) => files_handle.add(file_handle),
+                                Err(e) => {
+                                    cmd.message = format!("lsp_rename: {}: {}", path, e);
+                                    continue;
+                                }
+                            },
+                        };
+                        lsp::apply(files_handle.get_mut(i), &mut edits);
+                        files_handle.get_mut(i).update_syntax();
+                    }
+                    let content = &windows.file_mut().content;
+                    display_handle.center_y = display_handle.center_y.min(content.len() - 1);
+                    display_handle.center_x = display_handle.center_x.min(content[display_handle.center_y].chars().count());
+                    cmd.message = format!("lsp_rename: {} edits", count);
+                }
+                lsp::Response::Jump(path, pos) => {
+                    cmd.message = windows.run_files(files::Request::Jump(path, pos, String::new()), &mut display_handle);
+                }
+                lsp::Response::Locations(name, locations) => {
+                    let file_handle = file::File::locations(locations, name);
+                    cmd.message = windows.run_files(files::Request::Add(Box::new(file_handle), String::new()), &mut display_handle);
+                }
+                lsp::Response::Message(message) => cmd.message = message,
             }
-            event::Event::Resize(_, _) => {
-                display_handle.resize()?;
+        }
+
+        if changed {
+            windows.file_mut().update_syntax();
+            let files_handle = windows.files_mut();
+            files_handle.update_diagnostics();
+            for i in 0..files_handle.len() {
+                files_handle.get_mut(i).update_matches(cmd.highlight());
             }
-            _ => {}
+            display_handle.print(&cmd, &windows)?;
         }
-        display_handle.print(&cmd, &file_handle)?;
     }
 
     // post-processing
@@ -0,0 +1,315 @@
+//! # CONTENT
+//! center motions over file content
+
+/// # CONTENT
+/// get char at pos ('\n' at the end of row)
+/// # ARGUMENT
+/// - content: file content
+/// - pos: (x, y)
+/// # RETURN VALUE
+/// char at pos
+fn char_at(content: &[String], pos: (usize, usize)) -> char {
+    content[pos.1].chars().nth(pos.0).unwrap_or('\n')
+}
+
+/// # CONTENT
+/// get char class for word motions
+/// # ARGUMENT
+/// - c: char
+/// - big: true: WORD (only whitespace separates), false: word
+/// # RETURN VALUE
+/// - 0: whitespace
+/// - 1: word char (or any non-whitespace char for WORD)
+/// - 2: punctuation
+fn class(c: char, big: bool) -> u8 {
+    if c.is_whitespace() {
+        0
+    } else if big || c.is_alphanumeric() || c == '_' {
+        1
+    } else {
+        2
+    }
+}
+
+/// # CONTENT
+/// get next pos (the end of row counts as a pos)
+/// # ARGUMENT
+/// - content: file content
+/// - pos: (x, y)
+/// # RETURN VALUE
+/// Some((x, y)): next pos
+/// None: pos is the end of file
+fn next(content: &[String], pos: (usize, usize)) -> Option<(usize, usize)> {
+    if pos.0 < content[pos.1].chars().count() {
+        Some((pos.0 + 1, pos.1))
+    } else if pos.1 + 1 < content.len() {
+        Some((0, pos.1 + 1))
+    } else {
+        None
+    }
+}
+
+/// # CONTENT
+/// get previous pos (the end of row counts as a pos)
+/// # ARGUMENT
+/// - content: file content
+/// - pos: (x, y)
+/// # RETURN VALUE
+/// Some((x, y)): previous pos
+/// None: pos is the start of file
+fn prev(content: &[String], pos: (usize, usize)) -> Option<(usize, usize)> {
+    if pos.0 > 0 {
+        Some((pos.0 - 1, pos.1))
+    } else if pos.1 > 0 {
+        Some((content[pos.1 - 1].chars().count(), pos.1 - 1))
+    } else {
+        None
+    }
+}
+
+/// # CONTENT
+/// check if pos is an empty row (word motions stop there)
+/// # ARGUMENT
+/// - content: file content
+/// - pos: (x, y)
+/// # RETURN VALUE
+/// true: empty row
+fn is_empty_row(content: &[String], pos: (usize, usize)) -> bool {
+    content[pos.1].is_empty()
+}
+
+/// # CONTENT
+/// move to the start of next word
+/// # ARGUMENT
+/// - content: file content
+/// - pos: (x, y)
+/// - big: true: WORD, false: word
+/// # RETURN VALUE
+/// new (x, y)
+pub fn word_forward(content: &[String], pos: (usize, usize), big: bool) -> (usize, usize) {
+    let start_class = class(char_at(content, pos), big);
+    let mut pos = pos;
+    while let Some(p) = next(content, pos) {
+        pos = p;
+        if class(char_at(content, pos), big) != start_class {
+            break;
+        }
+    }
+    while class(char_at(content, pos), big) == 0 && !is_empty_row(content, pos) {
+        match next(content, pos) {
+            Some(p) => pos = p,
+            None => break,
+        }
+    }
+    pos
+}
+
+/// # CONTENT
+/// move to the end of word
+/// # ARGUMENT
+/// - content: file content
+/// - pos: (x, y)
+/// - big: true: WORD, false: word
+/// # RETURN VALUE
+/// new (x, y)
+pub fn word_end(content: &[String], pos: (usize, usize), big: bool) -> (usize, usize) {
+    let mut pos = match next(content, pos) {
+        Some(p) => p,
+        None => return pos,
+    };
+    while class(char_at(content, pos), big) == 0 {
+        match next(content, pos) {
+            Some(p) => pos = p,
+            None => return pos,
+        }
+    }
+    let word_class = class(char_at(content, pos), big);
+    while let Some(p) = next(content, pos) {
+        if class(char_at(content, p), big) != word_class {
+            break;
+        }
+        pos = p;
+    }
+    pos
+}
+
+/// # CONTENT
+/// move to the start of previous word
+/// # ARGUMENT
+/// - content: file content
+/// - pos: (x, y)
+/// - big: true: WORD, false: word
+/// # RETURN VALUE
+/// new (x, y)
+pub fn word_backward(content: &[String], pos: (usize, usize), big: bool) -> (usize, usize) {
+    let mut pos = match prev(content, pos) {
+        Some(p) => p,
+        None => return pos,
+    };
+    while class(char_at(content, pos), big) == 0 && !is_empty_row(content, pos) {
+        match prev(content, pos) {
+            Some(p) => pos = p,
+            None => return pos,
+        }
+    }
+    let word_class = class(char_at(content, pos), big);
+    while let Some(p) = prev(content, pos) {
+        if class(char_at(content, p), big) != word_class || p.1 != pos.1 {
+            break;
+        }
+        pos = p;
+    }
+    pos
+}
+
+/// # CONTENT
+/// move to the next/previous blank row after the current paragraph
+/// # ARGUMENT
+/// - content: file content
+/// - y: row index
+/// - forward: true: next, false: previous
+/// # RETURN VALUE
+/// new (x, y)
+pub fn paragraph(content: &[String], y: usize, forward: bool) -> (usize, usize) {
+    let is_blank = |y: usize| content[y].trim().is_empty();
+    let step = |y: usize| if forward { (y + 1 < content.len()).then_some(y + 1) } else { y.checked_sub(1) };
+    let mut y = y;
+    while let Some(n) = step(y) {
+        y = n;
+        if !is_blank(y) {
+            break;
+        }
+    }
+    while let Some(n) = step(y) {
+        y = n;
+        if is_blank(y) {
+            return (0, y);
+        }
+    }
+    if forward {
+        (content[y].chars().count(), y)
+    } else {
+        (0, y)
+    }
+}
+
+/// # CONTENT
+/// move to the bracket matching the one at or after pos in the row
+/// # ARGUMENT
+/// - content: file content
+/// - pos: (x, y)
+/// # RETURN VALUE
+/// Some((x, y)): matching bracket
+/// None: no bracket or no match
+pub fn matching_bracket(content: &[String], pos: (usize, usize)) -> Option<(usize, usize)> {
+    const PAIRS: [(char, char); 3] = [('(', ')'), ('[', ']'), ('{', '}')];
+    let x = content[pos.1].chars().skip(pos.0).position(|c| PAIRS.iter().any(|&(o, e)| c == o || c == e))? + pos.0;
+    let bracket = char_at(content, (x, pos.1));
+    let (open, close, forward) = PAIRS.iter().find_map(|&(o, e)| {
+        if bracket == o {
+            Some((o, e, true))
+        } else if bracket == e {
+            Some((o, e, false))
+        } else {
+            None
+        }
+    })?;
+    let mut depth = 0usize;
+    let mut pos = (x, pos.1);
+    loop {
+        let c = char_at(content, pos);
+        if c == open {
+            depth = if forward { depth + 1 } else { depth - 1 };
+        } else if c == close {
+            depth = if forward { depth - 1 } else { depth + 1 };
+        }
+        if depth == 0 {
+            return Some(pos);
+        }
+        pos = if forward { next(content, pos)? } else { prev(content, pos)? };
+    }
+}
+
+/// # CONTENT
+/// move to the first non-blank char of row
+/// # ARGUMENT
+/// - content: file content
+/// - y: row index
+/// # RETURN VALUE
+/// new (x, y)
+pub fn first_non_blank(content: &[String], y: usize) -> (usize, usize) {
+    let x = content[y].chars().position(|c| !c.is_whitespace()).unwrap_or(content[y].chars().count());
+    (x, y)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn content(rows: &[&str]) -> Vec<String> {
+        rows.iter().map(|row| row.to_string()).collect()
+    }
+
+    #[test]
+    fn word_forward_stops() {
+        let content = content(&["foo.bar baz", "", "  qux"]);
+        assert_eq!(word_forward(&content, (0, 0), false), (3, 0));
+        assert_eq!(word_forward(&content, (3, 0), false), (4, 0));
+        assert_eq!(word_forward(&content, (0, 0), true), (8, 0));
+        assert_eq!(word_forward(&content, (8, 0), false), (0, 1));
+        assert_eq!(word_forward(&content, (0, 1), false), (2, 2));
+        assert_eq!(word_forward(&content, (2, 2), false), (5, 2));
+    }
+
+    #[test]
+    fn word_end_stops() {
+        let content = content(&["foo.bar baz", "  x"]);
+        assert_eq!(word_end(&content, (0, 0), false), (2, 0));
+        assert_eq!(word_end(&content, (2, 0), false), (3, 0));
+        assert_eq!(word_end(&content, (0, 0), true), (6, 0));
+        assert_eq!(word_end(&content, (10, 0), false), (2, 1));
+        assert_eq!(word_end(&content, (2, 1), false), (3, 1));
+    }
+
+    #[test]
+    fn word_backward_stops() {
+        let content = content(&["foo.bar baz", "", "  qux"]);
+        assert_eq!(word_backward(&content, (8, 0), false), (4, 0));
+        assert_eq!(word_backward(&content, (4, 0), false), (3, 0));
+        assert_eq!(word_backward(&content, (8, 0), true), (0, 0));
+        assert_eq!(word_backward(&content, (2, 2), false), (0, 1));
+        assert_eq!(word_backward(&content, (0, 1), false), (8, 0));
+        assert_eq!(word_backward(&content, (0, 0), false), (0, 0));
+    }
+
+    #[test]
+    fn paragraph_stops() {
+        let content = content(&["a", "b", "", "c", "d", " ", "e"]);
+        assert_eq!(paragraph(&content, 0, true), (0, 2));
+        assert_eq!(paragraph(&content, 2, true), (0, 5));
+        assert_eq!(paragraph(&content, 5, true), (1, 6));
+        assert_eq!(paragraph(&content, 6, false), (0, 2));
+        assert_eq!(paragraph(&content, 1, false), (0, 0));
+    }
+
+    #[test]
+    fn matching_brackets() {
+        let content = content(&["f(a[b](c)) {", "  x }", ")"]);
+        assert_eq!(matching_bracket(&content, (0, 0)), Some((9, 0)));
+        assert_eq!(matching_bracket(&content, (3, 0)), Some((5, 0)));
+        assert_eq!(matching_bracket(&content, (9, 0)), Some((1, 0)));
+        assert_eq!(matching_bracket(&content, (10, 0)), Some((4, 1)));
+        assert_eq!(matching_bracket(&content, (4, 1)), Some((11, 0)));
+        assert_eq!(matching_bracket(&content, (0, 2)), None);
+        assert_eq!(matching_bracket(&["(a".to_string()], (0, 0)), None);
+        assert_eq!(matching_bracket(&["(a) b".to_string()], (3, 0)), None);
+    }
+
+    #[test]
+    fn first_non_blank_x() {
+        let content = content(&["  \tab", "   ", ""]);
+        assert_eq!(first_non_blank(&content, 0), (3, 0));
+        assert_eq!(first_non_blank(&content, 1), (3, 1));
+        assert_eq!(first_non_blank(&content, 2), (0, 2));
+    }
+}
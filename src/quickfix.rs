@@ -0,0 +1,239 @@
+//! # CONTENT
+//! quickfix list of diagnostics in build output
+
+use {
+    std:: {
+        io,
+        path,
+        process,
+        sync::mpsc,
+        thread,
+    },
+    crate:: {
+        location,
+        shell,
+    },
+    regex:: {
+        Regex,
+    },
+};
+
+/// # CONTENT
+/// build program run by ":make" until ":make_program" sets another one
+pub const DEFAULT_PROGRAM: &str = "cargo build";
+
+/// # CONTENT
+/// generic diagnostic row of "file:line:col: message" or "file:line: message" (the file may start with a drive as in "C:\src")
+const GENERIC: &str = r"^((?:[A-Za-z]:)?[^\s:][^:]*):(\d+):(?:(\d+):)?\s*(.+)$";
+
+/// # CONTENT
+/// quickfix list
+/// # FIELD
+/// - locations: parsed diagnostics
+/// - index: index of the current diagnostic
+pub struct Quickfix {
+    pub locations: Vec<location::Location>,
+    index: usize,
+}
+
+/// # CONTENT
+/// build or linter program running in a thread
+/// # FIELD
+/// - source: producer of the output (diagnostic::SOURCE_MAKE or diagnostic::SOURCE_LINT)
+/// - program: shell cmd
+/// - receiver: output sent when the program exits
+pub struct Job {
+    pub source: &'static str,
+    pub program: String,
+    receiver: mpsc::Receiver<io::Result<String>>,
+}
+
+/// # CONTENT
+/// run a build program in a shell
+/// # ARGUMENT
+/// - program: shell cmd
+/// # RETURN VALUE
+/// Ok(output): stdout followed by stderr
+/// Err(...): spawn error
+pub fn run(program: &str) -> io::Result<String> {
+    let output = shell::command(program)
+        .stdin(process::Stdio::null())
+        .output()?;
+    Ok(format!("{}{}", String::from_utf8_lossy(&output.stdout), String::from_utf8_lossy(&output.stderr)))
+}
+
+/// # CONTENT
+/// parse diagnostics of build output
+/// - rustc: "error[E0000]: message" followed by "--> file:line:col"
+/// - gcc and generic: "file:line:col: message" or "file:line: message" (of an existing file)
+/// # ARGUMENT
+/// - output: build output
+/// # RETURN VALUE
+/// diagnostic locations in output order
+pub fn parse(output: &str) -> Vec<location::Location> {
+    let header = Regex::new(r"^(error|warning)(\[\w+\])?: ").unwrap();
+    let arrow = Regex::new(r"^\s*--> (.+):(\d+):(\d+)$").unwrap();
+    let generic = Regex::new(GENERIC).unwrap();
+    let mut locations = Vec::new();
+    let mut message: Option<String> = None;
+    for row in output.lines() {
+        let row = row.trim_end();
+        if header.is_match(row) {
+            message = Some(row.to_string());
+        } else if let Some(c) = arrow.captures(row) {
+            if let Some(text) = message.take() {
+                locations.push(location::Location {
+                    path: c[1].to_string(),
+                    y: c[2].parse::<usize>().unwrap_or(1).saturating_sub(1),
+                    x: c[3].parse::<usize>().unwrap_or(1).saturating_sub(1),
+                    text,
+                });
+            }
+        } else if let Some(c) = generic.captures(row)
+            && path::Path::new(&c[1]).is_file() {
+            locations.push(location::Location {
+                path: c[1].to_string(),
+                y: c[2].parse::<usize>().unwrap_or(1).saturating_sub(1),
+                x: c.get(3).and_then(|m| m.as_str().parse::<usize>().ok()).unwrap_or(1).saturating_sub(1),
+                text: c[4].to_string(),
+            });
+        }
+    }
+    locations
+}
+
+impl Job {
+    /// # CONTENT
+    /// start a build program in a thread
+    /// # ARGUMENT
+    /// - source: producer of the output
+    /// - program: shell cmd
+    /// # RETURN VALUE
+    /// running Job
+    pub fn spawn(source: &'static str, program: &str) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let command = program.to_string();
+        thread::spawn(move || {
+            let _ = sender.send(run(&command));
+        });
+        Self {
+            source,
+            program: program.to_string(),
+            receiver,
+        }
+    }
+
+    /// # CONTENT
+    /// get the output if the program exited (without waiting)
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// Some(Ok(output)): stdout followed by stderr
+    /// Some(Err(...)): spawn error or lost thread
+    /// None: still running
+    pub fn poll(&self) -> Option<io::Result<String>> {
+        match self.receiver.try_recv() {
+            Ok(result) => Some(result),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => Some(Err(io::Error::other("program thread exited without output"))),
+        }
+    }
+}
+
+impl Quickfix {
+    /// # CONTENT
+    /// init Quickfix (the first diagnostic is current)
+    /// # ARGUMENT
+    /// - locations: parsed diagnostics
+    /// # RETURN VALUE
+    /// inited Quickfix
+    pub fn new(locations: Vec<location::Location>) -> Self {
+        Self {
+            locations,
+            index: 0,
+        }
+    }
+
+    /// # CONTENT
+    /// move to a diagnostic
+    /// # ARGUMENT
+    /// - step: diagnostics moved forward (negative for backward; 0 for the current one)
+    /// # RETURN VALUE
+    /// Some((location, message)): moved diagnostic and "(i of n) text"
+    /// None: no diagnostic in the direction
+    pub fn step(&mut self, step: isize) -> Option<(&location::Location, String)> {
+        let index = self.index.checked_add_signed(step).filter(|&i| i < self.locations.len())?;
+        self.index = index;
+        let location = &self.locations[index];
+        Some((location, format!("({} of {}) {}", index + 1, self.locations.len(), location.text)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parsed(output: &str) -> Vec<(String, usize, usize, String)> {
+        parse(output).into_iter().map(|l| (l.path, l.y, l.x, l.text)).collect()
+    }
+
+    #[test]
+    fn parse_rustc() {
+        let output = "error[E0308]: mismatched types\n  --> src/main.rs:10:5\n   |\nwarning: unused variable: `x`\n --> src/cmd.rs:2:9\n";
+        assert_eq!(parsed(output), [
+            ("src/main.rs".to_string(), 9, 4, "error[E0308]: mismatched types".to_string()),
+            ("src/cmd.rs".to_string(), 1, 8, "warning: unused variable: `x`".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn parse_header_without_arrow() {
+        let output = "error: could not compile `center`\nwarning: build failed\n   --> src/main.rs:3:1\n";
+        assert_eq!(parsed(output), [("src/main.rs".to_string(), 2, 0, "warning: build failed".to_string())]);
+        assert!(parsed("error: aborting\n").is_empty());
+        assert!(parsed("  --> src/main.rs:3:1\n").is_empty());
+    }
+
+    #[test]
+    fn parse_generic() {
+        assert_eq!(parsed("src/main.rs:12:3: error: x\n"), [("src/main.rs".to_string(), 11, 2, "error: x".to_string())]);
+        assert_eq!(parsed("src/main.rs:12: warning: y\n"), [("src/main.rs".to_string(), 11, 0, "warning: y".to_string())]);
+        assert!(parsed("no/such/file.rs:1:1: error: z\n").is_empty());
+        assert!(parsed("src:1:1: error: a directory\n").is_empty());
+    }
+
+    #[test]
+    fn generic_drive_path() {
+        let generic = Regex::new(GENERIC).unwrap();
+        let c = generic.captures(r"C:\src\main.rs:10:5: error: x").unwrap();
+        assert_eq!((&c[1], &c[2], &c[3], &c[4]), (r"C:\src\main.rs", "10", "5", "error: x"));
+        let c = generic.captures("src/main.rs:10: x").unwrap();
+        assert_eq!((&c[1], &c[2], c.get(3).is_none(), &c[4]), ("src/main.rs", "10", true, "x"));
+    }
+
+    #[test]
+    fn step_ends() {
+        let location = |y| location::Location { path: "a".to_string(), y, x: 0, text: format!("t{}", y) };
+        let mut quickfix = Quickfix::new(vec![location(0), location(1)]);
+        assert!(quickfix.step(-1).is_none());
+        assert_eq!(quickfix.step(0).map(|(l, m)| (l.y, m)), Some((0, "(1 of 2) t0".to_string())));
+        assert_eq!(quickfix.step(1).map(|(l, m)| (l.y, m)), Some((1, "(2 of 2) t1".to_string())));
+        assert!(quickfix.step(1).is_none());
+        assert_eq!(quickfix.step(0).map(|(l, _)| l.y), Some(1));
+        assert_eq!(quickfix.step(-1).map(|(l, _)| l.y), Some(0));
+        assert!(Quickfix::new(Vec::new()).step(0).is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn job_output() {
+        let job = Job::spawn("make", "echo out && echo err >&2");
+        let output = loop {
+            if let Some(output) = job.poll() {
+                break output.unwrap();
+            }
+            thread::sleep(std::time::Duration::from_millis(10));
+        };
+        assert_eq!(output.replace("\r\n", "\n"), "out\nerr\n");
+    }
+}
@@ -0,0 +1,325 @@
+//! # CONTENT
+//! yank/put registers and the system clipboard
+
+use {
+    std:: {
+        env,
+        io:: {
+            self,
+            Write,
+        },
+        process,
+    },
+    crate:: {
+        visual,
+    },
+};
+
+/// # CONTENT
+/// base64 alphabet of OSC 52 payloads
+const BASE64: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// # CONTENT
+/// clipboard tools of (env var telling the tool works, copy cmd, paste cmd)
+const TOOLS: &[(&str, &[&str], &[&str])] = &[
+    ("WAYLAND_DISPLAY", &["wl-copy"], &["wl-paste", "--no-newline"]),
+    ("DISPLAY", &["xclip", "-selection", "clipboard"], &["xclip", "-selection", "clipboard", "-o"]),
+];
+
+/// # CONTENT
+/// register content
+/// # FIELD
+/// - kind: selection kind the rows came from (decides how they are put)
+/// - rows: text rows
+#[derive(Clone)]
+pub struct Register {
+    pub kind: visual::Kind,
+    pub rows: Vec<String>,
+}
+
+impl Register {
+    /// # CONTENT
+    /// get Register from clipboard text
+    /// # ARGUMENT
+    /// - text: clipboard text (a trailing newline makes whole rows)
+    /// # RETURN VALUE
+    /// Register
+    fn from_text(text: &str) -> Self {
+        match text.strip_suffix('\n') {
+            Some(rows) => Self { kind: visual::Kind::Line, rows: rows.split('\n').map(|row| row.to_string()).collect() },
+            None => Self { kind: visual::Kind::Char, rows: text.split('\n').map(|row| row.to_string()).collect() },
+        }
+    }
+
+    /// # CONTENT
+    /// get clipboard text of Register
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// rows joined by newlines (whole rows end with a newline)
+    fn text(&self) -> String {
+        let mut text = self.rows.join("\n");
+        if self.kind == visual::Kind::Line {
+            text.push('\n');
+        }
+        text
+    }
+}
+
+/// # CONTENT
+/// check a register name
+/// # ARGUMENT
+/// - name: register name
+/// # RETURN VALUE
+/// true: '"' (unnamed), 'a'-'z' ('A'-'Z' append), '+' or '*' (clipboard)
+pub fn valid(name: char) -> bool {
+    name == '"' || name == '+' || name == '*' || name.is_ascii_alphabetic()
+}
+
+/// # CONTENT
+/// encode bytes in base64
+/// # ARGUMENT
+/// - bytes: encoded bytes
+/// # RETURN VALUE
+/// base64 text
+fn base64(bytes: &[u8]) -> String {
+    let mut encoded = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk.iter().enumerate().fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            encoded.push(if i <= chunk.len() { BASE64[(n >> (18 - 6 * i) & 0x3F) as usize] as char } else { '=' });
+        }
+    }
+    encoded
+}
+
+/// # CONTENT
+/// get the OSC 52 sequence setting the clipboard
+/// # ARGUMENT
+/// - text: copied text
+/// - tmux: also wrap the sequence for tmux
+/// # RETURN VALUE
+/// escape sequence
+fn osc52(text: &str, tmux: bool) -> String {
+    let mut osc = format!("\x1b]52;c;{}\x07", base64(text.as_bytes()));
+    if tmux {
+        // tmux takes the raw sequence with set-clipboard on, and the wrapped one with allow-passthrough on
+        osc += &format!("\x1bPtmux;{}\x1b\\", osc.replace('\x1b', "\x1b\x1b"));
+    }
+    osc
+}
+
+/// # CONTENT
+/// copy text to the system clipboard
+/// (OSC 52 through the terminal, also wrapped for tmux, and a clipboard tool when present)
+/// # ARGUMENT
+/// - text: copied text
+/// # RETURN VALUE
+/// none
+fn copy(text: &str) {
+    let osc = osc52(text, env::var("TMUX").is_ok());
+    let mut stdout = io::stdout();
+    let _ = stdout.write_all(osc.as_bytes()).and_then(|_| stdout.flush());
+    for (_, cmd, _) in TOOLS.iter().filter(|(var, _, _)| env::var(var).is_ok()) {
+        let child = process::Command::new(cmd[0])
+            .args(&cmd[1..])
+            .stdin(process::Stdio::piped())
+            .stdout(process::Stdio::null())
+            .stderr(process::Stdio::null())
+            .spawn();
+        if let Ok(mut child) = child {
+            if let Some(mut stdin) = child.stdin.take() {
+                let _ = stdin.write_all(text.as_bytes());
+            }
+            let _ = child.wait();
+            return;
+        }
+    }
+}
+
+/// # CONTENT
+/// paste text from the system clipboard through a clipboard tool
+/// # ARGUMENT
+/// none
+/// # RETURN VALUE
+/// Some(text): clipboard text
+/// None: no clipboard tool (OSC 52 paste is not read back)
+fn paste() -> Option<String> {
+    TOOLS.iter()
+        .filter(|(var, _, _)| env::var(var).is_ok())
+        .find_map(|(_, _, cmd)| {
+            let output = process::Command::new(cmd[0]).args(&cmd[1..]).stderr(process::Stdio::null()).output().ok()?;
+            if output.status.success() { String::from_utf8(output.stdout).ok() } else { None }
+        })
+}
+
+/// # CONTENT
+/// registers
+/// # FIELD
+/// - registers: (name, content) of set registers ('"' is the last yanked or deleted text)
+pub struct Registers {
+    registers: Vec<(char, Register)>,
+}
+
+impl Registers {
+    /// # CONTENT
+    /// init Registers without content
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// inited Registers
+    pub fn new() -> Self {
+        Self {
+            registers: Vec::new(),
+        }
+    }
+
+    /// # CONTENT
+    /// store content in '"' and a register
+    /// # ARGUMENT
+    /// - name: register name (None for '"' only)
+    /// - register: stored content
+    /// # RETURN VALUE
+    /// none
+    pub fn set(&mut self, name: Option<char>, register: Register) {
+        let register = match name {
+            Some(name) if name.is_ascii_uppercase() => {
+                let name = name.to_ascii_lowercase();
+                let mut appended = self.get_stored(name).unwrap_or(Register { kind: register.kind, rows: Vec::new() });
+                if appended.kind == visual::Kind::Char && register.kind == visual::Kind::Char && !appended.rows.is_empty() {
+                    let mut rows = register.rows.into_iter();
+                    if let (Some(last), Some(first)) = (appended.rows.last_mut(), rows.next()) {
+                        last.push_str(&first);
+                    }
+                    appended.rows.extend(rows);
+                } else {
+                    appended.kind = if appended.kind == register.kind { register.kind } else { visual::Kind::Line };
+                    appended.rows.extend(register.rows);
+                }
+                self.store(name, appended.clone());
+                appended
+            }
+            Some(name @ ('+' | '*')) => {
+                copy(&register.text());
+                self.store(name, register.clone());
+                register
+            }
+            Some(name) if name != '"' => {
+                self.store(name, register.clone());
+                register
+            }
+            _ => register,
+        };
+        self.store('"', register);
+    }
+
+    /// # CONTENT
+    /// get register content
+    /// # ARGUMENT
+    /// - name: register name ('+' and '*' read the system clipboard when a clipboard tool is present)
+    /// # RETURN VALUE
+    /// Some(register): register content
+    /// None: empty register
+    pub fn get(&self, name: char) -> Option<Register> {
+        match name {
+            '+' | '*' => paste().map(|text| Register::from_text(&text)).or_else(|| self.get_stored(name)),
+            _ => self.get_stored(name.to_ascii_lowercase()),
+        }
+    }
+
+    /// # CONTENT
+    /// get printable registers
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// rows of "name  kind  text" sorted by name
+    pub fn list(&self) -> Vec<String> {
+        let mut registers: Vec<_> = self.registers.iter().collect();
+        registers.sort_by_key(|(name, _)| *name);
+        registers.into_iter().map(|(name, register)| format!("\"{}  {:<5} {}", name, register.kind.name(), register.rows.join("\\n"))).collect()
+    }
+
+    /// # CONTENT
+    /// get stored register content
+    /// # ARGUMENT
+    /// - name: register name
+    /// # RETURN VALUE
+    /// Some(register): stored content
+    /// None: empty register
+    fn get_stored(&self, name: char) -> Option<Register> {
+        self.registers.iter().find(|(n, _)| *n == name).map(|(_, register)| register.clone())
+    }
+
+    /// # CONTENT
+    /// store register content
+    /// # ARGUMENT
+    /// - name: register name
+    /// - register: stored content
+    /// # RETURN VALUE
+    /// none
+    fn store(&mut self, name: char, register: Register) {
+        self.registers.retain(|(n, _)| *n != name);
+        self.registers.push((name, register));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn register(kind: visual::Kind, rows: &[&str]) -> Register {
+        Register { kind, rows: rows.iter().map(|row| row.to_string()).collect() }
+    }
+
+    #[test]
+    fn base64_padding() {
+        let encoded: Vec<String> = ["", "f", "fo", "foo", "foob", "fooba", "foobar"].iter().map(|s| base64(s.as_bytes())).collect();
+        assert_eq!(encoded, ["", "Zg==", "Zm8=", "Zm9v", "Zm9vYg==", "Zm9vYmE=", "Zm9vYmFy"]);
+        assert_eq!(base64("é\n".as_bytes()), "w6kK");
+        assert_eq!(base64(&[0xFF, 0xFE]), "//4=");
+    }
+
+    #[test]
+    fn osc52_tmux_wrap() {
+        assert_eq!(osc52("hi", false), "\x1b]52;c;aGk=\x07");
+        assert_eq!(osc52("hi", true), "\x1b]52;c;aGk=\x07\x1bPtmux;\x1b\x1b]52;c;aGk=\x07\x1b\\");
+    }
+
+    #[test]
+    fn text_kind() {
+        let line = Register::from_text("a\nb\n");
+        assert!(line.kind == visual::Kind::Line);
+        assert_eq!(line.rows, ["a", "b"]);
+        assert_eq!(line.text(), "a\nb\n");
+        let char = Register::from_text("a\nb");
+        assert!(char.kind == visual::Kind::Char);
+        assert_eq!(char.rows, ["a", "b"]);
+        assert_eq!(char.text(), "a\nb");
+        let empty = Register::from_text("");
+        assert!(empty.kind == visual::Kind::Char);
+        assert_eq!(empty.rows, [""]);
+        let newline = Register::from_text("\n");
+        assert!(newline.kind == visual::Kind::Line);
+        assert_eq!(newline.rows, [""]);
+    }
+
+    #[test]
+    fn uppercase_appends() {
+        let mut registers = Registers::new();
+        registers.set(Some('a'), register(visual::Kind::Char, &["ab"]));
+        registers.set(Some('A'), register(visual::Kind::Char, &["cd", "ef"]));
+        let appended = registers.get('a').unwrap();
+        assert!(appended.kind == visual::Kind::Char);
+        assert_eq!(appended.rows, ["abcd", "ef"]);
+        assert_eq!(registers.get('"').unwrap().rows, ["abcd", "ef"]);
+        registers.set(Some('A'), register(visual::Kind::Line, &["x"]));
+        let appended = registers.get('A').unwrap();
+        assert!(appended.kind == visual::Kind::Line);
+        assert_eq!(appended.rows, ["abcd", "ef", "x"]);
+        registers.set(Some('B'), register(visual::Kind::Char, &["y"]));
+        registers.set(None, register(visual::Kind::Line, &["z"]));
+        assert_eq!(registers.list(), ["\"\"  line  z", "\"a  line  abcd\\nef\\nx", "\"b  char  y"]);
+        assert!(registers.get('c').is_none());
+        assert!(valid('A') && valid('"') && valid('+') && !valid('1') && !valid('-'));
+    }
+}
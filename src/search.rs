@@ -0,0 +1,86 @@
+//! # CONTENT
+//! text search over file content
+
+use {
+    std:: {
+        ops,
+    },
+};
+
+/// # CONTENT
+/// compare chars (case-insensitive unless the pattern has an uppercase char)
+/// # ARGUMENT
+/// - a: char of row
+/// - b: char of pattern
+/// - ignore_case: compare ignoring case
+/// # RETURN VALUE
+/// true: same char
+fn eq(a: char, b: char, ignore_case: bool) -> bool {
+    if ignore_case {
+        a.to_lowercase().eq(b.to_lowercase())
+    } else {
+        a == b
+    }
+}
+
+/// # CONTENT
+/// find matches of a pattern in a row
+/// # ARGUMENT
+/// - row: row content
+/// - pattern: searched text (smartcase)
+/// # RETURN VALUE
+/// char ranges of matches
+pub fn find(row: &str, pattern: &str) -> Vec<ops::Range<usize>> {
+    let pattern: Vec<char> = pattern.chars().collect();
+    if pattern.is_empty() {
+        return Vec::new();
+    }
+    let ignore_case = !pattern.iter().any(|c| c.is_uppercase());
+    let row: Vec<char> = row.chars().collect();
+    let mut matches = Vec::new();
+    let mut x = 0;
+    while x + pattern.len() <= row.len() {
+        if row[x..x + pattern.len()].iter().zip(&pattern).all(|(&a, &b)| eq(a, b, ignore_case)) {
+            matches.push(x..x + pattern.len());
+            x += pattern.len();
+        } else {
+            x += 1;
+        }
+    }
+    matches
+}
+
+/// # CONTENT
+/// find matches of a pattern in content
+/// # ARGUMENT
+/// - content: file content
+/// - pattern: searched text (smartcase)
+/// # RETURN VALUE
+/// match start pos (x, y) in order
+pub fn find_all(content: &[String], pattern: &str) -> Vec<(usize, usize)> {
+    content.iter().enumerate().flat_map(|(y, row)| {
+        find(row, pattern).into_iter().map(move |r| (r.start, y))
+    }).collect()
+}
+
+/// # CONTENT
+/// find the nearest match from pos (wraps around the file)
+/// # ARGUMENT
+/// - content: file content
+/// - pattern: searched text (smartcase)
+/// - pos: (x, y) searched from
+/// - forward: true: after pos, false: before pos
+/// - inclusive: true: a match at pos counts
+/// # RETURN VALUE
+/// Some((x, y)): match start pos
+/// None: no match
+pub fn next(content: &[String], pattern: &str, pos: (usize, usize), forward: bool, inclusive: bool) -> Option<(usize, usize)> {
+    let matches = find_all(content, pattern);
+    let key = |&(x, y): &(usize, usize)| (y, x);
+    let pos_key = key(&pos);
+    if forward {
+        matches.iter().find(|m| if inclusive { key(m) >= pos_key } else { key(m) > pos_key }).or(matches.first()).copied()
+    } else {
+        matches.iter().rev().find(|m| if inclusive { key(m) <= pos_key } else { key(m) < pos_key }).or(matches.last()).copied()
+    }
+}
@@ -0,0 +1,54 @@
+//! # CONTENT
+//! shell cmds of external programs (sh, or cmd on Windows)
+
+use {
+    std:: {
+        process,
+    },
+};
+
+/// # CONTENT
+/// get a process running a shell cmd
+/// # ARGUMENT
+/// - program: shell cmd
+/// # RETURN VALUE
+/// "sh -c program" ("cmd /C program" on Windows)
+pub fn command(program: &str) -> process::Command {
+    let mut command = if cfg!(windows) { process::Command::new("cmd") } else { process::Command::new("sh") };
+    command.args([if cfg!(windows) { "/C" } else { "-c" }, program]);
+    command
+}
+
+/// # CONTENT
+/// quote an arg of a shell cmd
+/// # ARGUMENT
+/// - arg: arg (as a file path)
+/// # RETURN VALUE
+/// single-quoted arg ("'" escaped as '\''), or double-quoted arg on Windows
+pub fn quote(arg: &str) -> String {
+    if cfg!(windows) {
+        format!("\"{}\"", arg)
+    } else {
+        format!("'{}'", arg.replace('\'', "'\\''"))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_runs_shell() {
+        let output = command("printf '%s' \"$0\" && exit 3").output().unwrap();
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), "sh");
+        assert_eq!(output.status.code(), Some(3));
+    }
+
+    #[test]
+    fn quote_path() {
+        assert_eq!(quote("a b.rs"), "'a b.rs'");
+        let path = "it's $HOME; `x`.rs";
+        let output = command(&format!("printf '%s' {}", quote(path))).output().unwrap();
+        assert_eq!(String::from_utf8(output.stdout).unwrap(), path);
+    }
+}
@@ -0,0 +1,317 @@
+//! # CONTENT
+//! regex search-and-replace (":s/pattern/replacement/flags")
+
+use {
+    regex:: {
+        Regex,
+        RegexBuilder,
+    },
+};
+
+/// # CONTENT
+/// parsed substitute cmd
+/// # FIELD
+/// - regex: searched pattern
+/// - replacement: replacement ("$1" or "\1" for capture groups)
+/// - global: replace every match in a row (otherwise the first one)
+/// - confirm: ask before each replacement
+/// - top: first row index of the range
+/// - bottom: last row index of the range
+pub struct Substitute {
+    regex: Regex,
+    replacement: String,
+    global: bool,
+    pub confirm: bool,
+    pub top: usize,
+    bottom: usize,
+}
+
+/// # CONTENT
+/// split text at unescaped delimiters
+/// # ARGUMENT
+/// - s: text after the first delimiter
+/// - delimiter: delimiter char
+/// # RETURN VALUE
+/// parts (escaped delimiters are unescaped)
+fn split(s: &str, delimiter: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some(n) if n == delimiter => parts.last_mut().unwrap().push(n),
+                Some(n) => {
+                    parts.last_mut().unwrap().push(c);
+                    parts.last_mut().unwrap().push(n);
+                }
+                None => parts.last_mut().unwrap().push(c),
+            }
+        } else if c == delimiter {
+            parts.push(String::new());
+        } else {
+            parts.last_mut().unwrap().push(c);
+        }
+    }
+    parts
+}
+
+/// # CONTENT
+/// parse a row address of a range
+/// # ARGUMENT
+/// - s: address ("." current row, "$" last row, or row number)
+/// - center_y: center pos y
+/// - len: row count
+/// # RETURN VALUE
+/// Ok(row index)
+/// Err(message): invalid address
+fn address(s: &str, center_y: usize, len: usize) -> Result<usize, String> {
+    match s {
+        "" | "." => Ok(center_y),
+        "$" => Ok(len - 1),
+        _ => match s.parse::<usize>() {
+            Ok(n) if n >= 1 && n <= len => Ok(n - 1),
+            _ => Err(format!("invalid address: {}", s)),
+        },
+    }
+}
+
+impl Substitute {
+    /// # CONTENT
+    /// parse a substitute cmd
+    /// # ARGUMENT
+    /// - cmd: "[range]s/pattern/replacement/[flags]" (range: "%", "N", "N,M"; flags: g, i, w, c)
+    /// - center_y: center pos y
+    /// - len: row count
+    /// # RETURN VALUE
+    /// Ok(Substitute)
+    /// Err(message): parse error
+    pub fn parse(cmd: &str, center_y: usize, len: usize) -> Result<Self, String> {
+        let s_pos = cmd.find('s').ok_or("not a substitute cmd")?;
+        let (range, rest) = (&cmd[..s_pos], &cmd[s_pos + 1..]);
+        let (top, bottom) = match range {
+            "%" => (0, len - 1),
+            _ => match range.split_once(',') {
+                Some((a, b)) => (address(a, center_y, len)?, address(b, center_y, len)?),
+                None => (address(range, center_y, len)?, address(range, center_y, len)?),
+            },
+        };
+        if top > bottom {
+            return Err("backwards range".to_string());
+        }
+        let delimiter = rest.chars().next().ok_or("missing pattern")?;
+        if delimiter.is_alphanumeric() || delimiter == '\\' {
+            return Err(format!("invalid delimiter: {}", delimiter));
+        }
+        let parts = split(&rest[delimiter.len_utf8()..], delimiter);
+        if parts.len() > 3 {
+            return Err("trailing characters".to_string());
+        }
+        let pattern = parts[0].clone();
+        let replacement = parts.get(1).cloned().unwrap_or_default();
+        let flags = parts.get(2).cloned().unwrap_or_default();
+        if pattern.is_empty() {
+            return Err("missing pattern".to_string());
+        }
+        if let Some(f) = flags.chars().find(|f| !"cgiw".contains(*f)) {
+            return Err(format!("invalid flag: {}", f));
+        }
+        let pattern = if flags.contains('w') { format!(r"\b(?:{})\b", pattern) } else { pattern };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(flags.contains('i'))
+            .build()
+            .map_err(|e| e.to_string().lines().last().unwrap_or_default().to_string())?;
+
+        // "\1" style groups -> "${1}"
+        let mut expanded = String::new();
+        let mut chars = replacement.chars().peekable();
+        while let Some(c) = chars.next() {
+            match (c, chars.peek()) {
+                ('\\', Some(n)) if n.is_ascii_digit() => {
+                    expanded.push_str(&format!("${{{}}}", n));
+                    chars.next();
+                }
+                ('\\', Some('\\')) => {
+                    expanded.push('\\');
+                    chars.next();
+                }
+                _ => expanded.push(c),
+            }
+        }
+
+        Ok(Self {
+            regex,
+            replacement: expanded,
+            global: flags.contains('g'),
+            confirm: flags.contains('c'),
+            top,
+            bottom,
+        })
+    }
+
+    /// # CONTENT
+    /// replace every match in the range
+    /// # ARGUMENT
+    /// - content: file content
+    /// # RETURN VALUE
+    /// replaced count
+    pub fn run(&self, content: &mut [String]) -> usize {
+        let mut count = 0;
+        for row in content[self.top..=self.bottom].iter_mut() {
+            let n = if self.global { self.regex.find_iter(row).count() } else { self.regex.find(row).iter().count() };
+            if n > 0 {
+                *row = if self.global {
+                    self.regex.replace_all(row, self.replacement.as_str()).into_owned()
+                } else {
+                    self.regex.replace(row, self.replacement.as_str()).into_owned()
+                };
+                count += n;
+            }
+        }
+        count
+    }
+
+    /// # CONTENT
+    /// find the next match in the range
+    /// # ARGUMENT
+    /// - content: file content
+    /// - pos: (byte x, y) searched from
+    /// # RETURN VALUE
+    /// Some((start byte x, end byte x, y)): match
+    /// None: no more match
+    pub fn find(&self, content: &[String], pos: (usize, usize)) -> Option<(usize, usize, usize)> {
+        let (mut x, mut y) = pos;
+        while y <= self.bottom {
+            if x <= content[y].len()
+                && let Some(m) = self.regex.find_at(&content[y], x) {
+                return Some((m.start(), m.end(), y));
+            }
+            x = 0;
+            y += 1;
+        }
+        None
+    }
+
+    /// # CONTENT
+    /// replace a match and find the next one
+    /// # ARGUMENT
+    /// - content: file content
+    /// - m: (start byte x, end byte x, y) of the match
+    /// # RETURN VALUE
+    /// Some((start byte x, end byte x, y)): next match
+    /// None: no more match
+    pub fn replace_next(&self, content: &mut [String], m: (usize, usize, usize)) -> Option<(usize, usize, usize)> {
+        let replaced = self.replace(content, m);
+        self.find(content, self.after(content, replaced))
+    }
+
+    /// # CONTENT
+    /// skip a match and find the next one
+    /// # ARGUMENT
+    /// - content: file content
+    /// - m: (start byte x, end byte x, y) of the match
+    /// # RETURN VALUE
+    /// Some((start byte x, end byte x, y)): next match
+    /// None: no more match
+    pub fn skip_next(&self, content: &[String], m: (usize, usize, usize)) -> Option<(usize, usize, usize)> {
+        self.find(content, self.after(content, m))
+    }
+
+    /// # CONTENT
+    /// get the pos searched after a handled match
+    /// # ARGUMENT
+    /// - content: file content
+    /// - m: (start byte x, end byte x, y) of the match (end after replacement)
+    /// # RETURN VALUE
+    /// (byte x, y)
+    fn after(&self, content: &[String], m: (usize, usize, usize)) -> (usize, usize) {
+        let (start, end, y) = m;
+        if !self.global {
+            (0, y + 1)
+        } else if end == start {
+            // step over an empty match
+            (end + content[y][end..].chars().next().map(|c| c.len_utf8()).unwrap_or(1), y)
+        } else {
+            (end, y)
+        }
+    }
+
+    /// # CONTENT
+    /// replace a match
+    /// # ARGUMENT
+    /// - content: file content
+    /// - m: (start byte x, end byte x, y) of the match
+    /// # RETURN VALUE
+    /// (start byte x, end byte x, y) of the replaced text
+    fn replace(&self, content: &mut [String], m: (usize, usize, usize)) -> (usize, usize, usize) {
+        let (start, end, y) = m;
+        let mut replaced = String::new();
+        if let Some(caps) = self.regex.captures_at(&content[y], start) {
+            caps.expand(&self.replacement, &mut replaced);
+        }
+        content[y].replace_range(start..end, &replaced);
+        (start, start + replaced.len(), y)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(rows: &[&str]) -> Vec<String> {
+        rows.iter().map(|row| row.to_string()).collect()
+    }
+
+    fn run(cmd: &str, center_y: usize, content: &[&str]) -> (usize, Vec<String>) {
+        let mut content = rows(content);
+        let count = Substitute::parse(cmd, center_y, content.len()).unwrap().run(&mut content);
+        (count, content)
+    }
+
+    #[test]
+    fn ranges() {
+        let content = ["a", "a", "a", "a"];
+        assert_eq!(run("s/a/b/", 1, &content).1, ["a", "b", "a", "a"]);
+        assert_eq!(run("%s/a/b/", 1, &content).1, ["b", "b", "b", "b"]);
+        assert_eq!(run("3s/a/b/", 0, &content).1, ["a", "a", "b", "a"]);
+        assert_eq!(run("2,3s/a/b/", 0, &content).1, ["a", "b", "b", "a"]);
+        assert_eq!(run(".,$s/a/b/", 2, &content).1, ["a", "a", "b", "b"]);
+        assert!(Substitute::parse("3,2s/a/b/", 0, 4).is_err());
+        assert!(Substitute::parse("5s/a/b/", 0, 4).is_err());
+        assert!(Substitute::parse("0s/a/b/", 0, 4).is_err());
+    }
+
+    #[test]
+    fn flags() {
+        assert_eq!(run("s/a/b/", 0, &["aAa"]), (1, rows(&["bAa"])));
+        assert_eq!(run("s/a/b/g", 0, &["aAa"]), (2, rows(&["bAb"])));
+        assert_eq!(run("s/a/b/gi", 0, &["aAa"]), (3, rows(&["bbb"])));
+        assert_eq!(run("s/a/b/gw", 0, &["a ab a"]), (2, rows(&["b ab b"])));
+        assert!(Substitute::parse("s/a/b/c", 0, 1).unwrap().confirm);
+        assert!(Substitute::parse("s/a/b/x", 0, 1).is_err());
+        assert!(Substitute::parse("s/a/b/g/", 0, 1).is_err());
+        assert!(Substitute::parse("s//b/", 0, 1).is_err());
+    }
+
+    #[test]
+    fn replacement_groups() {
+        assert_eq!(run(r"s/(\w+) (\w+)/\2 \1/", 0, &["ab cd"]).1, ["cd ab"]);
+        assert_eq!(run(r"s/(\w+) (\w+)/$2 $1/", 0, &["ab cd"]).1, ["cd ab"]);
+        assert_eq!(run(r"s/(a)/\1\1/g", 0, &["aba"]).1, ["aabaa"]);
+        assert_eq!(run(r"s/a/\\/", 0, &["a"]).1, ["\\"]);
+        assert_eq!(run(r"s#a/b#\##", 0, &["a/b"]).1, ["#"]);
+    }
+
+    #[test]
+    fn confirm_steps() {
+        let mut content = rows(&["aa", "a"]);
+        let sub = Substitute::parse("%s/a/b/gc", 0, content.len()).unwrap();
+        let m = sub.find(&content, (0, sub.top)).unwrap();
+        assert_eq!(m, (0, 1, 0));
+        let m = sub.skip_next(&content, m).unwrap();
+        assert_eq!(m, (1, 2, 0));
+        let m = sub.replace_next(&mut content, m).unwrap();
+        assert_eq!(m, (0, 1, 1));
+        assert_eq!(sub.replace_next(&mut content, m), None);
+        assert_eq!(content, ["ab", "b"]);
+    }
+}
@@ -0,0 +1,387 @@
+//! # CONTENT
+//! tree-sitter based syntax highlighting and structural navigation
+//! (no-op unless built with the `syntax` feature)
+
+#[cfg(feature = "syntax")]
+use {
+    std:: {
+        ops,
+    },
+    tree_sitter:: {
+        InputEdit,
+        Node,
+        Parser,
+        Point,
+        Query,
+        QueryCursor,
+        StreamingIterator,
+        Tree,
+    },
+};
+
+/// # CONTENT
+/// structural navigation and highlighting are compiled in
+pub const ENABLED: bool = cfg!(feature = "syntax");
+
+/// # CONTENT
+/// highlight kind of a char
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Kind {
+    Attribute,
+    Comment,
+    Constant,
+    Function,
+    Keyword,
+    Operator,
+    Property,
+    String,
+    Type,
+}
+
+impl Kind {
+    /// # CONTENT
+    /// get Kind from tree-sitter capture name
+    /// # ARGUMENT
+    /// - name: capture name (e.g. "function.method")
+    /// # RETURN VALUE
+    /// Some(Kind): highlighted capture
+    /// None: not highlighted capture
+    #[cfg_attr(not(feature = "syntax"), allow(dead_code))]
+    fn from_capture(name: &str) -> Option<Self> {
+        match name {
+            "text.literal" => return Some(Self::String),
+            "text.reference" | "text.uri" => return Some(Self::Function),
+            "text.title" => return Some(Self::Keyword),
+            _ => {}
+        }
+        match name.split('.').next().unwrap_or_default() {
+            "attribute" => Some(Self::Attribute),
+            "comment" => Some(Self::Comment),
+            "boolean" | "constant" | "number" => Some(Self::Constant),
+            "function" => Some(Self::Function),
+            "keyword" => Some(Self::Keyword),
+            "operator" => Some(Self::Operator),
+            "label" | "property" | "variable" => Some(Self::Property),
+            "escape" | "string" => Some(Self::String),
+            "constructor" | "type" => Some(Self::Type),
+            _ => None,
+        }
+    }
+}
+
+/// # CONTENT
+/// structural motion
+pub enum Motion {
+    Child,
+    NextSibling,
+    Parent,
+}
+
+/// # CONTENT
+/// tree-sitter language handle
+/// # FIELD
+/// - parser: parser set to the language
+/// - query: highlight query of the language
+#[cfg(feature = "syntax")]
+struct Language {
+    parser: Parser,
+    query: Query,
+}
+
+#[cfg(feature = "syntax")]
+impl Language {
+    /// # CONTENT
+    /// get Language from file extension
+    /// # ARGUMENT
+    /// - path: file path
+    /// # RETURN VALUE
+    /// Some(Language): supported language
+    /// None: unsupported language
+    fn new(path: &str) -> Option<Self> {
+        let (language, highlights): (tree_sitter::Language, &str) = match path.rsplit('.').next()? {
+            "json" => (tree_sitter_json::LANGUAGE.into(), tree_sitter_json::HIGHLIGHTS_QUERY),
+            "md" => (tree_sitter_md::LANGUAGE.into(), tree_sitter_md::HIGHLIGHT_QUERY_BLOCK),
+            "rs" => (tree_sitter_rust::LANGUAGE.into(), tree_sitter_rust::HIGHLIGHTS_QUERY),
+            "toml" => (tree_sitter_toml_ng::LANGUAGE.into(), tree_sitter_toml_ng::HIGHLIGHTS_QUERY),
+            _ => return None,
+        };
+        let mut parser = Parser::new();
+        parser.set_language(&language).ok()?;
+        let query = Query::new(&language, highlights).ok()?;
+        Some(Self { parser, query })
+    }
+}
+
+/// # CONTENT
+/// syntax info of a file
+/// # FIELD
+/// - highlight: highlight kind of each char (per row)
+/// - language: language of the file (None if unsupported)
+/// - tree: last parsed syntax tree
+/// - text: content of the last parse (rows joined by '\n')
+/// - node: byte range of the node selected by the last structural motion
+pub struct Syntax {
+    highlight: Vec<Vec<Option<Kind>>>,
+    #[cfg(feature = "syntax")]
+    language: Option<Language>,
+    #[cfg(feature = "syntax")]
+    tree: Option<Tree>,
+    #[cfg(feature = "syntax")]
+    text: String,
+    #[cfg(feature = "syntax")]
+    node: Option<ops::Range<usize>>,
+}
+
+/// # CONTENT
+/// get the row and byte col of a byte offset
+/// # ARGUMENT
+/// - text: rows joined by '\n'
+/// - byte: byte offset
+/// # RETURN VALUE
+/// tree-sitter point
+#[cfg(feature = "syntax")]
+fn point(text: &str, byte: usize) -> Point {
+    let before = &text[..byte];
+    Point {
+        row: before.matches('\n').count(),
+        column: byte - before.rfind('\n').map_or(0, |i| i + 1),
+    }
+}
+
+/// # CONTENT
+/// get the edit between two texts (the byte range between their common prefix and suffix)
+/// # ARGUMENT
+/// - old: parsed text
+/// - new: changed text
+/// # RETURN VALUE
+/// tree-sitter edit
+#[cfg(feature = "syntax")]
+fn input_edit(old: &str, new: &str) -> InputEdit {
+    let mut start = old.bytes().zip(new.bytes()).take_while(|(a, b)| a == b).count();
+    while !old.is_char_boundary(start) || !new.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut suffix = old[start..].bytes().rev().zip(new[start..].bytes().rev()).take_while(|(a, b)| a == b).count();
+    while !old.is_char_boundary(old.len() - suffix) || !new.is_char_boundary(new.len() - suffix) {
+        suffix -= 1;
+    }
+    let (old_end, new_end) = (old.len() - suffix, new.len() - suffix);
+    InputEdit {
+        start_byte: start,
+        old_end_byte: old_end,
+        new_end_byte: new_end,
+        start_position: point(old, start),
+        old_end_position: point(old, old_end),
+        new_end_position: point(new, new_end),
+    }
+}
+
+impl Syntax {
+    /// # CONTENT
+    /// init Syntax
+    /// # ARGUMENT
+    /// - _path: file path (language is chosen by its extension)
+    /// # RETURN VALUE
+    /// inited Syntax
+    pub fn new(_path: &Option<String>) -> Self {
+        Self {
+            highlight: Vec::new(),
+            #[cfg(feature = "syntax")]
+            language: _path.as_deref().and_then(Language::new),
+            #[cfg(feature = "syntax")]
+            tree: None,
+            #[cfg(feature = "syntax")]
+            text: String::new(),
+            #[cfg(feature = "syntax")]
+            node: None,
+        }
+    }
+
+    /// # CONTENT
+    /// get highlight kind of a char
+    /// # ARGUMENT
+    /// - x: char index in the row
+    /// - y: row index
+    /// # RETURN VALUE
+    /// Some(Kind): highlighted char
+    /// None: plain char
+    pub fn kind(&self, x: usize, y: usize) -> Option<Kind> {
+        self.highlight.get(y).and_then(|row| row.get(x).copied().flatten())
+    }
+
+    /// # CONTENT
+    /// reparse changed content and update highlight of the changed rows
+    /// (the old tree is edited by the differing byte range and reused by the parser)
+    /// # ARGUMENT
+    /// - _content: file content
+    /// # RETURN VALUE
+    /// none
+    pub fn update(&mut self, _content: &[String]) {
+        #[cfg(feature = "syntax")]
+        {
+            let Some(language) = self.language.as_mut() else {
+                return;
+            };
+            let text = _content.join("\n");
+            if self.tree.is_some() && text == self.text {
+                return;
+            }
+            let mut rows = 0.._content.len();
+            match self.tree.as_mut() {
+                Some(tree) => {
+                    let edit = input_edit(&self.text, &text);
+                    tree.edit(&edit);
+                    let (start, old_end, new_end) = (edit.start_position.row, edit.old_end_position.row, edit.new_end_position.row);
+                    self.highlight.splice(start..=old_end, vec![Vec::new(); new_end - start + 1]);
+                    rows = start..new_end + 1;
+                }
+                None => self.highlight = vec![Vec::new(); _content.len()],
+            }
+            let old_tree = self.tree.take();
+            self.tree = language.parser.parse(&text, old_tree.as_ref());
+            self.text = text;
+            let Some(tree) = self.tree.as_ref() else {
+                return;
+            };
+            if let Some(old_tree) = old_tree {
+                for range in old_tree.changed_ranges(tree) {
+                    rows.start = rows.start.min(range.start_point.row);
+                    rows.end = rows.end.max(range.end_point.row + 1);
+                }
+            }
+            rows.end = rows.end.min(_content.len());
+
+            // highlight per byte of the rows
+            let mut highlight: Vec<Vec<Option<Kind>>> = _content[rows.clone()].iter().map(|row| vec![None; row.len()]).collect();
+            let names = language.query.capture_names();
+            let mut query_cursor = QueryCursor::new();
+            query_cursor.set_point_range(Point { row: rows.start, column: 0 }..Point { row: rows.end, column: 0 });
+            let mut captures = query_cursor.captures(&language.query, tree.root_node(), self.text.as_bytes());
+            let mut last_id = None;
+            while let Some((query_match, index)) = captures.next() {
+                let capture = query_match.captures[*index];
+                if last_id == Some(capture.node.id()) {
+                    continue; // the first pattern wins for the same node
+                }
+                last_id = Some(capture.node.id());
+                let Some(kind) = Kind::from_capture(names[capture.index as usize]) else {
+                    continue;
+                };
+                let (start, end) = (capture.node.start_position(), capture.node.end_position());
+                for (y, row) in highlight.iter_mut().enumerate().map(|(i, row)| (rows.start + i, row)).filter(|&(y, _)| start.row <= y && y <= end.row) {
+                    let left = if y == start.row { start.column } else { 0 };
+                    let right = if y == end.row { end.column.min(row.len()) } else { row.len() };
+                    for cell in row.iter_mut().take(right).skip(left) {
+                        *cell = Some(kind);
+                    }
+                }
+            }
+
+            // byte -> char
+            let highlight: Vec<Vec<Option<Kind>>> = _content[rows.clone()].iter().zip(highlight).map(|(row, kinds)| {
+                row.char_indices().map(|(i, _)| kinds[i]).collect()
+            }).collect();
+            self.highlight.splice(rows, highlight);
+        }
+    }
+
+    /// # CONTENT
+    /// get center destination of a structural motion
+    /// # ARGUMENT
+    /// - _content: file content
+    /// - _x: center pos x (char index)
+    /// - _y: center pos y
+    /// - _motion: structural motion
+    /// # RETURN VALUE
+    /// Some((x, y)): new center pos
+    /// None: no node to move to
+    pub fn motion(&mut self, _content: &[String], _x: usize, _y: usize, _motion: Motion) -> Option<(usize, usize)> {
+        #[cfg(feature = "syntax")]
+        {
+            let tree = self.tree.as_ref()?;
+            let column = _content[_y].char_indices().nth(_x).map(|(i, _)| i).unwrap_or(_content[_y].len());
+            let point = Point { row: _y, column };
+
+            // recover the selected node if the center is still on it
+            let selected = self.node.clone().and_then(|range| {
+                tree.root_node().descendant_for_byte_range(range.start, range.end)
+            }).filter(|node| node.start_position() == point);
+            let node = match selected {
+                Some(n) => n,
+                None => tree.root_node().named_descendant_for_point_range(point, point)?,
+            };
+
+            let next: Option<Node> = match _motion {
+                Motion::Child => node.named_child(0),
+                Motion::NextSibling => {
+                    let mut n = node;
+                    loop {
+                        if let Some(s) = n.next_named_sibling() {
+                            break Some(s);
+                        }
+                        n = n.parent()?;
+                    }
+                }
+                Motion::Parent => {
+                    let mut n = node;
+                    loop {
+                        n = n.parent()?;
+                        if n.byte_range() != node.byte_range() {
+                            break Some(n);
+                        }
+                    }
+                }
+            };
+            let next = next?;
+            self.node = Some(next.byte_range());
+            let start = next.start_position();
+            let x = _content[start.row][..start.column.min(_content[start.row].len())].chars().count();
+            Some((x, start.row))
+        }
+        #[cfg(not(feature = "syntax"))]
+        None
+    }
+}
+
+#[cfg(all(test, feature = "syntax"))]
+mod tests {
+    use super::*;
+
+    fn rows(text: &str) -> Vec<String> {
+        text.split('\n').map(|row| row.to_string()).collect()
+    }
+
+    fn full(content: &[String]) -> Vec<Vec<Option<Kind>>> {
+        let mut syntax = Syntax::new(&Some("a.rs".to_string()));
+        syntax.update(content);
+        syntax.highlight
+    }
+
+    #[test]
+    fn input_edit_covers_the_differing_bytes() {
+        let edit = input_edit("ab\ncd\nef", "ab\nxyz\nef");
+        assert_eq!((edit.start_byte, edit.old_end_byte, edit.new_end_byte), (3, 5, 6));
+        assert_eq!((edit.start_position.row, edit.start_position.column), (1, 0));
+        assert_eq!((edit.old_end_position.row, edit.old_end_position.column), (1, 2));
+        assert_eq!((edit.new_end_position.row, edit.new_end_position.column), (1, 3));
+        let edit = input_edit("aé", "aè");
+        assert_eq!((edit.start_byte, edit.old_end_byte, edit.new_end_byte), (1, 3, 3));
+    }
+
+    #[test]
+    fn incremental_update_matches_a_full_parse() {
+        let mut syntax = Syntax::new(&Some("a.rs".to_string()));
+        let steps = [
+            "fn main() {\n    let a = 1;\n}",
+            "fn main() {\n    /* open\n    let a = 1;\n}",
+            "fn main() {\n    /* open */\n    let a = \"s\";\n}",
+            "fn main() {\n}",
+            "// only a comment",
+        ];
+        for text in steps {
+            let content = rows(text);
+            syntax.update(&content);
+            assert_eq!(syntax.highlight, full(&content), "{}", text);
+        }
+    }
+}
@@ -0,0 +1,280 @@
+//! # CONTENT
+//! visual selection and operators applied to it
+
+use {
+    std:: {
+        ops,
+    },
+    crate:: {
+        display,
+    },
+};
+
+/// # CONTENT
+/// selection kind
+/// - Block: display col range of each row
+/// - Char: chars from the start pos to the end pos
+/// - Line: whole rows
+#[derive(Clone, Copy, PartialEq)]
+pub enum Kind {
+    Block,
+    Char,
+    Line,
+}
+
+impl Kind {
+    /// # CONTENT
+    /// get kind name
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// kind name
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Block => "block",
+            Self::Char => "char",
+            Self::Line => "line",
+        }
+    }
+}
+
+/// # CONTENT
+/// selected range
+/// # FIELD
+/// - kind: selection kind
+/// - top: first selected row index
+/// - bottom: last selected row index
+/// - left: start char index (Char) or first display col (Block)
+/// - right: end char index (Char, inclusive) or last display col (Block)
+pub struct Selection {
+    pub kind: Kind,
+    pub top: usize,
+    pub bottom: usize,
+    pub left: usize,
+    pub right: usize,
+}
+
+impl Selection {
+    /// # CONTENT
+    /// init Selection between the anchor and the center
+    /// # ARGUMENT
+    /// - kind: selection kind
+    /// - anchor: (x, y) where the selection started
+    /// - center: (x, y) of center
+    /// - content: file content
+    /// - display_handle: display converting char index to display col
+    /// # RETURN VALUE
+    /// inited Selection
+    pub fn new(kind: Kind, anchor: (usize, usize), center: (usize, usize), content: &[String], display_handle: &display::Display) -> Self {
+        let (start, end) = if (anchor.1, anchor.0) <= (center.1, center.0) { (anchor, center) } else { (center, anchor) };
+        let (left, right) = match kind {
+            Kind::Block => {
+                let a = display_handle.visual_x(&content[anchor.1], anchor.0);
+                let c = display_handle.visual_x(&content[center.1], center.0);
+                (a.min(c), a.max(c))
+            }
+            Kind::Char => (start.0, end.0),
+            Kind::Line => (0, usize::MAX),
+        };
+        Self {
+            kind,
+            top: start.1,
+            bottom: end.1,
+            left,
+            right,
+        }
+    }
+
+    /// # CONTENT
+    /// get selected chars of a row
+    /// # ARGUMENT
+    /// - content: file content
+    /// - y: row index
+    /// - display_handle: display converting display col to char index
+    /// # RETURN VALUE
+    /// Some(range): selected char indexes (row length is the end of row)
+    /// None: row is not selected
+    pub fn range(&self, content: &[String], y: usize, display_handle: &display::Display) -> Option<ops::Range<usize>> {
+        if y < self.top || y > self.bottom {
+            return None;
+        }
+        let len = content[y].chars().count();
+        Some(match self.kind {
+            Kind::Block => {
+                let start = display_handle.char_x(&content[y], self.left);
+                let end = (display_handle.char_x(&content[y], self.right) + 1).min(len);
+                start..end.max(start)
+            }
+            Kind::Char => {
+                let start = if y == self.top { self.left } else { 0 };
+                let end = if y == self.bottom { self.right + 1 } else { len + 1 };
+                start..end.min(len + 1)
+            }
+            Kind::Line => 0..len + 1,
+        })
+    }
+
+    /// # CONTENT
+    /// get the first selected pos
+    /// # ARGUMENT
+    /// - content: file content
+    /// - display_handle: display converting display col to char index
+    /// # RETURN VALUE
+    /// (x, y)
+    pub fn start(&self, content: &[String], display_handle: &display::Display) -> (usize, usize) {
+        match self.kind {
+            Kind::Block => (display_handle.char_x(&content[self.top], self.left), self.top),
+            Kind::Char => (self.left, self.top),
+            Kind::Line => (0, self.top),
+        }
+    }
+
+    /// # CONTENT
+    /// remove the selected text
+    /// # ARGUMENT
+    /// - content: file content (keeps at least one row)
+    /// - display_handle: display converting display col to char index
+    /// # RETURN VALUE
+    /// removed rows
+    pub fn delete(&self, content: &mut Vec<String>, display_handle: &display::Display) -> Vec<String> {
+        match self.kind {
+            Kind::Block => (self.top..=self.bottom).map(|y| {
+                let range = self.range(content, y, display_handle).unwrap_or_default();
+                let mut chars: Vec<char> = content[y].chars().collect();
+                let removed = chars.drain(range).collect();
+                content[y] = chars.into_iter().collect();
+                removed
+            }).collect(),
+            Kind::Char => {
+                // a selection ending at the end of row also removes the newline
+                let len = content[self.bottom].chars().count();
+                let last = if self.right >= len && self.bottom + 1 < content.len() { self.bottom + 1 } else { self.bottom };
+                let chars: Vec<char> = content[self.top..=last].join("\n").chars().collect();
+                let offset: usize = content[self.top..self.bottom].iter().map(|row| row.chars().count() + 1).sum();
+                let end = (offset + self.right + 1).min(chars.len());
+                let start = self.left.min(end);
+                let removed: String = chars[start..end].iter().collect();
+                let rest: String = chars[..start].iter().chain(&chars[end..]).collect();
+                content.splice(self.top..=last, rest.split('\n').map(|row| row.to_string()));
+                removed.split('\n').map(|row| row.to_string()).collect()
+            }
+            Kind::Line => {
+                let removed = content.drain(self.top..=self.bottom).collect();
+                if content.is_empty() {
+                    content.push(String::new());
+                }
+                removed
+            }
+        }
+    }
+
+    /// # CONTENT
+    /// get the selected text
+    /// # ARGUMENT
+    /// - content: file content
+    /// - display_handle: display converting display col to char index
+    /// # RETURN VALUE
+    /// selected rows
+    pub fn text(&self, content: &[String], display_handle: &display::Display) -> Vec<String> {
+        let shifted = Self {
+            kind: self.kind,
+            top: 0,
+            bottom: self.bottom - self.top,
+            left: self.left,
+            right: self.right,
+        };
+        shifted.delete(&mut content[self.top..(self.bottom + 2).min(content.len())].to_vec(), display_handle)
+    }
+
+    /// # CONTENT
+    /// convert the selected chars
+    /// # ARGUMENT
+    /// - content: file content
+    /// - display_handle: display converting display col to char index
+    /// - f: char conversion
+    /// # RETURN VALUE
+    /// none
+    pub fn map(&self, content: &mut [String], display_handle: &display::Display, f: impl Fn(char) -> String) {
+        for y in self.top..=self.bottom {
+            let range = self.range(content, y, display_handle).unwrap_or_default();
+            content[y] = content[y].chars().enumerate().map(|(x, c)| if range.contains(&x) { f(c) } else { c.to_string() }).collect();
+        }
+    }
+
+    /// # CONTENT
+    /// shift the selected rows by an indent level
+    /// # ARGUMENT
+    /// - content: file content
+    /// - indent: inserted indentation (None to remove one level)
+    /// - tab_width: removed spaces of one level
+    /// # RETURN VALUE
+    /// none
+    pub fn shift(&self, content: &mut [String], indent: Option<&str>, tab_width: usize) {
+        for row in &mut content[self.top..=self.bottom] {
+            match indent {
+                Some(indent) if !row.is_empty() => row.insert_str(0, indent),
+                Some(_) => {}
+                None if row.starts_with('\t') => {
+                    row.remove(0);
+                }
+                None => {
+                    let spaces = row.len() - row.trim_start_matches(' ').len();
+                    row.drain(..spaces.min(tab_width));
+                }
+            }
+        }
+    }
+}
+
+/// # CONTENT
+/// insert rows removed or yanked from a selection
+/// # ARGUMENT
+/// - content: file content
+/// - pos: (x, y) of center
+/// - kind: selection kind of the rows
+/// - rows: inserted rows
+/// - after: true: after center (next char or row), false: before center
+/// - display_handle: display converting char index to display col
+/// # RETURN VALUE
+/// (x, y) of the first inserted char
+pub fn paste(content: &mut Vec<String>, pos: (usize, usize), kind: Kind, rows: &[String], after: bool, display_handle: &display::Display) -> (usize, usize) {
+    let (x, y) = pos;
+    let x = if after && kind != Kind::Line { (x + 1).min(content[y].chars().count()) } else { x };
+    match kind {
+        Kind::Block => {
+            let visual_x = display_handle.visual_x(&content[y], x);
+            for (i, piece) in rows.iter().enumerate() {
+                if y + i >= content.len() {
+                    content.push(String::new());
+                }
+                let row = &mut content[y + i];
+                let width = display_handle.visual_x(row, usize::MAX);
+                if width < visual_x {
+                    row.push_str(&" ".repeat(visual_x - width));
+                }
+                let char_x = display_handle.char_x(row, visual_x);
+                let byte_x = row.char_indices().nth(char_x).map(|(b, _)| b).unwrap_or(row.len());
+                row.insert_str(byte_x, piece);
+            }
+            (x, y)
+        }
+        Kind::Char => {
+            let byte_x = content[y].char_indices().nth(x).map(|(b, _)| b).unwrap_or(content[y].len());
+            let tail = content[y].split_off(byte_x);
+            let mut inserted: Vec<String> = rows.to_vec();
+            if let Some(first) = inserted.first_mut() {
+                first.insert_str(0, &content[y]);
+            }
+            if let Some(last) = inserted.last_mut() {
+                last.push_str(&tail);
+            }
+            content.splice(y..=y, inserted);
+            (x, y)
+        }
+        Kind::Line => {
+            let y = if after { y + 1 } else { y };
+            content.splice(y..y, rows.iter().cloned());
+            (0, y)
+        }
+    }
+}
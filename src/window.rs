@@ -0,0 +1,866 @@
+//! # CONTENT
+//! window panes splitting the display
+
+use {
+    crate:: {
+        display,
+        explorer,
+        file,
+        files,
+    },
+};
+
+/// # CONTENT
+/// display col count of the tree panel
+const TREE_COL: usize = 30;
+
+/// # CONTENT
+/// split direction
+/// - Horizontal: panes stacked top to bottom
+/// - Vertical: panes side by side
+#[derive(Clone, Copy, PartialEq)]
+pub enum Split {
+    Horizontal,
+    Vertical,
+}
+
+/// # CONTENT
+/// focus move direction
+#[derive(Clone, Copy)]
+pub enum Direction {
+    Down,
+    Left,
+    Right,
+    Up,
+}
+
+/// # CONTENT
+/// window cmd requested by Cmd and run by Windows::run
+/// - Close: close the focused pane
+/// - Focus: focus the pane next to the focused one
+/// - FocusNext: focus the next pane in layout order
+/// - Grow: change the focused pane size along a split by a delta
+/// - Resize: set the focused pane size along a split
+/// - Split: split the focused pane (the new pane shows the same buffer and gets focus)
+/// - Tab: switch to a tab by number (1 origin)
+/// - TabClose: close the current tab
+/// - TabNew: open a tab with a pane showing the focused buffer
+/// - TabNext: switch to the next tab
+/// - TabPrev: switch to the previous tab
+/// - Tree: open a tree panel of a root dir at the left of the tab (or close the open one)
+pub enum Request {
+    Close,
+    Focus(Direction),
+    FocusNext,
+    Grow(Split, isize),
+    Resize(Split, usize),
+    Split(Split),
+    Tab(usize),
+    TabClose,
+    TabNew,
+    TabNext,
+    TabPrev,
+    Tree(String),
+}
+
+/// # CONTENT
+/// display area of a pane
+/// # FIELD
+/// - left: first display col
+/// - top: first display row
+/// - col: display col count
+/// - row: display row count
+#[derive(Clone, Copy)]
+pub struct Rect {
+    pub left: usize,
+    pub top: usize,
+    pub col: usize,
+    pub row: usize,
+}
+
+/// # CONTENT
+/// printed pane of (area, buffer, center pos, focused)
+pub type View<'a> = (Rect, &'a file::File, (usize, usize), bool);
+
+/// # CONTENT
+/// layout tree
+/// - Pane: pane index
+/// - Split: direction, per-mille of the first part, first part, second part
+enum Node {
+    Pane(usize),
+    Split(Split, usize, Box<Node>, Box<Node>),
+}
+
+impl Node {
+    /// # CONTENT
+    /// check if a pane is in the tree
+    /// # ARGUMENT
+    /// - pane: pane index
+    /// # RETURN VALUE
+    /// true: pane is in the tree
+    fn contains(&self, pane: usize) -> bool {
+        match self {
+            Self::Pane(i) => *i == pane,
+            Self::Split(_, _, first, second) => first.contains(pane) || second.contains(pane),
+        }
+    }
+
+    /// # CONTENT
+    /// get the first pane of the tree
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// pane index
+    fn first(&self) -> usize {
+        match self {
+            Self::Pane(i) => *i,
+            Self::Split(_, _, first, _) => first.first(),
+        }
+    }
+
+    /// # CONTENT
+    /// split areas of the tree
+    /// # ARGUMENT
+    /// - area: area of the tree
+    /// - panes: (pane index, area) in layout order (pushed)
+    /// - separators: areas of vertical separators (pushed)
+    /// # RETURN VALUE
+    /// none
+    fn rects(&self, area: Rect, panes: &mut Vec<(usize, Rect)>, separators: &mut Vec<Rect>) {
+        match self {
+            Self::Pane(i) => panes.push((*i, area)),
+            Self::Split(split, ratio, first, second) => {
+                let (a, separator, b) = split_rect(area, *split, *ratio);
+                first.rects(a, panes, separators);
+                separators.extend(separator);
+                second.rects(b, panes, separators);
+            }
+        }
+    }
+
+    /// # CONTENT
+    /// set the size of a pane along the nearest split of a direction containing it
+    /// # ARGUMENT
+    /// - area: area of the tree
+    /// - pane: pane index
+    /// - split: split direction
+    /// - size: display rows (Horizontal) or cols (Vertical) of the pane side
+    /// # RETURN VALUE
+    /// true: resized
+    fn resize(&mut self, area: Rect, pane: usize, split: Split, size: usize) -> bool {
+        let Self::Split(node_split, ratio, first, second) = self else {
+            return false;
+        };
+        let (a, _, b) = split_rect(area, *node_split, *ratio);
+        if first.resize(a, pane, split, size) || second.resize(b, pane, split, size) {
+            return true;
+        }
+        if *node_split != split || !self.contains(pane) {
+            return false;
+        }
+        let Self::Split(_, ratio, first, _) = self else {
+            return false;
+        };
+        let total = match split {
+            Split::Horizontal => area.row,
+            Split::Vertical => area.col.saturating_sub(1),
+        }.max(1);
+        let first_size = if first.contains(pane) { size } else { total.saturating_sub(size) };
+        *ratio = (first_size.clamp(1, total) * 1000).div_ceil(total).clamp(1, 999);
+        true
+    }
+
+    /// # CONTENT
+    /// remove a pane (its sibling takes the area of their split)
+    /// # ARGUMENT
+    /// - pane: removed pane index (greater indexes are decremented)
+    /// # RETURN VALUE
+    /// Some(index): first pane of the sibling (before decrementing)
+    /// None: pane is not in the tree
+    fn remove(&mut self, pane: usize) -> Option<usize> {
+        let sibling = match self {
+            Self::Pane(_) => return None,
+            Self::Split(_, _, first, second) => match (first.as_ref(), second.as_ref()) {
+                (Self::Pane(i), _) if *i == pane => Some(std::mem::replace(second.as_mut(), Self::Pane(0))),
+                (_, Self::Pane(i)) if *i == pane => Some(std::mem::replace(first.as_mut(), Self::Pane(0))),
+                _ => None,
+            },
+        };
+        match sibling {
+            Some(sibling) => {
+                let focus = sibling.first();
+                *self = sibling;
+                Some(focus)
+            }
+            None => match self {
+                Self::Split(_, _, first, second) => first.remove(pane).or_else(|| second.remove(pane)),
+                Self::Pane(_) => None,
+            },
+        }
+    }
+
+    /// # CONTENT
+    /// decrement pane indexes after a removed pane
+    /// # ARGUMENT
+    /// - removed: removed pane index
+    /// # RETURN VALUE
+    /// none
+    fn renumber(&mut self, removed: usize) {
+        match self {
+            Self::Pane(i) if *i > removed => *i -= 1,
+            Self::Pane(_) => {}
+            Self::Split(_, _, first, second) => {
+                first.renumber(removed);
+                second.renumber(removed);
+            }
+        }
+    }
+
+    /// # CONTENT
+    /// replace a pane with a split of it and a new pane
+    /// # ARGUMENT
+    /// - pane: split pane index
+    /// - new: new pane index
+    /// - split: split direction
+    /// # RETURN VALUE
+    /// none
+    fn split(&mut self, pane: usize, new: usize, split: Split) {
+        match self {
+            Self::Pane(i) if *i == pane => *self = Self::Split(split, 500, Box::new(Self::Pane(pane)), Box::new(Self::Pane(new))),
+            Self::Pane(_) => {}
+            Self::Split(_, _, first, second) => {
+                first.split(pane, new, split);
+                second.split(pane, new, split);
+            }
+        }
+    }
+}
+
+/// # CONTENT
+/// split an area in two
+/// # ARGUMENT
+/// - area: split area
+/// - split: split direction
+/// - ratio: per-mille of the first part
+/// # RETURN VALUE
+/// (first part, separator between vertical parts, second part)
+fn split_rect(area: Rect, split: Split, ratio: usize) -> (Rect, Option<Rect>, Rect) {
+    match split {
+        Split::Horizontal => {
+            let row = (area.row * ratio / 1000).clamp(1, area.row.saturating_sub(1).max(1));
+            (
+                Rect { row, ..area },
+                None,
+                Rect { top: area.top + row, row: area.row.saturating_sub(row), ..area },
+            )
+        }
+        Split::Vertical => {
+            let width = area.col.saturating_sub(1);
+            let col = (width * ratio / 1000).clamp(1, width.saturating_sub(1).max(1));
+            (
+                Rect { col, ..area },
+                Some(Rect { left: area.left + col, col: 1, ..area }),
+                Rect { left: area.left + col + 1, col: width.saturating_sub(col), ..area },
+            )
+        }
+    }
+}
+
+/// # CONTENT
+/// pane
+/// # FIELD
+/// - file: index of the shown buffer
+/// - center: center pos (x, y) while the pane is not focused
+struct Pane {
+    file: usize,
+    center: (usize, usize),
+}
+
+/// # CONTENT
+/// tab page
+/// # FIELD
+/// - panes: panes (index is the pane index of the layout)
+/// - layout: layout tree
+/// - focus: focused pane index
+struct Tab {
+    panes: Vec<Pane>,
+    layout: Node,
+    focus: usize,
+}
+
+impl Tab {
+    /// # CONTENT
+    /// init Tab with a pane
+    /// # ARGUMENT
+    /// - pane: the pane
+    /// # RETURN VALUE
+    /// inited Tab
+    fn new(pane: Pane) -> Self {
+        Self {
+            panes: vec![pane],
+            layout: Node::Pane(0),
+            focus: 0,
+        }
+    }
+}
+
+/// # CONTENT
+/// tab pages of window panes and the buffers they show
+/// # FIELD
+/// - files: open buffers
+/// - tabs: tab pages
+/// - tab: current tab index (the center of its focused pane is Display::center_x/center_y)
+pub struct Windows {
+    files: files::Files,
+    tabs: Vec<Tab>,
+    tab: usize,
+}
+
+impl Windows {
+    /// # CONTENT
+    /// init Windows with a tab of a pane showing the current buffer
+    /// # ARGUMENT
+    /// - files: open buffers
+    /// # RETURN VALUE
+    /// inited Windows
+    pub fn new(files: files::Files) -> Self {
+        Self {
+            tabs: vec![Tab::new(Pane { file: files.index(), center: (0, 0) })],
+            files,
+            tab: 0,
+        }
+    }
+
+    /// # CONTENT
+    /// get the open buffers
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// open buffers
+    pub fn files(&self) -> &files::Files {
+        &self.files
+    }
+
+    /// # CONTENT
+    /// get the open buffers to edit (buffers are only added, panes keep their indexes)
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// open buffers
+    pub fn files_mut(&mut self) -> &mut files::Files {
+        &mut self.files
+    }
+
+    /// # CONTENT
+    /// get the buffer of the focused pane to edit
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// focused buffer
+    pub fn file_mut(&mut self) -> &mut file::File {
+        self.files.current_mut()
+    }
+
+    /// # CONTENT
+    /// get the pane area of the display
+    /// # ARGUMENT
+    /// - display_handle: display (the last row is the cmd row, the first one is the tab line if several tabs)
+    /// # RETURN VALUE
+    /// area of the panes
+    fn area(&self, display_handle: &display::Display) -> Rect {
+        let top = if self.tabs.len() > 1 { 1 } else { 0 };
+        Rect { left: 0, top, col: display_handle.term_col, row: display_handle.term_row.saturating_sub(1 + top) }
+    }
+
+    /// # CONTENT
+    /// get the pane areas of the current tab
+    /// # ARGUMENT
+    /// - display_handle: display
+    /// # RETURN VALUE
+    /// ((pane index, area) in layout order, vertical separator areas)
+    fn rects(&self, display_handle: &display::Display) -> (Vec<(usize, Rect)>, Vec<Rect>) {
+        let (mut panes, mut separators) = (Vec::new(), Vec::new());
+        self.tabs[self.tab].layout.rects(self.area(display_handle), &mut panes, &mut separators);
+        (panes, separators)
+    }
+
+    /// # CONTENT
+    /// get printed panes of the current tab
+    /// # ARGUMENT
+    /// - display_handle: display (its center is the center of the focused pane)
+    /// # RETURN VALUE
+    /// (panes, vertical separator areas)
+    pub fn views(&self, display_handle: &display::Display) -> (Vec<View<'_>>, Vec<Rect>) {
+        let tab = &self.tabs[self.tab];
+        let (panes, separators) = self.rects(display_handle);
+        let views = panes.into_iter().map(|(i, rect)| {
+            let focused = i == tab.focus;
+            let center = if focused { (display_handle.center_x, display_handle.center_y) } else { tab.panes[i].center };
+            (rect, self.files.get(tab.panes[i].file), center, focused)
+        }).collect();
+        (views, separators)
+    }
+
+    /// # CONTENT
+    /// get tab line labels
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// (label of "number file name (pane count)", current) of tabs (empty if only one tab)
+    pub fn tabs(&self) -> Vec<(String, bool)> {
+        if self.tabs.len() == 1 {
+            return Vec::new();
+        }
+        self.tabs.iter().enumerate().map(|(i, tab)| {
+            let path = self.files.get(tab.panes[tab.focus].file).path.clone();
+            let name = path.as_deref().map(|path| path.rsplit('/').next().unwrap_or(path)).unwrap_or("[no name]");
+            let label = if tab.panes.len() > 1 { format!("{} {} ({})", i + 1, name, tab.panes.len()) } else { format!("{} {}", i + 1, name) };
+            (label, i == self.tab)
+        }).collect()
+    }
+
+    /// # CONTENT
+    /// set the display area to the focused pane
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// none
+    pub fn fit(&self, display_handle: &mut display::Display) {
+        let focus = self.tabs[self.tab].focus;
+        if let Some((_, rect)) = self.rects(display_handle).0.into_iter().find(|(i, _)| *i == focus) {
+            display_handle.set_area(rect);
+        }
+    }
+
+    /// # CONTENT
+    /// remember the center of the focused pane
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// none
+    fn save(&mut self, display_handle: &display::Display) {
+        let tab = &mut self.tabs[self.tab];
+        tab.panes[tab.focus].center = (display_handle.center_x, display_handle.center_y);
+    }
+
+    /// # CONTENT
+    /// show the buffer and center of the focused pane
+    /// # ARGUMENT
+    /// none
+    /// # RETURN VALUE
+    /// none
+    fn load(&mut self, display_handle: &mut display::Display) {
+        let tab = &self.tabs[self.tab];
+        let pane = &tab.panes[tab.focus];
+        self.files.select(pane.file);
+        let content = &self.files.current().content;
+        display_handle.center_y = pane.center.1.min(content.len() - 1);
+        display_handle.center_x = pane.center.0.min(content[display_handle.center_y].chars().count());
+    }
+
+    /// # CONTENT
+    /// focus a pane of the current tab
+    /// # ARGUMENT
+    /// - pane: focused pane index
+    /// - save: remember the center of the focused pane (false if it is closed)
+    /// # RETURN VALUE
+    /// none
+    fn focus(&mut self, pane: usize, save: bool, display_handle: &mut display::Display) {
+        if save {
+            self.save(display_handle);
+        }
+        self.tabs[self.tab].focus = pane;
+        self.load(display_handle);
+    }
+
+    /// # CONTENT
+    /// switch to a tab
+    /// # ARGUMENT
+    /// - index: tab index
+    /// - save: remember the center of the focused pane (false if its tab is closed)
+    /// # RETURN VALUE
+    /// none
+    fn switch(&mut self, index: usize, save: bool, display_handle: &mut display::Display) {
+        if save {
+            self.save(display_handle);
+        }
+        self.tab = index;
+        self.load(display_handle);
+    }
+
+    /// # CONTENT
+    /// run a window or tab cmd
+    /// # ARGUMENT
+    /// - request: window or tab cmd
+    /// # RETURN VALUE
+    /// message of the result
+    pub fn run(&mut self, request: Request, display_handle: &mut display::Display) -> String {
+        let (rects, _) = self.rects(display_handle);
+        let focus = self.tabs[self.tab].focus;
+        let Some(&(_, rect)) = rects.iter().find(|(i, _)| *i == focus) else {
+            return String::new();
+        };
+        let len = self.tabs.len();
+        let tab_cmd = matches!(request, Request::Tab(_) | Request::TabClose | Request::TabNew | Request::TabNext | Request::TabPrev);
+        match request {
+            Request::Close if self.tabs[self.tab].panes.len() == 1 => return "close: last window".to_string(),
+            Request::Close => {
+                let tab = &mut self.tabs[self.tab];
+                let next = tab.layout.remove(focus).unwrap_or(0);
+                tab.layout.renumber(focus);
+                tab.panes.remove(focus);
+                self.focus(if next > focus { next - 1 } else { next }, false, display_handle);
+            }
+            Request::Focus(direction) => {
+                // nearest pane beyond the focused side overlapping it
+                let overlap = |a: (usize, usize), b: (usize, usize)| a.0 < b.0 + b.1 && b.0 < a.0 + a.1;
+                let found = rects.iter().filter_map(|&(i, r)| {
+                    let distance = match direction {
+                        Direction::Down if r.top >= rect.top + rect.row && overlap((r.left, r.col), (rect.left, rect.col)) => r.top - rect.top,
+                        Direction::Up if r.top + r.row <= rect.top && overlap((r.left, r.col), (rect.left, rect.col)) => rect.top - r.top,
+                        Direction::Right if r.left >= rect.left + rect.col && overlap((r.top, r.row), (rect.top, rect.row)) => r.left - rect.left,
+                        Direction::Left if r.left + r.col <= rect.left && overlap((r.top, r.row), (rect.top, rect.row)) => rect.left - r.left,
+                        _ => return None,
+                    };
+                    Some((distance, r.top.abs_diff(rect.top) + r.left.abs_diff(rect.left), i))
+                }).min();
+                match found {
+                    Some((_, _, i)) => self.focus(i, true, display_handle),
+                    None => return "no window in the direction".to_string(),
+                }
+            }
+            Request::FocusNext => {
+                let k = rects.iter().position(|(i, _)| *i == focus).unwrap_or(0);
+                self.focus(rects[(k + 1) % rects.len()].0, true, display_handle);
+            }
+            Request::Grow(split, delta) => {
+                let size = match split {
+                    Split::Horizontal => rect.row,
+                    Split::Vertical => rect.col,
+                };
+                return self.run(Request::Resize(split, size.saturating_add_signed(delta)), display_handle);
+            }
+            Request::Resize(split, size) => {
+                let area = self.area(display_handle);
+                if !self.tabs[self.tab].layout.resize(area, focus, split, size) {
+                    return "resize: no split in the direction".to_string();
+                }
+            }
+            Request::Split(split) => {
+                let tab = &mut self.tabs[self.tab];
+                let new = tab.panes.len();
+                tab.panes.push(Pane { file: tab.panes[focus].file, center: (display_handle.center_x, display_handle.center_y) });
+                tab.layout.split(focus, new, split);
+                self.focus(new, true, display_handle);
+            }
+            Request::Tab(n) if n == 0 || n > len => return format!("tab: no tab {}", n),
+            Request::Tab(n) => self.switch(n - 1, true, display_handle),
+            Request::TabClose if len == 1 => return "tab_close: last tab".to_string(),
+            Request::TabClose => {
+                let closed = self.tab;
+                self.tabs.remove(closed);
+                self.switch(closed.min(len - 2), false, display_handle);
+            }
+            Request::TabNew => {
+                let pane = Pane { file: self.files.index(), center: (display_handle.center_x, display_handle.center_y) };
+                self.tabs.insert(self.tab + 1, Tab::new(pane));
+                self.switch(self.tab + 1, true, display_handle);
+            }
+            Request::TabNext => self.switch((self.tab + 1) % len, true, display_handle),
+            Request::TabPrev => self.switch((self.tab + len - 1) % len, true, display_handle),
+            Request::Tree(root) => {
+                let is_tree = |file_handle: &file::File| matches!(file_handle.explorer.as_ref().map(|e| &e.kind), Some(explorer::Kind::Tree(_, _)));
+                let tab = &self.tabs[self.tab];
+                match (0..tab.panes.len()).find(|&i| is_tree(self.files.get(tab.panes[i].file))) {
+                    Some(_) if tab.panes.len() == 1 => return "tree: last window".to_string(),
+                    Some(i) => {
+                        self.focus(i, true, display_handle);
+                        return self.run(Request::Close, display_handle);
+                    }
+                    None => {
+                        let file = match self.files.position(is_tree) {
+                            Some(file) => file,
+                            None => match file::File::explorer(explorer::Kind::Tree(root.clone(), Vec::new()), format!("[tree] {}", root)) {
+                                Ok(file_handle) => self.files.add(file_handle),
+                                Err(e) => return format!("tree: {}", e),
+                            },
+                        };
+                        let ratio = (TREE_COL * 1000).div_ceil(self.area(display_handle).col.max(1)).clamp(1, 999);
+                        let tab = &mut self.tabs[self.tab];
+                        let new = tab.panes.len();
+                        tab.panes.push(Pane { file, center: (0, 0) });
+                        let layout = std::mem::replace(&mut tab.layout, Node::Pane(new));
+                        tab.layout = Node::Split(Split::Vertical, ratio, Box::new(Node::Pane(new)), Box::new(layout));
+                        self.focus(new, true, display_handle);
+                    }
+                }
+            }
+        }
+        self.fit(display_handle);
+        let tab = &self.tabs[self.tab];
+        if tab_cmd {
+            format!("tab {} of {}", self.tab + 1, self.tabs.len())
+        } else {
+            format!("window {} of {}", tab.focus + 1, tab.panes.len())
+        }
+    }
+
+    /// # CONTENT
+    /// run a buffer cmd in the focused pane
+    /// # ARGUMENT
+    /// - request: buffer cmd
+    /// # RETURN VALUE
+    /// message of the result
+    pub fn run_files(&mut self, request: files::Request, display_handle: &mut display::Display) -> String {
+        let (closed, len) = (self.files.index(), self.files.len());
+        let message = self.files.run(request, display_handle);
+        if self.files.len() < len {
+            // panes of the closed buffer show the buffer shown instead
+            for pane in self.tabs.iter_mut().flat_map(|tab| tab.panes.iter_mut()) {
+                if pane.file == closed {
+                    pane.file = self.files.index();
+                    pane.center = (0, 0);
+                } else if pane.file > closed {
+                    pane.file -= 1;
+                }
+            }
+        }
+        let tab = &mut self.tabs[self.tab];
+        tab.panes[tab.focus].file = self.files.index();
+        message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shape(node: &Node) -> String {
+        match node {
+            Node::Pane(i) => i.to_string(),
+            Node::Split(split, ratio, first, second) => {
+                let name = if *split == Split::Horizontal { "H" } else { "V" };
+                format!("{}{}({},{})", name, ratio, shape(first), shape(second))
+            }
+        }
+    }
+
+    fn area() -> Rect {
+        Rect { left: 0, top: 0, col: 81, row: 24 }
+    }
+
+    /// Windows of an unnamed buffer and buffers "[1]".."[count - 1]" on a 81x25 display
+    fn windows(count: usize) -> (Windows, display::Display) {
+        let mut files_handle = files::Files::open(&[]).unwrap();
+        for i in 1..count {
+            let mut file_handle = file::File::new(Some(format!("[{}]", i)));
+            file_handle.content = vec![String::new()];
+            files_handle.add(file_handle);
+        }
+        let mut display_handle = display::Display::new();
+        display_handle.term_col = 81;
+        display_handle.term_row = 25;
+        (Windows::new(files_handle), display_handle)
+    }
+
+    fn layout(windows_handle: &Windows) -> String {
+        shape(&windows_handle.tabs[windows_handle.tab].layout)
+    }
+
+    fn focus(windows_handle: &Windows) -> usize {
+        windows_handle.tabs[windows_handle.tab].focus
+    }
+
+    fn focused_rect(windows_handle: &Windows, display_handle: &display::Display) -> Rect {
+        let (views, _) = windows_handle.views(display_handle);
+        views.into_iter().find(|view| view.3).unwrap().0
+    }
+
+    #[test]
+    fn node_split_and_remove() {
+        let mut node = Node::Pane(0);
+        node.split(0, 1, Split::Vertical);
+        node.split(1, 2, Split::Horizontal);
+        assert_eq!(shape(&node), "V500(0,H500(1,2))");
+        assert!(node.contains(2) && !node.contains(3));
+        assert_eq!(node.remove(3), None);
+        assert_eq!(Node::Pane(0).remove(0), None);
+
+        let mut first = Node::Split(Split::Vertical, 500, Box::new(Node::Pane(0)), Box::new(Node::Split(Split::Horizontal, 500, Box::new(Node::Pane(1)), Box::new(Node::Pane(2)))));
+        assert_eq!(first.remove(0), Some(1));
+        first.renumber(0);
+        assert_eq!(shape(&first), "H500(0,1)");
+
+        assert_eq!(node.remove(2), Some(1));
+        node.renumber(2);
+        assert_eq!(shape(&node), "V500(0,1)");
+        assert_eq!(node.remove(1), Some(0));
+        assert_eq!(shape(&node), "0");
+    }
+
+    #[test]
+    fn node_resize_ratio() {
+        let mut node = Node::Split(Split::Vertical, 500, Box::new(Node::Pane(0)), Box::new(Node::Pane(1)));
+        assert!(node.resize(area(), 0, Split::Vertical, 20));
+        assert_eq!(shape(&node), "V250(0,1)");
+        assert!(node.resize(area(), 1, Split::Vertical, 20));
+        assert_eq!(shape(&node), "V750(0,1)");
+        assert!(node.resize(area(), 0, Split::Vertical, 0));
+        assert_eq!(shape(&node), "V13(0,1)");
+        assert!(node.resize(area(), 0, Split::Vertical, 1000));
+        assert_eq!(shape(&node), "V999(0,1)");
+        assert!(!node.resize(area(), 0, Split::Horizontal, 5));
+        assert!(!Node::Pane(0).resize(area(), 0, Split::Vertical, 5));
+
+        // the nearest split of the direction is resized
+        let mut nested = Node::Split(Split::Horizontal, 500, Box::new(Node::Pane(0)), Box::new(Node::Split(Split::Horizontal, 500, Box::new(Node::Pane(1)), Box::new(Node::Pane(2)))));
+        assert!(nested.resize(area(), 2, Split::Horizontal, 3));
+        assert_eq!(shape(&nested), "H500(0,H750(1,2))");
+    }
+
+    #[test]
+    fn split_rect_clamped() {
+        let (a, separator, b) = split_rect(area(), Split::Vertical, 500);
+        assert_eq!((a.left, a.col, b.left, b.col), (0, 40, 41, 40));
+        assert_eq!(separator.map(|s| (s.left, s.col, s.row)), Some((40, 1, 24)));
+        let (a, _, b) = split_rect(area(), Split::Vertical, 999);
+        assert_eq!((a.col, b.col), (79, 1));
+        let (a, _, b) = split_rect(area(), Split::Vertical, 1);
+        assert_eq!((a.col, b.col), (1, 79));
+        let (a, separator, b) = split_rect(area(), Split::Horizontal, 250);
+        assert!(separator.is_none());
+        assert_eq!((a.top, a.row, b.top, b.row), (0, 6, 6, 18));
+        let (a, _, b) = split_rect(Rect { row: 1, ..area() }, Split::Horizontal, 500);
+        assert_eq!((a.row, b.row), (1, 0));
+        let (a, _, b) = split_rect(Rect { col: 2, ..area() }, Split::Vertical, 500);
+        assert_eq!((a.col, b.col), (1, 0));
+    }
+
+    #[test]
+    fn split_then_close() {
+        let (mut windows_handle, mut display_handle) = windows(1);
+        assert_eq!(windows_handle.run(Request::Close, &mut display_handle), "close: last window");
+        assert_eq!(windows_handle.run(Request::Split(Split::Vertical), &mut display_handle), "window 2 of 2");
+        assert_eq!((layout(&windows_handle), focus(&windows_handle)), ("V500(0,1)".to_string(), 1));
+        assert_eq!(focused_rect(&windows_handle, &display_handle).left, 41);
+        assert_eq!(windows_handle.run(Request::Close, &mut display_handle), "window 1 of 1");
+        assert_eq!((layout(&windows_handle), focus(&windows_handle)), ("0".to_string(), 0));
+
+        // closing the first pane renumbers the second one
+        windows_handle.run(Request::Split(Split::Horizontal), &mut display_handle);
+        windows_handle.run(Request::FocusNext, &mut display_handle);
+        assert_eq!(focus(&windows_handle), 0);
+        windows_handle.run(Request::Close, &mut display_handle);
+        assert_eq!((layout(&windows_handle), focus(&windows_handle)), ("0".to_string(), 0));
+        assert_eq!(focused_rect(&windows_handle, &display_handle).row, 24);
+    }
+
+    #[test]
+    fn nested_splits_close() {
+        let (mut windows_handle, mut display_handle) = windows(1);
+        windows_handle.run(Request::Split(Split::Vertical), &mut display_handle);
+        windows_handle.run(Request::Split(Split::Horizontal), &mut display_handle);
+        windows_handle.run(Request::Split(Split::Vertical), &mut display_handle);
+        assert_eq!(layout(&windows_handle), "V500(0,H500(1,V500(2,3)))");
+        windows_handle.run(Request::Focus(Direction::Up), &mut display_handle);
+        assert_eq!(focus(&windows_handle), 1);
+        windows_handle.run(Request::Close, &mut display_handle);
+        assert_eq!((layout(&windows_handle), focus(&windows_handle)), ("V500(0,V500(1,2))".to_string(), 1));
+        assert_eq!(windows_handle.tabs[0].panes.len(), 3);
+    }
+
+    #[test]
+    fn focus_directions() {
+        let (mut windows_handle, mut display_handle) = windows(1);
+        windows_handle.run(Request::Split(Split::Vertical), &mut display_handle);
+        windows_handle.run(Request::Split(Split::Horizontal), &mut display_handle);
+        assert_eq!(focus(&windows_handle), 2);
+        assert_eq!(windows_handle.run(Request::Focus(Direction::Down), &mut display_handle), "no window in the direction");
+        windows_handle.run(Request::Focus(Direction::Up), &mut display_handle);
+        assert_eq!(focus(&windows_handle), 1);
+        windows_handle.run(Request::Focus(Direction::Left), &mut display_handle);
+        assert_eq!(focus(&windows_handle), 0);
+        assert_eq!(windows_handle.run(Request::Focus(Direction::Left), &mut display_handle), "no window in the direction");
+        windows_handle.run(Request::Focus(Direction::Right), &mut display_handle);
+        assert_eq!(focus(&windows_handle), 1);
+        windows_handle.run(Request::Focus(Direction::Down), &mut display_handle);
+        assert_eq!(focus(&windows_handle), 2);
+    }
+
+    #[test]
+    fn resize_clamped() {
+        let (mut windows_handle, mut display_handle) = windows(1);
+        windows_handle.run(Request::Split(Split::Vertical), &mut display_handle);
+        windows_handle.run(Request::Resize(Split::Vertical, 0), &mut display_handle);
+        assert_eq!(layout(&windows_handle), "V999(0,1)");
+        assert_eq!(focused_rect(&windows_handle, &display_handle).col, 1);
+        windows_handle.run(Request::Resize(Split::Vertical, 1000), &mut display_handle);
+        assert_eq!(layout(&windows_handle), "V13(0,1)");
+        assert_eq!(focused_rect(&windows_handle, &display_handle).col, 79);
+        windows_handle.run(Request::Grow(Split::Vertical, -39), &mut display_handle);
+        assert_eq!(focused_rect(&windows_handle, &display_handle).col, 40);
+        assert_eq!(windows_handle.run(Request::Resize(Split::Horizontal, 5), &mut display_handle), "resize: no split in the direction");
+    }
+
+    #[test]
+    fn close_buffer_shown_in_panes() {
+        let (mut windows_handle, mut display_handle) = windows(3);
+        windows_handle.run_files(files::Request::Name("2".to_string()), &mut display_handle);
+        windows_handle.run(Request::Split(Split::Vertical), &mut display_handle);
+        windows_handle.run_files(files::Request::Name("3".to_string()), &mut display_handle);
+        windows_handle.run(Request::Split(Split::Horizontal), &mut display_handle);
+        windows_handle.run_files(files::Request::Name("2".to_string()), &mut display_handle);
+        windows_handle.run(Request::TabNew, &mut display_handle);
+        windows_handle.run_files(files::Request::Name("1".to_string()), &mut display_handle);
+        windows_handle.run(Request::TabPrev, &mut display_handle);
+        let shown = |windows_handle: &Windows, tab: usize| -> Vec<Option<String>> {
+            windows_handle.tabs[tab].panes.iter().map(|pane| windows_handle.files.get(pane.file).path.clone()).collect()
+        };
+        assert_eq!(shown(&windows_handle, 0), [Some("[1]".to_string()), Some("[2]".to_string()), Some("[1]".to_string())]);
+        windows_handle.run_files(files::Request::Close, &mut display_handle);
+        assert_eq!(windows_handle.files.len(), 2);
+        assert_eq!(shown(&windows_handle, 0), [Some("[2]".to_string()), Some("[2]".to_string()), Some("[2]".to_string())]);
+        assert_eq!(shown(&windows_handle, 1), [None]);
+        windows_handle.run(Request::TabNext, &mut display_handle);
+        assert_eq!(windows_handle.files.current().path, None);
+    }
+
+    #[test]
+    fn tab_close_middle() {
+        let (mut windows_handle, mut display_handle) = windows(1);
+        assert_eq!(windows_handle.run(Request::TabClose, &mut display_handle), "tab_close: last tab");
+        assert!(windows_handle.tabs().is_empty());
+        windows_handle.run(Request::TabNew, &mut display_handle);
+        windows_handle.run(Request::Split(Split::Vertical), &mut display_handle);
+        windows_handle.run(Request::TabNew, &mut display_handle);
+        windows_handle.run(Request::Split(Split::Vertical), &mut display_handle);
+        windows_handle.run(Request::Split(Split::Vertical), &mut display_handle);
+        assert_eq!(windows_handle.run(Request::Tab(4), &mut display_handle), "tab: no tab 4");
+        assert_eq!(windows_handle.run(Request::Tab(0), &mut display_handle), "tab: no tab 0");
+        assert_eq!(windows_handle.run(Request::Tab(2), &mut display_handle), "tab 2 of 3");
+        assert_eq!(windows_handle.run(Request::TabClose, &mut display_handle), "tab 2 of 2");
+        let labels = [("1 [no name]".to_string(), false), ("2 [no name] (3)".to_string(), true)];
+        assert_eq!(windows_handle.tabs(), labels);
+        // the tab line takes the first row
+        assert_eq!(focused_rect(&windows_handle, &display_handle).top, 1);
+
+        assert_eq!(windows_handle.run(Request::TabNext, &mut display_handle), "tab 1 of 2");
+        assert_eq!(windows_handle.run(Request::TabPrev, &mut display_handle), "tab 2 of 2");
+        assert_eq!(windows_handle.run(Request::TabClose, &mut display_handle), "tab 1 of 1");
+        assert_eq!(layout(&windows_handle), "0");
+        assert_eq!(focused_rect(&windows_handle, &display_handle).top, 0);
+    }
+
+    #[test]
+    fn tab_keeps_center() {
+        let (mut windows_handle, mut display_handle) = windows(1);
+        windows_handle.file_mut().content = vec![String::new(), "abc".to_string()];
+        (display_handle.center_x, display_handle.center_y) = (2, 1);
+        windows_handle.run(Request::TabNew, &mut display_handle);
+        (display_handle.center_x, display_handle.center_y) = (0, 0);
+        windows_handle.run(Request::TabPrev, &mut display_handle);
+        assert_eq!((display_handle.center_x, display_handle.center_y), (2, 1));
+        windows_handle.run(Request::TabNext, &mut display_handle);
+        assert_eq!((display_handle.center_x, display_handle.center_y), (0, 0));
+    }
+}